@@ -106,7 +106,10 @@ impl SnapshotCreator {
 
         let latency =
             METRICS.storage_logs_processing_duration[&StorageChunkStage::SaveToGcs].start();
-        let storage_logs_chunk = SnapshotStorageLogsChunk { storage_logs: logs };
+        let storage_logs_chunk = SnapshotStorageLogsChunk {
+            storage_logs: logs,
+            proofs: vec![],
+        };
         let key = SnapshotStorageLogsStorageKey {
             l1_batch_number,
             chunk_id,
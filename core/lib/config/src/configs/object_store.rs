@@ -16,4 +16,8 @@ pub struct ObjectStoreConfig {
     pub file_backed_base_path: String,
     pub gcs_credential_file_path: String,
     pub max_retries: u16,
+    /// Maximum number of files concurrently open by the file-backed object store. Operations exceeding the limit
+    /// wait until other operations complete, instead of failing with "too many open files" errors.
+    /// If not set, the number of open files is not limited.
+    pub file_backed_max_open_files: Option<usize>,
 }
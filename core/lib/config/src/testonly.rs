@@ -652,6 +652,7 @@ impl RandomConfig for configs::ObjectStoreConfig {
             file_backed_base_path: g.gen(),
             gcs_credential_file_path: g.gen(),
             max_retries: g.gen(),
+            file_backed_max_open_files: g.gen(),
         }
     }
 }
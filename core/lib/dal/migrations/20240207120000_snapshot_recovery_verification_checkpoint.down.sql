@@ -0,0 +1,2 @@
+ALTER TABLE snapshot_recovery DROP COLUMN storage_logs_chunks_verified;
+ALTER TABLE snapshot_recovery DROP COLUMN verified_storage_logs_count;
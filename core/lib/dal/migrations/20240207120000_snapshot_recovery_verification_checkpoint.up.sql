@@ -0,0 +1,2 @@
+ALTER TABLE snapshot_recovery ADD COLUMN storage_logs_chunks_verified BOOL[];
+ALTER TABLE snapshot_recovery ADD COLUMN verified_storage_logs_count BIGINT;
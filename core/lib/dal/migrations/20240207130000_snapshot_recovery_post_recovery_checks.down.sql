@@ -0,0 +1 @@
+ALTER TABLE snapshot_recovery DROP COLUMN post_recovery_checks_passed;
@@ -0,0 +1 @@
+ALTER TABLE snapshot_recovery ADD COLUMN post_recovery_checks_passed BOOL NOT NULL DEFAULT FALSE;
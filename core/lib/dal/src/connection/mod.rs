@@ -16,9 +16,9 @@ use sqlx::{
     postgres::{PgConnectOptions, PgPool, PgPoolOptions, Postgres},
 };
 
-pub use self::processor::StorageProcessor;
 pub(crate) use self::processor::StorageProcessorTags;
 use self::processor::TracedConnections;
+pub use self::processor::{IsolationLevel, StorageProcessor};
 use crate::metrics::CONNECTION_METRICS;
 
 mod processor;
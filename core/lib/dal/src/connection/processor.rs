@@ -13,6 +13,33 @@ use sqlx::{pool::PoolConnection, types::chrono, Connection, PgConnection, Postgr
 
 use crate::{metrics::CONNECTION_METRICS, ConnectionPool};
 
+/// Postgres transaction isolation level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::ReadCommitted => "READ COMMITTED",
+            Self::RepeatableRead => "REPEATABLE READ",
+            Self::Serializable => "SERIALIZABLE",
+        }
+    }
+
+    fn from_setting(setting: &str) -> Option<Self> {
+        Some(match setting {
+            "read committed" => Self::ReadCommitted,
+            "repeatable read" => Self::RepeatableRead,
+            "serializable" => Self::Serializable,
+            _ => return None,
+        })
+    }
+}
+
 /// Tags that can be associated with a connection.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct StorageProcessorTags {
@@ -155,6 +182,29 @@ impl<'a> StorageProcessor<'a> {
         Ok(StorageProcessor { inner })
     }
 
+    /// Starts a transaction with the specified isolation level (as opposed to the default level configured
+    /// for the database, which is used by [`Self::start_transaction()`]).
+    pub async fn start_transaction_with_isolation_level(
+        &mut self,
+        level: IsolationLevel,
+    ) -> sqlx::Result<StorageProcessor<'_>> {
+        let mut transaction = self.start_transaction().await?;
+        let statement = format!("SET TRANSACTION ISOLATION LEVEL {}", level.as_sql());
+        sqlx::query(&statement).execute(transaction.conn()).await?;
+        Ok(transaction)
+    }
+
+    /// Returns the isolation level of the current transaction, or the default isolation level
+    /// if the `StorageProcessor` is not within a transaction.
+    pub async fn isolation_level(&mut self) -> sqlx::Result<IsolationLevel> {
+        let setting: String = sqlx::query_scalar("SELECT current_setting('transaction_isolation')")
+            .fetch_one(self.conn())
+            .await?;
+        IsolationLevel::from_setting(&setting).ok_or_else(|| {
+            sqlx::Error::Decode(format!("unsupported isolation level: {setting}").into())
+        })
+    }
+
     /// Checks if the `StorageProcessor` is currently within database transaction.
     pub fn in_transaction(&self) -> bool {
         matches!(self.inner, StorageProcessorInner::Transaction { .. })
@@ -207,6 +257,7 @@ impl<'a> StorageProcessor<'a> {
 
 #[cfg(test)]
 mod tests {
+    use super::IsolationLevel;
     use crate::ConnectionPool;
 
     #[tokio::test]
@@ -222,6 +273,29 @@ mod tests {
         assert_eq!(transaction_tags, original_tags);
     }
 
+    #[tokio::test]
+    async fn starting_transaction_with_isolation_level() {
+        let pool = ConnectionPool::constrained_test_pool(1).await;
+        let mut connection = pool.access_storage().await.unwrap();
+        let default_level = connection.isolation_level().await.unwrap();
+        assert_eq!(default_level, IsolationLevel::ReadCommitted);
+
+        for level in [
+            IsolationLevel::ReadCommitted,
+            IsolationLevel::RepeatableRead,
+            IsolationLevel::Serializable,
+        ] {
+            let mut transaction = connection
+                .start_transaction_with_isolation_level(level)
+                .await
+                .unwrap();
+            assert_eq!(transaction.isolation_level().await.unwrap(), level);
+            transaction.commit().await.unwrap();
+        }
+        // The isolation level is reset after the transaction ends.
+        assert_eq!(connection.isolation_level().await.unwrap(), default_level);
+    }
+
     #[tokio::test]
     async fn tracing_connections() {
         let pool = ConnectionPool::constrained_test_pool(1).await;
@@ -98,6 +98,37 @@ impl FactoryDepsDal<'_, '_> {
         }
     }
 
+    /// Returns hashes out of the specified bytecode `hashes` that have no corresponding factory dependency.
+    /// Unlike [`Self::get_factory_deps()`], doesn't load bytecodes.
+    pub async fn get_missing_factory_deps(&mut self, hashes: &[H256]) -> sqlx::Result<Vec<H256>> {
+        let hashes_as_bytes: Vec<_> = hashes.iter().map(H256::as_bytes).collect();
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                u.bytecode_hash AS "bytecode_hash!"
+            FROM
+                UNNEST($1::bytea[]) AS u (bytecode_hash)
+            WHERE
+                NOT EXISTS (
+                    SELECT
+                        1
+                    FROM
+                        factory_deps
+                    WHERE
+                        factory_deps.bytecode_hash = u.bytecode_hash
+                )
+            "#,
+            &hashes_as_bytes as &[&[u8]],
+        )
+        .fetch_all(self.storage.conn())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| H256::from_slice(&row.bytecode_hash))
+            .collect())
+    }
+
     /// Returns bytecodes for factory deps with the specified `hashes`.
     pub async fn get_factory_deps(
         &mut self,
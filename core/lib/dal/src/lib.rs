@@ -2,7 +2,7 @@
 
 pub use sqlx::{types::BigDecimal, Error as SqlxError};
 
-pub use crate::connection::{ConnectionPool, StorageProcessor};
+pub use crate::connection::{ConnectionPool, IsolationLevel, StorageProcessor};
 use crate::{
     basic_witness_input_producer_dal::BasicWitnessInputProducerDal, blocks_dal::BlocksDal,
     blocks_web3_dal::BlocksWeb3Dal, consensus_dal::ConsensusDal,
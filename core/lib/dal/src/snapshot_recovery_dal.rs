@@ -1,9 +1,22 @@
+use std::time::Duration;
+
 use zksync_types::{
-    snapshots::SnapshotRecoveryStatus, L1BatchNumber, MiniblockNumber, ProtocolVersionId, H256,
+    snapshots::{SnapshotRecoveryCheckpoint, SnapshotRecoveryStatus},
+    L1BatchNumber, MiniblockNumber, ProtocolVersionId, H256,
 };
 
 use crate::StorageProcessor;
 
+/// Class of advisory locks used to coordinate snapshot recovery (`snap` in ASCII).
+const RECOVERY_LOCK_CLASS: i32 = 0x736e_6170;
+/// Object ID of the advisory lock guarding recovery initialization. Storage log chunks use their IDs
+/// (which are non-negative) as object IDs.
+const RECOVERY_INITIALIZATION_LOCK_ID: i32 = -1;
+/// Staging table for storage logs recovered from a snapshot (see [`SnapshotRecoveryDal::create_staging_tables()`]).
+pub(crate) const STORAGE_LOGS_STAGING_TABLE: &str = "snapshot_recovery_storage_logs";
+/// Staging table for initial writes recovered from a snapshot (see [`SnapshotRecoveryDal::create_staging_tables()`]).
+pub(crate) const INITIAL_WRITES_STAGING_TABLE: &str = "snapshot_recovery_initial_writes";
+
 #[derive(Debug)]
 pub struct SnapshotRecoveryDal<'a, 'c> {
     pub(crate) storage: &'a mut StorageProcessor<'c>,
@@ -65,6 +78,367 @@ impl SnapshotRecoveryDal<'_, '_> {
         Ok(())
     }
 
+    pub async fn mark_storage_logs_chunk_as_unprocessed(
+        &mut self,
+        chunk_id: u64,
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE snapshot_recovery
+            SET
+                storage_logs_chunks_processed[$1] = FALSE,
+                updated_at = NOW()
+            "#,
+            chunk_id as i32 + 1
+        )
+        .execute(self.storage.conn())
+        .await?;
+
+        Ok(())
+    }
+
+    /// Overwrites processed flags for all storage logs chunks, e.g., after they were re-derived
+    /// from the persisted storage logs. The number of chunks may differ from the persisted one.
+    pub async fn set_storage_logs_chunks_processed(
+        &mut self,
+        chunks_processed: &[bool],
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE snapshot_recovery
+            SET
+                storage_logs_chunks_processed = $1,
+                updated_at = NOW()
+            "#,
+            chunks_processed
+        )
+        .execute(self.storage.conn())
+        .await?;
+
+        Ok(())
+    }
+
+    /// Persists an incremental verification checkpoint for the snapshot recovery in progress,
+    /// replacing the previous checkpoint (if any).
+    pub async fn save_verification_checkpoint(
+        &mut self,
+        checkpoint: &SnapshotRecoveryCheckpoint,
+    ) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE snapshot_recovery
+            SET
+                storage_logs_chunks_verified = $1,
+                verified_storage_logs_count = $2,
+                updated_at = NOW()
+            "#,
+            &checkpoint.verified_chunks,
+            checkpoint.storage_logs_count as i64
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the latest verification checkpoint persisted with [`Self::save_verification_checkpoint()`].
+    pub async fn get_verification_checkpoint(
+        &mut self,
+    ) -> sqlx::Result<Option<SnapshotRecoveryCheckpoint>> {
+        let record = sqlx::query!(
+            r#"
+            SELECT
+                storage_logs_chunks_verified,
+                verified_storage_logs_count
+            FROM
+                snapshot_recovery
+            "#,
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+
+        Ok(record.and_then(|row| {
+            Some(SnapshotRecoveryCheckpoint {
+                verified_chunks: row.storage_logs_chunks_verified?,
+                storage_logs_count: row.verified_storage_logs_count? as u64,
+            })
+        }))
+    }
+
+    /// Marks post-recovery checks for the recovered snapshot as passed, so that they are not re-run
+    /// on applier restarts.
+    pub async fn mark_post_recovery_checks_as_passed(&mut self) -> sqlx::Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE snapshot_recovery
+            SET
+                post_recovery_checks_passed = TRUE,
+                updated_at = NOW()
+            "#
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(())
+    }
+
+    /// Checks whether post-recovery checks were marked as passed with [`Self::mark_post_recovery_checks_as_passed()`].
+    pub async fn are_post_recovery_checks_passed(&mut self) -> sqlx::Result<bool> {
+        let passed = sqlx::query_scalar!(
+            r#"
+            SELECT
+                post_recovery_checks_passed
+            FROM
+                snapshot_recovery
+            "#
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+        Ok(passed.unwrap_or(false))
+    }
+
+    /// Resets the marker set by [`Self::mark_post_recovery_checks_as_passed()`].
+    #[cfg(feature = "testonly")]
+    pub async fn reset_post_recovery_checks_for_tests(&mut self) {
+        sqlx::query!(
+            r#"
+            UPDATE snapshot_recovery
+            SET
+                post_recovery_checks_passed = FALSE
+            "#
+        )
+        .execute(self.storage.conn())
+        .await
+        .unwrap();
+    }
+
+    /// Acquires a transaction-level advisory lock guarding initialization of snapshot recovery, waiting until
+    /// the lock is available. The lock is released when the current transaction ends. Allows multiple processes
+    /// to concurrently recover the same node storage.
+    pub async fn lock_recovery_initialization(&mut self) -> sqlx::Result<()> {
+        // `pg_advisory_xact_lock()` returns `void`, which isn't supported by `query!` macros.
+        sqlx::query("SELECT pg_advisory_xact_lock($1, $2)")
+            .bind(RECOVERY_LOCK_CLASS)
+            .bind(RECOVERY_INITIALIZATION_LOCK_ID)
+            .execute(self.storage.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// Tries to acquire a transaction-level advisory lock for the specified storage logs chunk without waiting.
+    /// Returns `false` if the lock is held by another transaction. The lock is released when the current
+    /// transaction ends.
+    pub async fn try_lock_storage_logs_chunk(&mut self, chunk_id: u64) -> sqlx::Result<bool> {
+        sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1, $2)")
+            .bind(RECOVERY_LOCK_CLASS)
+            .bind(chunk_id as i32)
+            .fetch_one(self.storage.conn())
+            .await
+    }
+
+    /// Checks whether the specified storage logs chunk is marked as processed. Returns `false` if there is
+    /// no snapshot recovery status.
+    pub async fn is_storage_logs_chunk_processed(&mut self, chunk_id: u64) -> sqlx::Result<bool> {
+        let is_processed = sqlx::query_scalar!(
+            r#"
+            SELECT
+                storage_logs_chunks_processed[$1] AS "is_processed"
+            FROM
+                snapshot_recovery
+            "#,
+            chunk_id as i32 + 1
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+        Ok(is_processed.flatten().unwrap_or(false))
+    }
+
+    /// Returns the time elapsed since snapshot recovery was started (i.e., since the recovery status was inserted),
+    /// or `None` if recovery hasn't started. The time is measured using the DB clock.
+    pub async fn get_recovery_age(&mut self) -> sqlx::Result<Option<Duration>> {
+        let age = sqlx::query_scalar!(
+            r#"
+            SELECT
+                EXTRACT(
+                    EPOCH
+                    FROM
+                        (NOW() - created_at)
+                )::FLOAT8 AS "age!"
+            FROM
+                snapshot_recovery
+            "#
+        )
+        .fetch_optional(self.storage.conn())
+        .await?;
+        Ok(age.map(|age| Duration::from_secs_f64(age.max(0.0))))
+    }
+
+    /// Deletes all data persisted by snapshot recovery: the recovery status, storage logs, initial writes,
+    /// factory dependencies and tokens, as well as staging tables. Should only be used to restart recovery
+    /// that is not complete, since the node storage doesn't contain other data in this case.
+    ///
+    /// Tables are truncated rather than deleted from row by row, since they may contain hundreds of millions
+    /// of rows for a large snapshot. `TRUNCATE` takes an `ACCESS EXCLUSIVE` lock on the tables, but it's fine
+    /// given that the node doesn't serve any data during recovery.
+    ///
+    /// This method should be called in a transaction, so that the data is deleted atomically.
+    pub async fn delete_recovery_data(&mut self) -> sqlx::Result<()> {
+        // Staging tables are not a part of the schema checked by `query!` macros.
+        let drop_staging_tables = format!(
+            "DROP TABLE IF EXISTS {STORAGE_LOGS_STAGING_TABLE}, {INITIAL_WRITES_STAGING_TABLE}"
+        );
+        let statements = [
+            "TRUNCATE storage_logs, initial_writes, factory_deps, tokens, snapshot_recovery",
+            drop_staging_tables.as_str(),
+        ];
+        for statement in statements {
+            sqlx::query(statement).execute(self.storage.conn()).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value that will be returned by the next `nextval()` call for the specified Postgres sequence,
+    /// or `None` if the sequence doesn't exist.
+    pub async fn get_sequence_next_value(&mut self, sequence: &str) -> sqlx::Result<Option<u64>> {
+        // Sequences are not a part of the schema checked by `query!` macros.
+        let row: Option<(String, i64)> = sqlx::query_as(
+            "SELECT seqrelid::regclass::TEXT, seqincrement FROM pg_sequence \
+             WHERE seqrelid = to_regclass($1)",
+        )
+        .bind(sequence)
+        .fetch_optional(self.storage.conn())
+        .await?;
+        let Some((quoted_sequence, increment_by)) = row else {
+            return Ok(None);
+        };
+
+        // `pg_sequences.last_value` is `NULL` after `setval(.., FALSE)`, so the sequence relation is queried instead.
+        // `quoted_sequence` is produced by Postgres, so it's safe to interpolate into the query.
+        let (last_value, is_called): (i64, bool) = sqlx::query_as(&format!(
+            "SELECT last_value, is_called FROM {quoted_sequence}"
+        ))
+        .fetch_one(self.storage.conn())
+        .await?;
+        let next_value = if is_called {
+            last_value + increment_by
+        } else {
+            last_value
+        };
+        Ok(Some(next_value as u64))
+    }
+
+    /// Sets the value returned by the next `nextval()` call for the specified Postgres sequence. Returns `false`
+    /// if the sequence doesn't exist.
+    pub async fn set_sequence_next_value(
+        &mut self,
+        sequence: &str,
+        next_value: u64,
+    ) -> sqlx::Result<bool> {
+        let value: Option<i64> = sqlx::query_scalar("SELECT setval(to_regclass($1), $2, FALSE)")
+            .bind(sequence)
+            .bind(next_value as i64)
+            .fetch_one(self.storage.conn())
+            .await?;
+        Ok(value.is_some())
+    }
+
+    /// Creates a Postgres sequence with the specified name.
+    #[cfg(feature = "testonly")]
+    pub async fn create_sequence_for_tests(&mut self, sequence: &str) {
+        sqlx::query(&format!("CREATE SEQUENCE {sequence}"))
+            .execute(self.storage.conn())
+            .await
+            .unwrap();
+    }
+
+    /// Creates staging tables for storage logs and initial writes recovered from a snapshot, unless they already exist.
+    /// The staging tables have the same columns as `storage_logs` and `initial_writes`, but no indices or constraints.
+    pub async fn create_staging_tables(&mut self) -> sqlx::Result<()> {
+        // DDL statements cannot be checked by `query!` macros since the staging tables don't exist in the schema.
+        for (staging_table, live_table) in [
+            (STORAGE_LOGS_STAGING_TABLE, "storage_logs"),
+            (INITIAL_WRITES_STAGING_TABLE, "initial_writes"),
+        ] {
+            let statement = format!(
+                "CREATE TABLE IF NOT EXISTS {staging_table} (LIKE {live_table} INCLUDING DEFAULTS)"
+            );
+            sqlx::query(&statement).execute(self.storage.conn()).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks whether staging tables created by [`Self::create_staging_tables()`] exist.
+    pub async fn staging_tables_exist(&mut self) -> sqlx::Result<bool> {
+        sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL AND to_regclass($2) IS NOT NULL")
+            .bind(STORAGE_LOGS_STAGING_TABLE)
+            .bind(INITIAL_WRITES_STAGING_TABLE)
+            .fetch_one(self.storage.conn())
+            .await
+    }
+
+    /// Moves all rows from the staging tables created by [`Self::create_staging_tables()`] to `storage_logs`
+    /// and `initial_writes` and drops the staging tables. Returns `false` if the staging tables don't exist
+    /// (e.g., because they were already moved).
+    ///
+    /// This method should be called in a transaction, so that the live tables are updated atomically.
+    pub async fn swap_staging_tables(&mut self) -> sqlx::Result<bool> {
+        if !self.staging_tables_exist().await? {
+            return Ok(false);
+        }
+
+        let storage_logs_statement = format!(
+            "INSERT INTO storage_logs (
+                hashed_key, address, key, value, operation_number, tx_hash, miniblock_number,
+                created_at, updated_at
+            )
+            SELECT
+                hashed_key, address, key, value, operation_number, tx_hash, miniblock_number,
+                created_at, updated_at
+            FROM {STORAGE_LOGS_STAGING_TABLE}"
+        );
+        let initial_writes_statement = format!(
+            "INSERT INTO initial_writes (hashed_key, index, l1_batch_number, created_at, updated_at)
+            SELECT hashed_key, index, l1_batch_number, created_at, updated_at
+            FROM {INITIAL_WRITES_STAGING_TABLE}"
+        );
+        let drop_statement =
+            format!("DROP TABLE {STORAGE_LOGS_STAGING_TABLE}, {INITIAL_WRITES_STAGING_TABLE}");
+        for statement in [
+            storage_logs_statement,
+            initial_writes_statement,
+            drop_statement,
+        ] {
+            sqlx::query(&statement).execute(self.storage.conn()).await?;
+        }
+        Ok(true)
+    }
+
+    /// Returns L1 batch numbers for up to `limit` snapshot recovery status rows in ascending order.
+    /// There may be more than one row only because of a bug or manual intervention, in which case
+    /// the recovery status is ambiguous.
+    pub async fn get_recovery_status_l1_batches(
+        &mut self,
+        limit: usize,
+    ) -> sqlx::Result<Vec<L1BatchNumber>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                l1_batch_number
+            FROM
+                snapshot_recovery
+            ORDER BY
+                l1_batch_number
+            LIMIT
+                $1
+            "#,
+            limit as i64
+        )
+        .fetch_all(self.storage.conn())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| L1BatchNumber(row.l1_batch_number as u32))
+            .collect())
+    }
+
     pub async fn get_applied_snapshot_status(
         &mut self,
     ) -> sqlx::Result<Option<SnapshotRecoveryStatus>> {
@@ -102,7 +476,9 @@ impl SnapshotRecoveryDal<'_, '_> {
 #[cfg(test)]
 mod tests {
     use zksync_types::{
-        snapshots::SnapshotRecoveryStatus, L1BatchNumber, MiniblockNumber, ProtocolVersionId, H256,
+        snapshots::{SnapshotRecoveryStatus, SnapshotStorageLog},
+        AccountTreeId, Address, L1BatchNumber, MiniblockNumber, ProtocolVersionId, StorageKey,
+        H256,
     };
 
     use crate::ConnectionPool;
@@ -152,5 +528,132 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(status, updated_status_from_db.unwrap());
+
+        status.l1_batch_number = L1BatchNumber(456);
+        applied_status_dal
+            .insert_initial_recovery_status(&status)
+            .await
+            .unwrap();
+        let l1_batches = applied_status_dal
+            .get_recovery_status_l1_batches(2)
+            .await
+            .unwrap();
+        assert_eq!(l1_batches, [L1BatchNumber(123), L1BatchNumber(456)]);
+    }
+
+    #[tokio::test]
+    async fn swapping_staging_tables() {
+        let connection_pool = ConnectionPool::test_pool().await;
+        let mut conn = connection_pool.access_storage().await.unwrap();
+        let snapshot_storage_logs: Vec<_> = (1..=10_u64)
+            .map(|i| SnapshotStorageLog {
+                key: StorageKey::new(AccountTreeId::new(Address::repeat_byte(1)), H256::random()),
+                value: H256::random(),
+                l1_batch_number_of_initial_write: L1BatchNumber(1),
+                enumeration_index: i,
+            })
+            .collect();
+
+        let mut dal = conn.snapshot_recovery_dal();
+        assert!(!dal.staging_tables_exist().await.unwrap());
+        assert!(!dal.swap_staging_tables().await.unwrap());
+        dal.create_staging_tables().await.unwrap();
+        // Creating tables is idempotent.
+        dal.create_staging_tables().await.unwrap();
+        assert!(dal.staging_tables_exist().await.unwrap());
+
+        conn.storage_logs_dal()
+            .insert_storage_logs_from_snapshot_into_staging(
+                MiniblockNumber(1),
+                &snapshot_storage_logs,
+            )
+            .await
+            .unwrap();
+        conn.storage_logs_dedup_dal()
+            .insert_initial_writes_from_snapshot_into_staging(&snapshot_storage_logs)
+            .await
+            .unwrap();
+        let live_storage_logs = conn
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await;
+        assert!(live_storage_logs.is_empty());
+        let live_initial_writes = conn
+            .storage_logs_dedup_dal()
+            .dump_all_initial_writes_for_tests()
+            .await;
+        assert!(live_initial_writes.is_empty());
+
+        let mut transaction = conn.start_transaction().await.unwrap();
+        assert!(transaction
+            .snapshot_recovery_dal()
+            .swap_staging_tables()
+            .await
+            .unwrap());
+        transaction.commit().await.unwrap();
+
+        let live_storage_logs = conn
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await;
+        assert_eq!(live_storage_logs.len(), snapshot_storage_logs.len());
+        let live_initial_writes = conn
+            .storage_logs_dedup_dal()
+            .dump_all_initial_writes_for_tests()
+            .await;
+        assert_eq!(live_initial_writes.len(), snapshot_storage_logs.len());
+        for log in &snapshot_storage_logs {
+            let hashed_key = log.key.hashed_key();
+            let db_log = live_storage_logs
+                .iter()
+                .find(|db_log| db_log.hashed_key == hashed_key)
+                .unwrap();
+            assert_eq!(db_log.value, log.value);
+            assert_eq!(db_log.operation_number, log.enumeration_index);
+        }
+
+        let mut dal = conn.snapshot_recovery_dal();
+        assert!(!dal.staging_tables_exist().await.unwrap());
+        assert!(!dal.swap_staging_tables().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn manipulating_sequence_next_value() {
+        const SEQUENCE: &str = "test_seq";
+
+        let connection_pool = ConnectionPool::test_pool().await;
+        let mut conn = connection_pool.access_storage().await.unwrap();
+        let mut dal = conn.snapshot_recovery_dal();
+        assert_eq!(dal.get_sequence_next_value(SEQUENCE).await.unwrap(), None);
+
+        sqlx::query(&format!("CREATE SEQUENCE {SEQUENCE} START 5 INCREMENT 2"))
+            .execute(dal.storage.conn())
+            .await
+            .unwrap();
+        assert_eq!(
+            dal.get_sequence_next_value(SEQUENCE).await.unwrap(),
+            Some(5)
+        );
+
+        assert!(dal.set_sequence_next_value(SEQUENCE, 100).await.unwrap());
+        assert_eq!(
+            dal.get_sequence_next_value(SEQUENCE).await.unwrap(),
+            Some(100)
+        );
+        // Reading the next value doesn't advance the sequence.
+        assert_eq!(
+            dal.get_sequence_next_value(SEQUENCE).await.unwrap(),
+            Some(100)
+        );
+
+        let value: i64 = sqlx::query_scalar(&format!("SELECT nextval('{SEQUENCE}')"))
+            .fetch_one(dal.storage.conn())
+            .await
+            .unwrap();
+        assert_eq!(value, 100);
+        assert_eq!(
+            dal.get_sequence_next_value(SEQUENCE).await.unwrap(),
+            Some(102)
+        );
     }
 }
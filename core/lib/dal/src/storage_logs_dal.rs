@@ -7,7 +7,9 @@ use zksync_types::{
 };
 
 pub use crate::models::storage_log::{DbStorageLog, StorageRecoveryLogEntry};
-use crate::{instrument::InstrumentExt, StorageProcessor};
+use crate::{
+    instrument::InstrumentExt, snapshot_recovery_dal::STORAGE_LOGS_STAGING_TABLE, StorageProcessor,
+};
 
 #[derive(Debug)]
 pub struct StorageLogsDal<'a, 'c> {
@@ -73,17 +75,43 @@ impl StorageLogsDal<'_, '_> {
         miniblock_number: MiniblockNumber,
         snapshot_storage_logs: &[SnapshotStorageLog],
     ) -> sqlx::Result<()> {
-        let mut copy = self
-            .storage
-            .conn()
-            .copy_in_raw(
-                "COPY storage_logs(
-                    hashed_key, address, key, value, operation_number, tx_hash, miniblock_number,
-                    created_at, updated_at
-                )
-                FROM STDIN WITH (DELIMITER '|')",
+        self.copy_storage_logs_from_snapshot(
+            "storage_logs",
+            miniblock_number,
+            snapshot_storage_logs,
+        )
+        .await
+    }
+
+    /// Same as [`Self::insert_storage_logs_from_snapshot()`], but inserts storage logs into the staging table
+    /// created by [`SnapshotRecoveryDal::create_staging_tables()`](crate::snapshot_recovery_dal::SnapshotRecoveryDal::create_staging_tables()).
+    pub async fn insert_storage_logs_from_snapshot_into_staging(
+        &mut self,
+        miniblock_number: MiniblockNumber,
+        snapshot_storage_logs: &[SnapshotStorageLog],
+    ) -> sqlx::Result<()> {
+        self.copy_storage_logs_from_snapshot(
+            STORAGE_LOGS_STAGING_TABLE,
+            miniblock_number,
+            snapshot_storage_logs,
+        )
+        .await
+    }
+
+    async fn copy_storage_logs_from_snapshot(
+        &mut self,
+        table_name: &str,
+        miniblock_number: MiniblockNumber,
+        snapshot_storage_logs: &[SnapshotStorageLog],
+    ) -> sqlx::Result<()> {
+        let copy_statement = format!(
+            "COPY {table_name}(
+                hashed_key, address, key, value, operation_number, tx_hash, miniblock_number,
+                created_at, updated_at
             )
-            .await?;
+            FROM STDIN WITH (DELIMITER '|')"
+        );
+        let mut copy = self.storage.conn().copy_in_raw(&copy_statement).await?;
 
         let mut buffer = String::new();
         let now = Utc::now().naive_utc().to_string();
@@ -603,6 +631,122 @@ impl StorageLogsDal<'_, '_> {
         Ok(count.unwrap_or(0) as u64)
     }
 
+    /// Fetches up to `limit` storage logs in the specified miniblock together with their initial writes, ordered
+    /// by the hashed key and starting after `after_hashed_key` (if specified). Logs are returned together with
+    /// their hashed keys as stored in Postgres. Used to stream the recovered state from Postgres.
+    pub async fn get_recovered_storage_logs(
+        &mut self,
+        miniblock_number: MiniblockNumber,
+        after_hashed_key: Option<H256>,
+        limit: usize,
+    ) -> sqlx::Result<Vec<(H256, SnapshotStorageLog)>> {
+        // Any hashed key is greater than an empty byte sequence.
+        let after_hashed_key = after_hashed_key.as_ref().map_or(&[][..], H256::as_bytes);
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                storage_logs.hashed_key,
+                storage_logs.address,
+                storage_logs.key,
+                storage_logs.value,
+                initial_writes.l1_batch_number,
+                initial_writes.index
+            FROM
+                storage_logs
+                INNER JOIN initial_writes ON storage_logs.hashed_key = initial_writes.hashed_key
+            WHERE
+                storage_logs.miniblock_number = $1
+                AND storage_logs.hashed_key > $2
+            ORDER BY
+                storage_logs.hashed_key
+            LIMIT
+                $3
+            "#,
+            miniblock_number.0 as i64,
+            after_hashed_key,
+            limit as i64
+        )
+        .fetch_all(self.storage.conn())
+        .await?;
+
+        let storage_logs = rows.into_iter().map(|row| {
+            let log = SnapshotStorageLog {
+                key: StorageKey::new(
+                    AccountTreeId::new(Address::from_slice(&row.address)),
+                    H256::from_slice(&row.key),
+                ),
+                value: H256::from_slice(&row.value),
+                l1_batch_number_of_initial_write: L1BatchNumber(row.l1_batch_number as u32),
+                enumeration_index: row.index as u64,
+            };
+            (H256::from_slice(&row.hashed_key), log)
+        });
+        Ok(storage_logs.collect())
+    }
+
+    /// Counts storage logs with the specified hashed keys in the specified miniblock.
+    pub async fn count_storage_logs_for_keys(
+        &mut self,
+        hashed_keys: &[H256],
+        miniblock_number: MiniblockNumber,
+    ) -> sqlx::Result<u64> {
+        let hashed_keys: Vec<_> = hashed_keys.iter().map(H256::as_bytes).collect();
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT
+                COUNT(*) AS "count!"
+            FROM
+                storage_logs
+            WHERE
+                miniblock_number = $1
+                AND hashed_key = ANY ($2)
+            "#,
+            miniblock_number.0 as i64,
+            &hashed_keys as &[&[u8]]
+        )
+        .fetch_one(self.storage.conn())
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Counts unique storage keys (i.e., hashed keys) with at least one storage log.
+    pub async fn count_unique_storage_keys(&mut self) -> sqlx::Result<u64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT
+                COUNT(DISTINCT hashed_key) AS "count!"
+            FROM
+                storage_logs
+            "#
+        )
+        .fetch_one(self.storage.conn())
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Removes storage logs with the specified hashed keys in the specified miniblock. Returns the number
+    /// of removed logs.
+    pub async fn delete_storage_logs_for_keys(
+        &mut self,
+        hashed_keys: &[H256],
+        miniblock_number: MiniblockNumber,
+    ) -> sqlx::Result<u64> {
+        let hashed_keys: Vec<_> = hashed_keys.iter().map(H256::as_bytes).collect();
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM storage_logs
+            WHERE
+                miniblock_number = $1
+                AND hashed_key = ANY ($2)
+            "#,
+            miniblock_number.0 as i64,
+            &hashed_keys as &[&[u8]]
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     /// Gets a starting tree entry for each of the supplied `key_ranges` for the specified
     /// `miniblock_number`. This method is used during Merkle tree recovery.
     pub async fn get_chunk_starts_for_miniblock(
@@ -8,7 +8,7 @@ use zksync_types::{
 use zksync_utils::u256_to_h256;
 
 pub use crate::models::storage_log::DbInitialWrite;
-use crate::StorageProcessor;
+use crate::{snapshot_recovery_dal::INITIAL_WRITES_STAGING_TABLE, StorageProcessor};
 
 #[derive(Debug)]
 pub struct StorageLogsDedupDal<'a, 'c> {
@@ -52,14 +52,30 @@ impl StorageLogsDedupDal<'_, '_> {
         &mut self,
         snapshot_storage_logs: &[SnapshotStorageLog],
     ) -> sqlx::Result<()> {
-        let mut copy = self
-            .storage
-            .conn()
-            .copy_in_raw(
-                "COPY initial_writes (hashed_key, index, l1_batch_number, created_at, updated_at) \
-                FROM STDIN WITH (DELIMITER '|')",
-            )
-            .await?;
+        self.copy_initial_writes_from_snapshot("initial_writes", snapshot_storage_logs)
+            .await
+    }
+
+    /// Same as [`Self::insert_initial_writes_from_snapshot()`], but inserts initial writes into the staging table
+    /// created by [`SnapshotRecoveryDal::create_staging_tables()`](crate::snapshot_recovery_dal::SnapshotRecoveryDal::create_staging_tables()).
+    pub async fn insert_initial_writes_from_snapshot_into_staging(
+        &mut self,
+        snapshot_storage_logs: &[SnapshotStorageLog],
+    ) -> sqlx::Result<()> {
+        self.copy_initial_writes_from_snapshot(INITIAL_WRITES_STAGING_TABLE, snapshot_storage_logs)
+            .await
+    }
+
+    async fn copy_initial_writes_from_snapshot(
+        &mut self,
+        table_name: &str,
+        snapshot_storage_logs: &[SnapshotStorageLog],
+    ) -> sqlx::Result<()> {
+        let copy_statement = format!(
+            "COPY {table_name} (hashed_key, index, l1_batch_number, created_at, updated_at) \
+            FROM STDIN WITH (DELIMITER '|')"
+        );
+        let mut copy = self.storage.conn().copy_in_raw(&copy_statement).await?;
 
         let mut bytes: Vec<u8> = Vec::new();
         let now = Utc::now().naive_utc().to_string();
@@ -80,6 +96,25 @@ impl StorageLogsDedupDal<'_, '_> {
         Ok(())
     }
 
+    /// Removes initial writes with the specified hashed keys. Returns the number of removed writes.
+    pub async fn delete_initial_writes_for_keys(
+        &mut self,
+        hashed_keys: &[H256],
+    ) -> sqlx::Result<u64> {
+        let hashed_keys: Vec<_> = hashed_keys.iter().map(H256::as_bytes).collect();
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM initial_writes
+            WHERE
+                hashed_key = ANY ($1)
+            "#,
+            &hashed_keys as &[&[u8]]
+        )
+        .execute(self.storage.conn())
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     pub async fn insert_initial_writes(
         &mut self,
         l1_batch_number: L1BatchNumber,
@@ -162,6 +197,21 @@ impl StorageLogsDedupDal<'_, '_> {
         .map(|max| max as u64)
     }
 
+    /// Returns the minimum enumeration index among all initial writes, or `None` if there are no initial writes.
+    pub async fn min_enumeration_index(&mut self) -> sqlx::Result<Option<u64>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                MIN(INDEX) AS "min?"
+            FROM
+                initial_writes
+            "#,
+        )
+        .fetch_one(self.storage.conn())
+        .await?;
+        Ok(row.min.map(|min| min as u64))
+    }
+
     pub async fn initial_writes_for_batch(
         &mut self,
         l1_batch_number: L1BatchNumber,
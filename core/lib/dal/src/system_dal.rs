@@ -27,4 +27,49 @@ impl SystemDal<'_, '_> {
             _ => 0,
         }
     }
+
+    /// Returns tables from the provided list that are missing in the current DB schema.
+    pub async fn get_missing_tables(&mut self, table_names: &[&str]) -> sqlx::Result<Vec<String>> {
+        let table_names: Vec<_> = table_names.iter().map(|&name| name.to_owned()).collect();
+        let existing_tables: Vec<String> = sqlx::query_scalar(
+            "SELECT table_name::TEXT FROM information_schema.tables \
+             WHERE table_schema = current_schema() AND table_name = ANY($1::TEXT[])",
+        )
+        .bind(&table_names)
+        .fetch_all(self.storage.conn())
+        .await?;
+
+        Ok(table_names
+            .into_iter()
+            .filter(|name| !existing_tables.contains(name))
+            .collect())
+    }
+
+    /// Loads the specified relation (a table or an index) into the Postgres buffer cache using `pg_prewarm`.
+    /// Returns the number of prewarmed blocks. Requires the `pg_prewarm` extension to be installed.
+    pub async fn prewarm_relation(&mut self, relation: &str) -> sqlx::Result<u64> {
+        let block_count: i64 = sqlx::query_scalar("SELECT pg_prewarm($1::TEXT::regclass)")
+            .bind(relation)
+            .fetch_one(self.storage.conn())
+            .await?;
+        Ok(block_count as u64)
+    }
+
+    /// Creates the specified Postgres extension if it doesn't exist.
+    #[cfg(feature = "testonly")]
+    pub async fn create_extension_for_tests(&mut self, extension: &str) -> sqlx::Result<()> {
+        sqlx::query(&format!("CREATE EXTENSION IF NOT EXISTS {extension}"))
+            .execute(self.storage.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// Drops the specified table. Used in tests to emulate an incomplete DB schema.
+    #[cfg(feature = "testonly")]
+    pub async fn drop_table_for_tests(&mut self, table_name: &str) {
+        sqlx::query(&format!("DROP TABLE {table_name} CASCADE"))
+            .execute(self.storage.conn())
+            .await
+            .unwrap();
+    }
 }
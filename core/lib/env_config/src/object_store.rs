@@ -56,6 +56,7 @@ mod tests {
             file_backed_base_path: "artifacts".to_string(),
             gcs_credential_file_path: "/path/to/credentials.json".to_string(),
             max_retries: 5,
+            file_backed_max_open_files: None,
         }
     }
 
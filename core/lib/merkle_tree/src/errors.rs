@@ -2,7 +2,7 @@
 
 use std::{error, fmt, str::Utf8Error};
 
-use crate::types::NodeKey;
+use crate::types::{NodeKey, ValueHash, TREE_DEPTH};
 
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -135,6 +135,29 @@ impl fmt::Display for DeserializeError {
 
 impl error::Error for DeserializeError {}
 
+/// Error verifying a [`TreeEntryWithProof`](crate::TreeEntryWithProof).
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ProofVerificationError {
+    /// Merkle path is longer than the tree depth.
+    #[error("Merkle path has {len} hashes, while the tree depth is {}", TREE_DEPTH)]
+    MerklePathTooLong {
+        /// Length of the Merkle path.
+        len: usize,
+    },
+    /// Leaf index of the entry is zero, but its value is not.
+    #[error("Invalid missing value specification: leaf index is zero, but value is non-default")]
+    InvalidMissingValue,
+    /// Proof resolves to an unexpected root hash.
+    #[error("Root hash mismatch: proof resolves to {actual:?}, while the trusted root hash is {expected:?}")]
+    RootHashMismatch {
+        /// Trusted root hash.
+        expected: ValueHash,
+        /// Root hash computed from the proof.
+        actual: ValueHash,
+    },
+}
+
 /// Error accessing a specific tree version.
 #[derive(Debug)]
 pub struct NoVersionError {
@@ -49,7 +49,7 @@
 use zksync_crypto::hasher::blake2::Blake2Hasher;
 
 pub use crate::{
-    errors::NoVersionError,
+    errors::{NoVersionError, ProofVerificationError},
     hasher::{HashTree, TreeRangeDigest},
     pruning::{MerkleTreePruner, MerkleTreePrunerHandle},
     storage::{
@@ -2,12 +2,13 @@
 
 use std::{cmp, mem};
 
+use assert_matches::assert_matches;
 use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use test_casing::test_casing;
 use zksync_crypto::hasher::blake2::Blake2Hasher;
 use zksync_merkle_tree::{
-    Database, HashTree, MerkleTree, PatchSet, Patched, TreeEntry, TreeInstruction, TreeLogEntry,
-    TreeRangeDigest,
+    Database, HashTree, MerkleTree, PatchSet, Patched, ProofVerificationError, TreeEntry,
+    TreeInstruction, TreeLogEntry, TreeRangeDigest,
 };
 use zksync_types::{AccountTreeId, Address, StorageKey, H256, U256};
 
@@ -110,6 +111,34 @@ fn entry_proofs_are_computed_correctly_on_empty_tree(kv_count: u64) {
     }
 }
 
+#[test]
+fn verifying_invalid_entry_proofs_without_panicking() {
+    let mut tree = MerkleTree::new(PatchSet::default());
+    let kvs = generate_key_value_pairs(0..10);
+    let expected_hash = compute_tree_hash(kvs.iter().copied());
+    tree.extend(kvs.clone());
+
+    let mut entry = tree
+        .entries_with_proofs(0, &[kvs[3].key])
+        .unwrap()
+        .pop()
+        .unwrap();
+    entry.try_verify(&Blake2Hasher, expected_hash).unwrap();
+
+    let valid_value = entry.base.value;
+    entry.base.value = H256::repeat_byte(0xff);
+    let err = entry.try_verify(&Blake2Hasher, expected_hash).unwrap_err();
+    assert_matches!(
+        err,
+        ProofVerificationError::RootHashMismatch { expected, .. } if expected == expected_hash
+    );
+
+    entry.base.value = valid_value;
+    entry.merkle_path.resize(257, H256::zero());
+    let err = entry.try_verify(&Blake2Hasher, expected_hash).unwrap_err();
+    assert_matches!(err, ProofVerificationError::MerklePathTooLong { len: 257 });
+}
+
 #[test]
 fn proofs_are_computed_correctly_for_mixed_instructions() {
     const RNG_SEED: u64 = 123;
@@ -1,7 +1,10 @@
 use std::fmt::Debug;
 
 use async_trait::async_trait;
-use tokio::{fs, io};
+use tokio::{
+    fs, io,
+    sync::{Semaphore, SemaphorePermit},
+};
 
 use crate::raw::{Bucket, ObjectStore, ObjectStoreError};
 
@@ -17,10 +20,12 @@ impl From<io::Error> for ObjectStoreError {
 #[derive(Debug)]
 pub(crate) struct FileBackedObjectStore {
     base_dir: String,
+    /// Limits the number of concurrently open files. Not set if the number of open files is not limited.
+    open_files_semaphore: Option<Semaphore>,
 }
 
 impl FileBackedObjectStore {
-    pub async fn new(base_dir: String) -> Self {
+    pub async fn new(base_dir: String, max_open_files: Option<usize>) -> Self {
         for bucket in &[
             Bucket::ProverJobs,
             Bucket::WitnessInput,
@@ -41,18 +46,29 @@ impl FileBackedObjectStore {
                     panic!("failed creating bucket `{bucket_path}`: {err}");
                 });
         }
-        FileBackedObjectStore { base_dir }
+        FileBackedObjectStore {
+            base_dir,
+            open_files_semaphore: max_open_files.map(|limit| Semaphore::new(limit.max(1))),
+        }
     }
 
     fn filename(&self, bucket: Bucket, key: &str) -> String {
         format!("{}/{bucket}/{key}", self.base_dir)
     }
+
+    /// Waits until a file can be opened without exceeding the open files limit.
+    async fn acquire_file_permit(&self) -> Option<SemaphorePermit<'_>> {
+        let semaphore = self.open_files_semaphore.as_ref()?;
+        // `unwrap()` is safe: the semaphore is never closed
+        Some(semaphore.acquire().await.unwrap())
+    }
 }
 
 #[async_trait]
 impl ObjectStore for FileBackedObjectStore {
     async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
         let filename = self.filename(bucket, key);
+        let _permit = self.acquire_file_permit().await;
         fs::read(filename).await.map_err(From::from)
     }
 
@@ -63,14 +79,23 @@ impl ObjectStore for FileBackedObjectStore {
         value: Vec<u8>,
     ) -> Result<(), ObjectStoreError> {
         let filename = self.filename(bucket, key);
+        let _permit = self.acquire_file_permit().await;
         fs::write(filename, value).await.map_err(From::from)
     }
 
     async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
         let filename = self.filename(bucket, key);
+        let _permit = self.acquire_file_permit().await;
         fs::remove_file(filename).await.map_err(From::from)
     }
 
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let filename = self.filename(bucket, key);
+        let _permit = self.acquire_file_permit().await;
+        let metadata = fs::metadata(filename).await?;
+        Ok(metadata.len())
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String {
         format!("{}/{}", self.base_dir, bucket)
     }
@@ -86,7 +111,7 @@ mod test {
     async fn test_get() {
         let dir = TempDir::new("test-data").unwrap();
         let path = dir.into_path().into_os_string().into_string().unwrap();
-        let object_store = FileBackedObjectStore::new(path).await;
+        let object_store = FileBackedObjectStore::new(path, None).await;
         let expected = vec![9, 0, 8, 9, 0, 7];
         let result = object_store
             .put_raw(Bucket::ProverJobs, "test-key.bin", expected.clone())
@@ -103,7 +128,7 @@ mod test {
     async fn test_put() {
         let dir = TempDir::new("test-data").unwrap();
         let path = dir.into_path().into_os_string().into_string().unwrap();
-        let object_store = FileBackedObjectStore::new(path).await;
+        let object_store = FileBackedObjectStore::new(path, None).await;
         let bytes = vec![9, 0, 8, 9, 0, 7];
         let result = object_store
             .put_raw(Bucket::ProverJobs, "test-key.bin", bytes)
@@ -115,7 +140,7 @@ mod test {
     async fn test_remove() {
         let dir = TempDir::new("test-data").unwrap();
         let path = dir.into_path().into_os_string().into_string().unwrap();
-        let object_store = FileBackedObjectStore::new(path).await;
+        let object_store = FileBackedObjectStore::new(path, None).await;
         let result = object_store
             .put_raw(Bucket::ProverJobs, "test-key.bin", vec![0, 1])
             .await;
@@ -125,4 +150,77 @@ mod test {
             .await;
         assert!(result.is_ok(), "result must be OK");
     }
+
+    #[tokio::test]
+    async fn test_get_size() {
+        let dir = TempDir::new("test-data").unwrap();
+        let path = dir.into_path().into_os_string().into_string().unwrap();
+        let object_store = FileBackedObjectStore::new(path, None).await;
+        object_store
+            .put_raw(Bucket::ProverJobs, "test-key.bin", vec![9, 0, 8, 9, 0, 7])
+            .await
+            .unwrap();
+        let size = object_store
+            .get_size_raw(Bucket::ProverJobs, "test-key.bin")
+            .await
+            .unwrap();
+        assert_eq!(size, 6);
+
+        let err = object_store
+            .get_size_raw(Bucket::ProverJobs, "missing-key.bin")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ObjectStoreError::KeyNotFound(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn limiting_open_files() {
+        const MAX_OPEN_FILES: usize = 4;
+        const FILE_COUNT: usize = 100;
+
+        let dir = TempDir::new("test-data").unwrap();
+        let path = dir.into_path().into_os_string().into_string().unwrap();
+        let object_store =
+            std::sync::Arc::new(FileBackedObjectStore::new(path, Some(MAX_OPEN_FILES)).await);
+
+        let put_tasks: Vec<_> = (0..FILE_COUNT)
+            .map(|i| {
+                let object_store = object_store.clone();
+                tokio::spawn(async move {
+                    let key = format!("test-key-{i}.bin");
+                    object_store
+                        .put_raw(Bucket::ProverJobs, &key, vec![i as u8; 16])
+                        .await
+                })
+            })
+            .collect();
+        for task in put_tasks {
+            task.await.unwrap().unwrap();
+        }
+        let get_tasks: Vec<_> = (0..FILE_COUNT)
+            .map(|i| {
+                let object_store = object_store.clone();
+                tokio::spawn(async move {
+                    let key = format!("test-key-{i}.bin");
+                    object_store.get_raw(Bucket::ProverJobs, &key).await
+                })
+            })
+            .collect();
+        for (i, task) in get_tasks.into_iter().enumerate() {
+            let value = task.await.unwrap().unwrap();
+            assert_eq!(value, vec![i as u8; 16]);
+        }
+
+        // Simulate `MAX_OPEN_FILES` in-flight operations; a new operation must wait until one of them completes.
+        let semaphore = object_store.open_files_semaphore.as_ref().unwrap();
+        let permits = semaphore.acquire_many(MAX_OPEN_FILES as u32).await.unwrap();
+        let get_future = object_store.get_raw(Bucket::ProverJobs, "test-key-0.bin");
+        tokio::pin!(get_future);
+        let timeout_result =
+            tokio::time::timeout(std::time::Duration::from_millis(50), &mut get_future).await;
+        assert!(timeout_result.is_err(), "open files limit was exceeded");
+        drop(permits);
+        let value = get_future.await.unwrap();
+        assert_eq!(value, vec![0; 16]);
+    }
 }
@@ -1,8 +1,17 @@
 //! GCS-based [`ObjectStore`] implementation.
 
-use std::{fmt, future::Future, time::Duration};
+use std::{
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::TryStreamExt as _;
 use google_cloud_auth::{credentials::CredentialsFile, error::Error};
 use google_cloud_storage::{
     client::{Client, ClientConfig},
@@ -17,6 +26,8 @@ use google_cloud_storage::{
     },
 };
 use http::StatusCode;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_util::io::StreamReader;
 
 use crate::{
     metrics::GCS_METRICS,
@@ -63,6 +74,74 @@ impl fmt::Debug for GoogleCloudStorage {
     }
 }
 
+/// Object streamed from GCS using [`GoogleCloudStorage::stream_raw()`].
+pub struct GcsObjectStream {
+    /// CRC32C checksum of the object reported in its metadata, if any.
+    pub crc32c: Option<u32>,
+    /// Object size in bytes reported in its metadata.
+    pub size: u64,
+    /// Object contents.
+    pub reader: Box<dyn AsyncRead + Send + Unpin>,
+}
+
+impl fmt::Debug for GcsObjectStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("GcsObjectStream")
+            .field("crc32c", &self.crc32c)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reader checking that the wrapped stream has exactly the expected number of bytes. Guards against backends
+/// (e.g., misbehaving proxies) returning the full object instead of the requested range, or truncating the response.
+struct LengthCheckingReader<R> {
+    inner: R,
+    expected_len: u64,
+    read_len: u64,
+}
+
+impl<R> LengthCheckingReader<R> {
+    fn new(inner: R, expected_len: u64) -> Self {
+        Self {
+            inner,
+            expected_len,
+            read_len: 0,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LengthCheckingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled_len = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        let new_len = (buf.filled().len() - filled_len) as u64;
+        this.read_len += new_len;
+
+        if this.read_len > this.expected_len {
+            let err = format!(
+                "GCS returned more bytes than requested: expected {}, got at least {}",
+                this.expected_len, this.read_len
+            );
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, err)));
+        }
+        if new_len == 0 && buf.remaining() > 0 && this.read_len < this.expected_len {
+            let err = format!(
+                "GCS returned fewer bytes than requested: expected {}, got {}",
+                this.expected_len, this.read_len
+            );
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, err)));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum GoogleCloudStorageAuthMode {
     AuthenticatedWithCredentialFile(String),
@@ -106,6 +185,72 @@ impl GoogleCloudStorage {
         format!("{bucket}/{filename}")
     }
 
+    /// Starts streaming the object with the specified key. Unlike [`ObjectStore::get_raw()`], the object
+    /// is not buffered in memory; it is returned together with metadata (the CRC32C checksum and size)
+    /// of the same object generation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object doesn't exist or its metadata cannot be fetched. Errors occurring
+    /// while streaming are returned by the reader; in particular, the reader returns an error if the number
+    /// of streamed bytes differs from the object size.
+    pub async fn stream_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+    ) -> Result<GcsObjectStream, ObjectStoreError> {
+        let filename = Self::filename(bucket.as_str(), key);
+        tracing::trace!(
+            "Streaming data from GCS for key {filename} from bucket {}",
+            self.bucket_prefix
+        );
+
+        let request = GetObjectRequest {
+            bucket: self.bucket_prefix.clone(),
+            object: filename,
+            ..GetObjectRequest::default()
+        };
+        let object = retry(self.max_retries, || self.client.get_object(&request)).await?;
+        let crc32c = object
+            .crc32c
+            .as_deref()
+            .map(Self::decode_crc32c)
+            .transpose()?;
+        let size = u64::try_from(object.size).map_err(|err| ObjectStoreError::Other(err.into()))?;
+
+        // Pin the generation, so that the streamed contents correspond to the fetched metadata
+        // even if the object is overwritten concurrently.
+        let request = GetObjectRequest {
+            generation: Some(object.generation),
+            ..request
+        };
+        // The entire object is requested, so the streamed contents must have exactly `size` bytes.
+        let range = Range::default();
+        let stream = retry(self.max_retries, || {
+            self.client.download_streamed_object(&request, &range)
+        })
+        .await?;
+        let stream = stream.map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+        let reader = LengthCheckingReader::new(StreamReader::new(Box::pin(stream)), size);
+        Ok(GcsObjectStream {
+            crc32c,
+            size,
+            reader: Box::new(reader),
+        })
+    }
+
+    /// Decodes a CRC32C checksum from object metadata, where it's encoded as base64 in the big-endian byte order.
+    fn decode_crc32c(encoded: &str) -> Result<u32, ObjectStoreError> {
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|err| ObjectStoreError::Other(err.into()))?;
+        let bytes = <[u8; 4]>::try_from(bytes.as_slice()).map_err(|_| {
+            let err = format!("unexpected CRC32C checksum length: {}", bytes.len());
+            ObjectStoreError::Other(err.into())
+        })?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
     // For some bizarre reason, `async fn` doesn't work here, failing with the following error:
     //
     // > hidden type for `impl std::future::Future<Output = Result<(), ObjectStoreError>>`
@@ -215,6 +360,17 @@ impl ObjectStore for GoogleCloudStorage {
         self.remove_inner(bucket.as_str(), key).await
     }
 
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let filename = Self::filename(bucket.as_str(), key);
+        let request = GetObjectRequest {
+            bucket: self.bucket_prefix.clone(),
+            object: filename,
+            ..GetObjectRequest::default()
+        };
+        let object = retry(self.max_retries, || self.client.get_object(&request)).await?;
+        u64::try_from(object.size).map_err(|err| ObjectStoreError::Other(err.into()))
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String {
         format!(
             "https://storage.googleapis.com/{}/{}",
@@ -228,8 +384,55 @@ impl ObjectStore for GoogleCloudStorage {
 mod test {
     use std::sync::atomic::{AtomicU16, Ordering};
 
+    use tokio::io::AsyncReadExt;
+
     use super::*;
 
+    #[test]
+    fn decoding_crc32c() {
+        // CRC32C of an empty object as reported by GCS.
+        assert_eq!(GoogleCloudStorage::decode_crc32c("AAAAAA==").unwrap(), 0);
+        // CRC32C of `123456789`.
+        assert_eq!(
+            GoogleCloudStorage::decode_crc32c("4waSgw==").unwrap(),
+            0xe306_9283
+        );
+        assert!(GoogleCloudStorage::decode_crc32c("AAA=").is_err());
+    }
+
+    #[tokio::test]
+    async fn length_checking_reader_accepts_expected_length() {
+        let mut reader = LengthCheckingReader::new(&b"123456789"[..], 9);
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, b"123456789");
+    }
+
+    #[tokio::test]
+    async fn length_checking_reader_rejects_full_object_instead_of_range() {
+        // Emulates a backend ignoring the requested 4-byte range and returning the full object.
+        let mut reader = LengthCheckingReader::new(&b"123456789"[..], 4);
+        let mut buffer = vec![];
+        let err = reader.read_to_end(&mut buffer).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string().contains("more bytes than requested"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn length_checking_reader_rejects_truncated_object() {
+        let mut reader = LengthCheckingReader::new(&b"1234"[..], 9);
+        let mut buffer = vec![];
+        let err = reader.read_to_end(&mut buffer).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(
+            err.to_string().contains("fewer bytes than requested"),
+            "{err}"
+        );
+    }
+
     #[tokio::test]
     async fn test_retry_success_immediate() {
         let result = retry(2, || async { Ok::<_, &'static str>(42) }).await;
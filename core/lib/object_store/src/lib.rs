@@ -4,8 +4,9 @@
 //! - File-based storage saving blobs as separate files in the local filesystem
 //! - GCS-based storage
 //!
-//! These implementations are not exposed externally. Instead, a store trait object
-//! can be constructed using an [`ObjectStoreFactory`] based on the configuration.
+//! These implementations are generally not exposed externally. Instead, a store trait object
+//! can be constructed using an [`ObjectStoreFactory`] based on the configuration. The GCS-based
+//! implementation is exposed to allow streaming objects (see [`GoogleCloudStorage::stream_raw()`]).
 //! The configuration can be provided explicitly (see [`ObjectStoreFactory::new()`])
 //! or obtained from the environment (see [`ObjectStoreFactory::from_env()`]).
 //!
@@ -39,6 +40,7 @@ pub mod _reexports {
 }
 
 pub use self::{
+    gcs::{GcsObjectStream, GoogleCloudStorage, GoogleCloudStorageAuthMode},
     objects::StoredObject,
     raw::{Bucket, ObjectStore, ObjectStoreError, ObjectStoreFactory},
 };
@@ -46,6 +46,15 @@ impl ObjectStore for MockStore {
         Ok(())
     }
 
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let lock = self.inner.lock().await;
+        let maybe_bytes = lock.get(&bucket).and_then(|bucket_map| bucket_map.get(key));
+        maybe_bytes.map(|bytes| bytes.len() as u64).ok_or_else(|| {
+            let error_message = format!("missing key: {key} in bucket {bucket}");
+            ObjectStoreError::KeyNotFound(error_message.into())
+        })
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String {
         bucket.to_string()
     }
@@ -142,6 +142,20 @@ impl dyn ObjectStore + '_ {
         V::deserialize(bytes).map_err(ObjectStoreError::Serialization)
     }
 
+    /// Returns the size in bytes of the serialized value for the given key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an object with the `key` does not exist or cannot be accessed, or if the store
+    /// doesn't support getting object sizes.
+    pub async fn get_size<V: StoredObject>(
+        &self,
+        key: V::Key<'_>,
+    ) -> Result<u64, ObjectStoreError> {
+        let key = V::encode_key(key);
+        self.get_size_raw(V::BUCKET, &key).await
+    }
+
     /// Stores the value associating it with the key. If the key already exists,
     /// the value is replaced.
     ///
@@ -159,6 +173,27 @@ impl dyn ObjectStore + '_ {
         Ok(key)
     }
 
+    /// Removes values associated with the specified `keys` from the given bucket on a best-effort basis.
+    /// Unlike removing keys one by one with [`ObjectStore::remove_raw()`] and propagating the first error,
+    /// a failed removal doesn't prevent the remaining keys from being removed.
+    ///
+    /// Returns keys that could not be removed together with the corresponding errors. If all removals
+    /// succeed, the returned `Vec` is empty.
+    pub async fn remove_many<'k>(
+        &self,
+        bucket: Bucket,
+        keys: impl IntoIterator<Item = &'k str>,
+    ) -> Vec<(String, ObjectStoreError)> {
+        let mut failed_keys = vec![];
+        for key in keys {
+            if let Err(err) = self.remove_raw(bucket, key).await {
+                tracing::warn!("Failed removing key {key} from bucket {bucket}: {err}");
+                failed_keys.push((key.to_owned(), err));
+            }
+        }
+        failed_keys
+    }
+
     pub fn get_storage_prefix<V: StoredObject>(&self) -> String {
         self.storage_prefix_raw(V::BUCKET)
     }
@@ -167,12 +202,12 @@ impl dyn ObjectStore + '_ {
 #[cfg(test)]
 mod tests {
     use zksync_types::{
-        snapshots::{SnapshotFactoryDependency, SnapshotStorageLog},
+        snapshots::{SnapshotFactoryDependency, SnapshotStorageLog, SnapshotStorageLogProof},
         AccountTreeId, Bytes, StorageKey, H160, H256,
     };
 
     use super::*;
-    use crate::ObjectStoreFactory;
+    use crate::{mock::MockStore, ObjectStoreFactory};
 
     #[test]
     fn test_storage_logs_filesnames_generate_corretly() {
@@ -224,6 +259,10 @@ mod tests {
                     enumeration_index: 456,
                 },
             ],
+            proofs: vec![SnapshotStorageLogProof {
+                storage_log_index: 1,
+                merkle_path: vec![H256::random(), H256::random()],
+            }],
         };
         store.put(key, &storage_logs).await.unwrap();
         let reconstructed_storage_logs = store.get(key).await.unwrap();
@@ -248,4 +287,70 @@ mod tests {
         let reconstructed_factory_deps = store.get(key).await.unwrap();
         assert_eq!(factory_deps, reconstructed_factory_deps);
     }
+
+    /// Store failing to remove a single key.
+    #[derive(Debug, Default)]
+    struct StoreWithRemovalError {
+        inner: MockStore,
+    }
+
+    impl StoreWithRemovalError {
+        const FAILING_KEY: &'static str = "failing";
+    }
+
+    #[async_trait::async_trait]
+    impl ObjectStore for StoreWithRemovalError {
+        async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+            self.inner.get_raw(bucket, key).await
+        }
+
+        async fn put_raw(
+            &self,
+            bucket: Bucket,
+            key: &str,
+            value: Vec<u8>,
+        ) -> Result<(), ObjectStoreError> {
+            self.inner.put_raw(bucket, key, value).await
+        }
+
+        async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+            if key == Self::FAILING_KEY {
+                return Err(ObjectStoreError::Other("removal failed".into()));
+            }
+            self.inner.remove_raw(bucket, key).await
+        }
+
+        fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+            self.inner.storage_prefix_raw(bucket)
+        }
+    }
+
+    #[tokio::test]
+    async fn removing_many_keys_with_partial_failure() {
+        let store: &dyn ObjectStore = &StoreWithRemovalError::default();
+        let keys = ["first", StoreWithRemovalError::FAILING_KEY, "last"];
+        for key in keys {
+            store
+                .put_raw(Bucket::StorageSnapshot, key, vec![1, 2, 3])
+                .await
+                .unwrap();
+        }
+
+        let failed_keys = store.remove_many(Bucket::StorageSnapshot, keys).await;
+        assert_eq!(failed_keys.len(), 1);
+        assert_eq!(failed_keys[0].0, StoreWithRemovalError::FAILING_KEY);
+        assert!(matches!(failed_keys[0].1, ObjectStoreError::Other(_)));
+
+        for key in ["first", "last"] {
+            let err = store
+                .get_raw(Bucket::StorageSnapshot, key)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ObjectStoreError::KeyNotFound(_)));
+        }
+        store
+            .get_raw(Bucket::StorageSnapshot, StoreWithRemovalError::FAILING_KEY)
+            .await
+            .unwrap();
+    }
 }
@@ -62,6 +62,8 @@ pub enum ObjectStoreError {
     Serialization(BoxedError),
     /// Other error has occurred when accessing the store (e.g., a network error).
     Other(BoxedError),
+    /// Operation is not supported by the store (e.g., writing to a read-only store).
+    Unsupported(BoxedError),
 }
 
 impl fmt::Display for ObjectStoreError {
@@ -70,6 +72,7 @@ impl fmt::Display for ObjectStoreError {
             Self::KeyNotFound(err) => write!(formatter, "key not found: {err}"),
             Self::Serialization(err) => write!(formatter, "serialization error: {err}"),
             Self::Other(err) => write!(formatter, "other error: {err}"),
+            Self::Unsupported(err) => write!(formatter, "unsupported operation: {err}"),
         }
     }
 }
@@ -77,9 +80,10 @@ impl fmt::Display for ObjectStoreError {
 impl error::Error for ObjectStoreError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
-            Self::KeyNotFound(err) | Self::Serialization(err) | Self::Other(err) => {
-                Some(err.as_ref())
-            }
+            Self::KeyNotFound(err)
+            | Self::Serialization(err)
+            | Self::Other(err)
+            | Self::Unsupported(err) => Some(err.as_ref()),
         }
     }
 }
@@ -120,6 +124,17 @@ pub trait ObjectStore: 'static + fmt::Debug + Send + Sync {
     /// Returns an error if removal fails.
     async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError>;
 
+    /// Returns the size of the object with the given key in bytes without fetching the object.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an object with the `key` does not exist or cannot be accessed.
+    /// The default implementation returns [`ObjectStoreError::Unsupported`].
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let err = format!("getting size of `{bucket}/{key}` is not supported by {self:?}");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String;
 }
 
@@ -142,6 +157,10 @@ impl<T: ObjectStore + ?Sized> ObjectStore for Arc<T> {
         (**self).remove_raw(bucket, key).await
     }
 
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        (**self).get_size_raw(bucket, key).await
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String {
         (**self).storage_prefix_raw(bucket)
     }
@@ -222,7 +241,11 @@ impl ObjectStoreFactory {
             }
             ObjectStoreMode::FileBacked => {
                 tracing::trace!("Initialized FileBacked Object store");
-                let store = FileBackedObjectStore::new(config.file_backed_base_path.clone()).await;
+                let store = FileBackedObjectStore::new(
+                    config.file_backed_base_path.clone(),
+                    config.file_backed_max_open_files,
+                )
+                .await;
                 Arc::new(store)
             }
             ObjectStoreMode::GCSAnonymousReadOnly => {
@@ -45,6 +45,11 @@ impl ProtoRepr for proto::ObjectStore {
             max_retries: required(&self.max_retries)
                 .and_then(|x| Ok((*x).try_into()?))
                 .context("max_retries")?,
+            file_backed_max_open_files: self
+                .file_backed_max_open_files
+                .map(|x| x.try_into())
+                .transpose()
+                .context("file_backed_max_open_files")?,
         })
     }
 
@@ -55,6 +60,9 @@ impl ProtoRepr for proto::ObjectStore {
             file_backed_base_path: Some(this.file_backed_base_path.clone()),
             gcs_credential_file_path: Some(this.gcs_credential_file_path.clone()),
             max_retries: Some(this.max_retries.into()),
+            file_backed_max_open_files: this
+                .file_backed_max_open_files
+                .map(|x| x.try_into().unwrap()),
         }
     }
 }
@@ -0,0 +1,70 @@
+//! Acceptance queries run against the recovered storage.
+
+use std::ops::RangeInclusive;
+
+use anyhow::Context as _;
+use zksync_dal::StorageProcessor;
+use zksync_types::{MiniblockNumber, StorageKey, StorageValue};
+
+/// Query run against the recovered storage before recovery is declared successful. Acceptance queries
+/// are smoke tests catching recovery issues that cannot be detected by the applier itself (e.g., recovering
+/// from a snapshot of a wrong network).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcceptanceQuery {
+    /// Checks that the storage slot with the specified key has the expected value as of the snapshot miniblock.
+    /// A missing slot is considered to have the zero value.
+    StorageValue {
+        key: StorageKey,
+        expected_value: StorageValue,
+    },
+    /// Checks that the total number of recovered storage logs is in the specified range.
+    StorageLogCount { range: RangeInclusive<u64> },
+}
+
+impl AcceptanceQuery {
+    /// Runs this query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or returns an unexpected result.
+    pub(crate) async fn run(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        miniblock_number: MiniblockNumber,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::StorageValue {
+                key,
+                expected_value,
+            } => {
+                let hashed_key = key.hashed_key();
+                let values = storage
+                    .storage_logs_dal()
+                    .get_storage_values(&[hashed_key], miniblock_number)
+                    .await
+                    .context("failed fetching storage value")?;
+                let value = values
+                    .get(&hashed_key)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default();
+                anyhow::ensure!(
+                    value == *expected_value,
+                    "storage slot {key:?} has value {value:?}, while {expected_value:?} was expected"
+                );
+            }
+            Self::StorageLogCount { range } => {
+                let count = storage
+                    .storage_logs_dal()
+                    .count_miniblock_storage_logs(miniblock_number)
+                    .await
+                    .context("failed counting storage logs")?;
+                anyhow::ensure!(
+                    range.contains(&count),
+                    "{count} storage logs are recovered, while the expected number is in {range:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Manifests of data applied from a snapshot, allowing to audit recovery after the fact.
+
+use std::{fs, path::Path};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use zksync_types::{
+    snapshots::SnapshotStorageLog, web3::signing::keccak256, L1BatchNumber, MiniblockNumber, H256,
+};
+
+use crate::storage_logs_checksum;
+
+/// Entry for a single storage logs chunk in an [`AppliedManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedChunk {
+    pub chunk_id: u64,
+    pub storage_log_count: u64,
+    /// Checksum of the storage logs in the chunk (in the order they are included into the chunk)
+    /// computed with [`storage_logs_checksum()`].
+    pub digest: H256,
+}
+
+impl AppliedChunk {
+    /// Creates an entry for the specified chunk computing its digest using up to `parallelism` threads.
+    /// The digest doesn't depend on `parallelism`.
+    pub fn new(chunk_id: u64, storage_logs: &[SnapshotStorageLog], parallelism: usize) -> Self {
+        Self {
+            chunk_id,
+            storage_log_count: storage_logs.len() as u64,
+            digest: storage_logs_checksum(storage_logs, parallelism),
+        }
+    }
+}
+
+/// Manifest listing data applied during snapshot recovery. The manifest is sealed with a digest covering
+/// all its other fields, so it can be checked for integrity using [`Self::verify()`] when audited later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedManifest {
+    pub l1_batch_number: L1BatchNumber,
+    pub miniblock_number: MiniblockNumber,
+    /// Checksum of the recovered state; see [`state_checksum()`](crate::state_checksum()).
+    pub state_checksum: H256,
+    /// Applied storage logs chunks ordered by their IDs.
+    pub chunks: Vec<AppliedChunk>,
+    /// Keccak-256 digest of all other fields of the manifest.
+    pub digest: H256,
+}
+
+impl AppliedManifest {
+    /// Creates a sealed manifest. `chunks` are sorted by their IDs.
+    pub fn new(
+        l1_batch_number: L1BatchNumber,
+        miniblock_number: MiniblockNumber,
+        state_checksum: H256,
+        mut chunks: Vec<AppliedChunk>,
+    ) -> Self {
+        chunks.sort_unstable_by_key(|chunk| chunk.chunk_id);
+        let mut this = Self {
+            l1_batch_number,
+            miniblock_number,
+            state_checksum,
+            chunks,
+            digest: H256::zero(),
+        };
+        this.digest = this.compute_digest();
+        this
+    }
+
+    fn compute_digest(&self) -> H256 {
+        let mut buffer = Vec::with_capacity(4 + 4 + 32 + self.chunks.len() * (8 + 8 + 32));
+        buffer.extend_from_slice(&self.l1_batch_number.0.to_be_bytes());
+        buffer.extend_from_slice(&self.miniblock_number.0.to_be_bytes());
+        buffer.extend_from_slice(self.state_checksum.as_bytes());
+        for chunk in &self.chunks {
+            buffer.extend_from_slice(&chunk.chunk_id.to_be_bytes());
+            buffer.extend_from_slice(&chunk.storage_log_count.to_be_bytes());
+            buffer.extend_from_slice(chunk.digest.as_bytes());
+        }
+        H256(keccak256(&buffer))
+    }
+
+    /// Checks that the manifest digest matches its contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest was modified after it was sealed.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let expected_digest = self.compute_digest();
+        anyhow::ensure!(
+            self.digest == expected_digest,
+            "manifest digest {:?} doesn't match its contents (expected {expected_digest:?})",
+            self.digest
+        );
+        Ok(())
+    }
+
+    /// Reads a manifest from the specified JSON file. The manifest is not verified.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O and deserialization errors.
+    pub fn read_from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read(path)
+            .with_context(|| format!("failed reading manifest from `{}`", path.display()))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("failed deserializing manifest from `{}`", path.display()))
+    }
+
+    /// Writes the manifest to the specified file as JSON, overwriting the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O errors.
+    pub fn write_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_vec_pretty(self).context("failed serializing manifest")?;
+        fs::write(path, contents)
+            .with_context(|| format!("failed writing manifest to `{}`", path.display()))
+    }
+}
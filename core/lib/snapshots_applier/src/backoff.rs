@@ -0,0 +1,70 @@
+//! Backoff strategies for retries.
+
+use std::{fmt, time::Duration};
+
+use rand::Rng;
+
+/// Strategy determining delays between retries of a failed operation.
+pub trait BackoffStrategy: fmt::Debug + Send + Sync {
+    /// Returns the delay before the retry with the specified number. Retries are numbered starting from 1.
+    fn retry_delay(&self, retry_number: usize) -> Duration;
+}
+
+impl<T: BackoffStrategy + ?Sized> BackoffStrategy for &T {
+    fn retry_delay(&self, retry_number: usize) -> Duration {
+        (**self).retry_delay(retry_number)
+    }
+}
+
+/// Exponential backoff: the delay is multiplied by a constant factor after each retry. Optionally,
+/// each delay is randomly reduced by up to the specified fraction so that retries from multiple nodes
+/// are spread in time.
+#[derive(Debug, Clone, Copy)]
+pub struct ExponentialBackoff {
+    initial_delay: Duration,
+    multiplier: f32,
+    jitter: f32,
+}
+
+impl ExponentialBackoff {
+    /// Jitter used by the snapshot applier by default.
+    pub(crate) const DEFAULT_JITTER: f32 = 0.1;
+
+    /// Creates a backoff without jitter.
+    pub fn new(initial_delay: Duration, multiplier: f32) -> Self {
+        Self {
+            initial_delay,
+            multiplier,
+            jitter: 0.0,
+        }
+    }
+
+    /// Sets the maximum fraction (from 0 to 1) by which each delay is randomly reduced.
+    pub fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl BackoffStrategy for ExponentialBackoff {
+    fn retry_delay(&self, retry_number: usize) -> Duration {
+        let exponent = i32::try_from(retry_number.saturating_sub(1)).unwrap_or(i32::MAX);
+        let mut delay_secs =
+            self.initial_delay.as_secs_f64() * f64::from(self.multiplier).powi(exponent);
+        if self.jitter > 0.0 {
+            let reduction = rand::thread_rng().gen_range(0.0..=f64::from(self.jitter));
+            delay_secs *= 1.0 - reduction;
+        }
+        Duration::try_from_secs_f64(delay_secs).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Backoff with the same delay before each retry.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantBackoff(pub Duration);
+
+impl BackoffStrategy for ConstantBackoff {
+    fn retry_delay(&self, _retry_number: usize) -> Duration {
+        self.0
+    }
+}
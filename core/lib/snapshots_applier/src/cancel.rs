@@ -0,0 +1,117 @@
+//! Reasons of cancelling snapshot recovery.
+
+use std::{fmt, time::Duration};
+
+use zksync_types::{L1BatchNumber, H256};
+
+use crate::SnapshotRecoveryStalled;
+
+/// Reason of cancelling snapshot recovery before it's completed. Use [`Self::from_error()`] to extract
+/// the reason from an error returned by [`SnapshotsApplierConfig::run()`].
+///
+/// [`SnapshotsApplierConfig::run()`]: crate::SnapshotsApplierConfig::run()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancellationReason {
+    /// Recovery didn't finish in [`SnapshotsApplierConfig::max_recovery_duration`].
+    ///
+    /// [`SnapshotsApplierConfig::max_recovery_duration`]: crate::SnapshotsApplierConfig::max_recovery_duration
+    Deadline { max_recovery_duration: Duration },
+    /// No storage log chunks were processed during [`SnapshotsApplierConfig::stall_timeout`], and recovery
+    /// ran out of retries.
+    ///
+    /// [`SnapshotsApplierConfig::stall_timeout`]: crate::SnapshotsApplierConfig::stall_timeout
+    Stalled { stall_timeout: Duration },
+    /// The main node started returning a different root hash for the snapshot L1 batch
+    /// (see [`SnapshotsApplierConfig::main_node_head_check_interval`]).
+    ///
+    /// [`SnapshotsApplierConfig::main_node_head_check_interval`]: crate::SnapshotsApplierConfig::main_node_head_check_interval
+    MainNodeDiverged {
+        l1_batch_number: L1BatchNumber,
+        expected_root_hash: H256,
+        root_hash: H256,
+    },
+    /// The main node consistently doesn't report a root hash for the snapshot L1 batch
+    /// (see [`SnapshotsApplierConfig::main_node_head_check_interval`]).
+    ///
+    /// [`SnapshotsApplierConfig::main_node_head_check_interval`]: crate::SnapshotsApplierConfig::main_node_head_check_interval
+    MainNodeMissingL1Batch { l1_batch_number: L1BatchNumber },
+    /// Recovery was not retried because [`SnapshotsApplierConfig::retry_budget`] is exhausted.
+    ///
+    /// [`SnapshotsApplierConfig::retry_budget`]: crate::SnapshotsApplierConfig::retry_budget
+    RetryBudgetExhausted,
+    /// Shutdown was requested via [`SnapshotsApplierConfig::stop_receiver`]. Storage log chunks committed during
+    /// the grace period are kept, so recovery can be resumed.
+    ///
+    /// [`SnapshotsApplierConfig::stop_receiver`]: crate::SnapshotsApplierConfig::stop_receiver
+    Shutdown { grace_period: Duration },
+}
+
+impl fmt::Display for CancellationReason {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deadline {
+                max_recovery_duration,
+            } => write!(
+                formatter,
+                "snapshot recovery didn't finish in {max_recovery_duration:?}"
+            ),
+            Self::Stalled { stall_timeout } => write!(
+                formatter,
+                "no storage log chunks were processed in {stall_timeout:?}"
+            ),
+            Self::MainNodeDiverged {
+                l1_batch_number,
+                expected_root_hash,
+                root_hash,
+            } => write!(
+                formatter,
+                "main node returns root hash {root_hash:?} for snapshot L1 batch #{l1_batch_number}, \
+                 while recovery was started with {expected_root_hash:?}; the main node has probably reorged"
+            ),
+            Self::MainNodeMissingL1Batch { l1_batch_number } => write!(
+                formatter,
+                "main node doesn't return root hash for snapshot L1 batch #{l1_batch_number}; \
+                 the main node has probably reverted the batch"
+            ),
+            Self::RetryBudgetExhausted => formatter.write_str("retry budget is exhausted"),
+            Self::Shutdown { grace_period } => write!(
+                formatter,
+                "shutdown was requested (grace period for in-flight storage log chunks: {grace_period:?})"
+            ),
+        }
+    }
+}
+
+impl CancellationReason {
+    /// Extracts the cancellation reason from an error returned by the snapshot applier. Returns `None`
+    /// if recovery failed for another reason (e.g., because of a corrupted snapshot).
+    pub fn from_error(err: &anyhow::Error) -> Option<Self> {
+        if let Some(cancelled) = err.downcast_ref::<SnapshotRecoveryCancelled>() {
+            return Some(cancelled.reason.clone());
+        }
+        err.chain().find_map(|cause| {
+            if let Some(cancelled) = cause.downcast_ref::<SnapshotRecoveryCancelled>() {
+                Some(cancelled.reason.clone())
+            } else {
+                let stalled = cause.downcast_ref::<SnapshotRecoveryStalled>()?;
+                Some(Self::Stalled {
+                    stall_timeout: stalled.stall_timeout,
+                })
+            }
+        })
+    }
+}
+
+/// Error returned if snapshot recovery is cancelled. The cancellation reason can be extracted from
+/// the applier error using [`CancellationReason::from_error()`].
+#[derive(Debug, thiserror::Error)]
+#[error("snapshot recovery cancelled: {reason}")]
+pub struct SnapshotRecoveryCancelled {
+    pub reason: CancellationReason,
+}
+
+impl From<CancellationReason> for SnapshotRecoveryCancelled {
+    fn from(reason: CancellationReason) -> Self {
+        Self { reason }
+    }
+}
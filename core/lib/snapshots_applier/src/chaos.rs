@@ -0,0 +1,132 @@
+//! Failure injection for chaos testing of snapshot recovery.
+
+use async_trait::async_trait;
+use rand::Rng;
+use zksync_object_store::{Bucket, ObjectStoreError};
+use zksync_types::{
+    api::en::SyncBlock, snapshots::SnapshotHeader, tokens::TokenInfo, L1BatchNumber,
+    MiniblockNumber, StorageKey, StorageValue, H256,
+};
+use zksync_web3_decl::jsonrpsee::core::{client::Error, ClientError as RpcError};
+
+use crate::{SnapshotsApplierError, SnapshotsApplierMainNodeClient};
+
+/// Probabilistic failure injection used to validate recovery resilience, e.g. in staging environments.
+/// Injected failures are transient, so they are retried like real transient failures. By default,
+/// no failures are injected.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FailureInjection {
+    /// Probability (from 0 to 1) that an object store read fails.
+    pub object_store_read_failure_probability: f64,
+    /// Probability (from 0 to 1) that a main node RPC call fails.
+    pub rpc_failure_probability: f64,
+    /// Probability (from 0 to 1) that persisting a storage logs chunk to Postgres fails.
+    pub db_insert_failure_probability: f64,
+    /// Probability (from 0 to 1) that committing the DB transaction for a storage logs chunk fails.
+    /// The failure is injected after storage logs sinks are written to, so chunks are re-delivered to sinks.
+    pub db_commit_failure_probability: f64,
+}
+
+impl FailureInjection {
+    fn should_fail(probability: f64) -> bool {
+        probability > 0.0 && rand::thread_rng().gen_bool(probability.min(1.0))
+    }
+
+    pub(crate) fn object_store_read(
+        &self,
+        bucket: Bucket,
+        key: &str,
+    ) -> Result<(), ObjectStoreError> {
+        if Self::should_fail(self.object_store_read_failure_probability) {
+            let err = format!("injected failure reading `{bucket}/{key}`");
+            return Err(ObjectStoreError::Other(err.into()));
+        }
+        Ok(())
+    }
+
+    fn rpc_call(&self, method: &str) -> Result<(), RpcError> {
+        if Self::should_fail(self.rpc_failure_probability) {
+            tracing::debug!("Injected failure for `{method}` RPC call");
+            return Err(Error::RequestTimeout);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn db_insert(&self, chunk_id: u64) -> Result<(), SnapshotsApplierError> {
+        if Self::should_fail(self.db_insert_failure_probability) {
+            let err = anyhow::anyhow!("injected failure persisting storage logs chunk {chunk_id}");
+            return Err(SnapshotsApplierError::Retryable(err));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn db_commit(&self, chunk_id: u64) -> Result<(), SnapshotsApplierError> {
+        if Self::should_fail(self.db_commit_failure_probability) {
+            let err = anyhow::anyhow!(
+                "injected failure committing DB transaction for storage logs chunk {chunk_id}"
+            );
+            return Err(SnapshotsApplierError::Retryable(err));
+        }
+        Ok(())
+    }
+}
+
+/// Main node client wrapper injecting RPC failures.
+#[derive(Debug)]
+pub(crate) struct FailingMainNodeClient<'a> {
+    inner: &'a dyn SnapshotsApplierMainNodeClient,
+    failure_injection: FailureInjection,
+}
+
+impl<'a> FailingMainNodeClient<'a> {
+    pub fn new(
+        inner: &'a dyn SnapshotsApplierMainNodeClient,
+        failure_injection: FailureInjection,
+    ) -> Self {
+        Self {
+            inner,
+            failure_injection,
+        }
+    }
+}
+
+#[async_trait]
+impl SnapshotsApplierMainNodeClient for FailingMainNodeClient<'_> {
+    async fn fetch_l2_block(&self, number: MiniblockNumber) -> Result<Option<SyncBlock>, RpcError> {
+        self.failure_injection.rpc_call("fetch_l2_block")?;
+        self.inner.fetch_l2_block(number).await
+    }
+
+    async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError> {
+        self.failure_injection.rpc_call("fetch_newest_snapshot")?;
+        self.inner.fetch_newest_snapshot().await
+    }
+
+    async fn fetch_tokens(
+        &self,
+        at_miniblock: MiniblockNumber,
+    ) -> Result<Vec<TokenInfo>, RpcError> {
+        self.failure_injection.rpc_call("fetch_tokens")?;
+        self.inner.fetch_tokens(at_miniblock).await
+    }
+
+    async fn fetch_storage_value_at(
+        &self,
+        key: &StorageKey,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<StorageValue, RpcError> {
+        self.failure_injection.rpc_call("fetch_storage_value_at")?;
+        self.inner
+            .fetch_storage_value_at(key, miniblock_number)
+            .await
+    }
+
+    async fn fetch_l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> Result<Option<H256>, RpcError> {
+        self.failure_injection
+            .rpc_call("fetch_l1_batch_root_hash")?;
+        self.inner.fetch_l1_batch_root_hash(number).await
+    }
+}
@@ -0,0 +1,132 @@
+//! Order-dependent checksums of storage logs.
+
+use std::thread;
+
+use zksync_dal::{SqlxError, StorageProcessor};
+use zksync_types::{
+    snapshots::SnapshotStorageLog, web3::signing::keccak256, MiniblockNumber, H256,
+};
+
+/// Number of consecutive storage logs hashed together into a single tree leaf.
+const LEAF_LOG_COUNT: usize = 4_096;
+/// Length of a storage log encoding hashed into a leaf.
+const ENCODED_LOG_LEN: usize = 32 + 32 + 8 + 4;
+/// Number of consecutive storage logs in a segment of the state checksum (see [`state_checksum()`]).
+pub(crate) const STATE_CHECKSUM_SEGMENT_LEN: usize = 16 * LEAF_LOG_COUNT;
+
+/// Computes an order-dependent checksum of `storage_logs` using up to `parallelism` threads.
+///
+/// The checksum is a tree hash: consecutive logs are split into leaves of a fixed size, each leaf is hashed
+/// with Keccak-256 over the encodings of its logs (hashed key, value, enumeration index and the L1 batch
+/// of the initial write), and leaf hashes are folded pairwise into the root (an unpaired node at the end
+/// of a level is promoted to the next level as is). Since the tree shape only depends on the number of logs,
+/// the checksum is identical for any `parallelism`; leaves are distributed among threads in contiguous ranges.
+/// The checksum of an empty slice is zero.
+pub fn storage_logs_checksum(storage_logs: &[SnapshotStorageLog], parallelism: usize) -> H256 {
+    let leaves: Vec<_> = storage_logs.chunks(LEAF_LOG_COUNT).collect();
+    let parallelism = parallelism.clamp(1, leaves.len().max(1));
+    let mut level = if parallelism == 1 {
+        leaves.into_iter().map(hash_leaf).collect()
+    } else {
+        let leaves_per_thread = leaves.len().div_ceil(parallelism);
+        thread::scope(|scope| {
+            let handles: Vec<_> = leaves
+                .chunks(leaves_per_thread)
+                .map(|leaves| {
+                    scope.spawn(|| leaves.iter().copied().map(hash_leaf).collect::<Vec<_>>())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("checksum thread panicked"))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    fold_checksums(level)
+}
+
+/// Combines checksums of consecutive storage log segments (e.g., computed with [`storage_logs_checksum()`])
+/// into a single order-dependent checksum. Checksums are folded pairwise in the same way as tree leaves
+/// in [`storage_logs_checksum()`]; the combined checksum of an empty slice is zero.
+pub fn combine_checksums(checksums: &[H256]) -> H256 {
+    fold_checksums(checksums.to_vec())
+}
+
+/// Computes the checksum of the entire state, i.e. all storage logs sorted by the hashed key, using up to
+/// `parallelism` threads. Logs are split into segments of a fixed size, each segment is hashed with
+/// [`storage_logs_checksum()`], and segment checksums are combined with [`combine_checksums()`], so the checksum
+/// can be computed incrementally while streaming logs (e.g., from Postgres). As with [`storage_logs_checksum()`],
+/// the checksum doesn't depend on `parallelism`.
+pub fn state_checksum(sorted_storage_logs: &[SnapshotStorageLog], parallelism: usize) -> H256 {
+    let segment_checksums: Vec<_> = sorted_storage_logs
+        .chunks(STATE_CHECKSUM_SEGMENT_LEN)
+        .map(|segment| storage_logs_checksum(segment, parallelism))
+        .collect();
+    combine_checksums(&segment_checksums)
+}
+
+/// Computes [`state_checksum()`] of the state recovered into Postgres for the specified miniblock, i.e. of storage logs
+/// together with their initial writes. Logs are streamed from Postgres one segment at a time.
+pub(crate) async fn recovered_state_checksum(
+    storage: &mut StorageProcessor<'_>,
+    miniblock_number: MiniblockNumber,
+    parallelism: usize,
+) -> Result<H256, SqlxError> {
+    let mut segment_checksums = vec![];
+    let mut last_hashed_key = None;
+    loop {
+        let segment = storage
+            .storage_logs_dal()
+            .get_recovered_storage_logs(
+                miniblock_number,
+                last_hashed_key,
+                STATE_CHECKSUM_SEGMENT_LEN,
+            )
+            .await?;
+        let Some((hashed_key, _)) = segment.last() else {
+            break;
+        };
+        last_hashed_key = Some(*hashed_key);
+        let is_last_segment = segment.len() < STATE_CHECKSUM_SEGMENT_LEN;
+        let segment: Vec<_> = segment.into_iter().map(|(_, log)| log).collect();
+        segment_checksums.push(storage_logs_checksum(&segment, parallelism));
+        if is_last_segment {
+            break;
+        }
+    }
+    Ok(combine_checksums(&segment_checksums))
+}
+
+fn fold_checksums(mut level: Vec<H256>) -> H256 {
+    if level.is_empty() {
+        return H256::zero();
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut buffer = [0_u8; 64];
+                    buffer[..32].copy_from_slice(left.as_bytes());
+                    buffer[32..].copy_from_slice(right.as_bytes());
+                    H256(keccak256(&buffer))
+                }
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+fn hash_leaf(storage_logs: &[SnapshotStorageLog]) -> H256 {
+    let mut buffer = Vec::with_capacity(storage_logs.len() * ENCODED_LOG_LEN);
+    for log in storage_logs {
+        buffer.extend_from_slice(log.key.hashed_key().as_bytes());
+        buffer.extend_from_slice(log.value.as_bytes());
+        buffer.extend_from_slice(&log.enumeration_index.to_be_bytes());
+        buffer.extend_from_slice(&log.l1_batch_number_of_initial_write.0.to_be_bytes());
+    }
+    H256(keccak256(&buffer))
+}
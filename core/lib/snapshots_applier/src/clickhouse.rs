@@ -0,0 +1,207 @@
+//! Mirroring applied storage logs into ClickHouse (e.g., for analytics).
+
+use std::{fmt, sync::Arc};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::Serialize;
+use zksync_types::{
+    snapshots::{SnapshotRecoveryStatus, SnapshotStorageLog},
+    Address, H256,
+};
+
+use crate::StorageLogsSink;
+
+/// Row written to ClickHouse for each applied storage log. Hashes and addresses are serialized
+/// as `0x`-prefixed hex strings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClickHouseStorageLogRow {
+    pub l1_batch_number: u32,
+    pub chunk_id: u64,
+    pub hashed_key: H256,
+    pub address: Address,
+    pub key: H256,
+    pub value: H256,
+    pub l1_batch_number_of_initial_write: u32,
+    pub enumeration_index: u64,
+}
+
+/// ClickHouse API used by [`ClickHouseStorageLogsSink`].
+#[async_trait]
+pub trait ClickHouseClient: fmt::Debug + Send + Sync + 'static {
+    /// Inserts a batch of rows into the specified table using a single `INSERT` query. `dedup_token` uniquely
+    /// identifies the batch; it should be passed as the `insert_deduplication_token` setting, so that
+    /// the repeated insert of the same batch is ignored by ClickHouse.
+    async fn insert_rows(
+        &self,
+        table: &str,
+        dedup_token: &str,
+        rows: &[ClickHouseStorageLogRow],
+    ) -> anyhow::Result<()>;
+}
+
+/// [`ClickHouseClient`] implementation using the ClickHouse HTTP interface. Rows are inserted
+/// using the `JSONEachRow` format.
+#[derive(Debug)]
+pub struct HttpClickHouseClient {
+    client: reqwest::Client,
+    url: String,
+    credentials: Option<(String, String)>,
+}
+
+impl HttpClickHouseClient {
+    /// Creates a client for the HTTP interface at the specified URL (e.g., `http://localhost:8123`).
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            credentials: None,
+        }
+    }
+
+    /// Sets credentials used to authenticate queries.
+    #[must_use]
+    pub fn with_credentials(
+        mut self,
+        user: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+}
+
+#[async_trait]
+impl ClickHouseClient for HttpClickHouseClient {
+    async fn insert_rows(
+        &self,
+        table: &str,
+        dedup_token: &str,
+        rows: &[ClickHouseStorageLogRow],
+    ) -> anyhow::Result<()> {
+        let mut body = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut body, row).context("failed serializing row")?;
+            body.push(b'\n');
+        }
+
+        let query = format!("INSERT INTO {table} FORMAT JSONEachRow");
+        let mut request = self.client.post(&self.url).query(&[
+            ("query", query.as_str()),
+            ("insert_deduplication_token", dedup_token),
+        ]);
+        if let Some((user, password)) = &self.credentials {
+            request = request
+                .header("X-ClickHouse-User", user)
+                .header("X-ClickHouse-Key", password);
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .context("failed sending insert query")?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!("ClickHouse responded with {status}: {}", message.trim());
+        }
+        Ok(())
+    }
+}
+
+/// [`StorageLogsSink`] mirroring applied storage logs into a ClickHouse table. Logs from each chunk are written
+/// using batched inserts of up to [`Self::with_batch_size()`] rows each.
+///
+/// Since a chunk may be written several times (see [`StorageLogsSink`] docs), each batch is inserted with
+/// a deduplication token derived from the snapshot L1 batch number, chunk ID and batch index. Batch boundaries
+/// only depend on the chunk contents and the batch size, so a re-inserted batch has the same token and is ignored
+/// by ClickHouse. Deduplication by token works out of the box for `Replicated*MergeTree` tables; non-replicated
+/// `MergeTree` tables require the `non_replicated_deduplication_window` setting. Since deduplication only covers
+/// a limited window of recent inserts, the table should additionally use the `ReplacingMergeTree` engine
+/// with `(l1_batch_number, chunk_id, hashed_key)` as the sorting key; otherwise, duplicate rows are possible
+/// (e.g., if the batch size is changed between applier restarts).
+#[derive(Debug)]
+pub struct ClickHouseStorageLogsSink {
+    client: Arc<dyn ClickHouseClient>,
+    table: String,
+    batch_size: usize,
+}
+
+impl ClickHouseStorageLogsSink {
+    /// Default number of rows in a single insert.
+    pub const DEFAULT_BATCH_SIZE: usize = 10_000;
+
+    /// Creates a sink writing to the specified table.
+    pub fn new(client: Arc<dyn ClickHouseClient>, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            table: table.into(),
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the maximum number of rows in a single insert.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "batch size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn dedup_token(
+        status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        batch_idx: usize,
+        batch_size: usize,
+    ) -> String {
+        let l1_batch_number = status.l1_batch_number.0;
+        format!("snapshot-{l1_batch_number}-chunk-{chunk_id}-batch-{batch_idx}-of-{batch_size}")
+    }
+}
+
+#[async_trait]
+impl StorageLogsSink for ClickHouseStorageLogsSink {
+    async fn write_storage_logs_chunk(
+        &self,
+        status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let rows: Vec<_> = storage_logs
+            .iter()
+            .map(|log| ClickHouseStorageLogRow {
+                l1_batch_number: status.l1_batch_number.0,
+                chunk_id,
+                hashed_key: log.key.hashed_key(),
+                address: *log.key.address(),
+                key: *log.key.key(),
+                value: log.value,
+                l1_batch_number_of_initial_write: log.l1_batch_number_of_initial_write.0,
+                enumeration_index: log.enumeration_index,
+            })
+            .collect();
+
+        for (batch_idx, batch) in rows.chunks(self.batch_size).enumerate() {
+            let dedup_token = Self::dedup_token(status, chunk_id, batch_idx, self.batch_size);
+            self.client
+                .insert_rows(&self.table, &dedup_token, batch)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed inserting batch #{batch_idx} of storage logs chunk {chunk_id} into ClickHouse table `{}`",
+                        self.table
+                    )
+                })?;
+        }
+        tracing::debug!(
+            "Mirrored {} storage logs from chunk {chunk_id} into ClickHouse table `{}`",
+            rows.len(),
+            self.table
+        );
+        Ok(())
+    }
+}
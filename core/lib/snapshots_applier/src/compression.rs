@@ -0,0 +1,194 @@
+//! Compression of snapshot objects: decompression of storage log chunks compressed with a shared zstd dictionary,
+//! and an object store compressing written objects.
+
+use std::{
+    fmt,
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError};
+use zstd::dict::DecoderDictionary;
+
+use crate::format::DecodingLimits;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Prepared zstd dictionary referenced by [`SnapshotHeader::storage_logs_compression_dictionary`].
+///
+/// [`SnapshotHeader::storage_logs_compression_dictionary`]: zksync_types::snapshots::SnapshotHeader::storage_logs_compression_dictionary
+#[derive(Clone)]
+pub(crate) struct CompressionDictionary {
+    key: String,
+    dictionary: Arc<DecoderDictionary<'static>>,
+}
+
+impl fmt::Debug for CompressionDictionary {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("CompressionDictionary")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CompressionDictionary {
+    pub fn new(key: String, bytes: &[u8]) -> Self {
+        Self {
+            key,
+            dictionary: Arc::new(DecoderDictionary::copy(bytes)),
+        }
+    }
+
+    /// Decompresses a storage logs chunk compressed with zstd using this dictionary. Decompression is aborted
+    /// once the output exceeds [`DecodingLimits::max_decompressed_chunk_size`].
+    pub fn decompress(&self, bytes: &[u8], limits: &DecodingLimits) -> anyhow::Result<Vec<u8>> {
+        let decoder =
+            zstd::stream::read::Decoder::with_prepared_dictionary(bytes, &self.dictionary)
+                .context("failed initializing zstd decoder")?;
+        let max_size = limits.max_decompressed_chunk_size;
+        let mut decompressed_bytes = Vec::new();
+        decoder
+            .take(max_size as u64 + 1)
+            .read_to_end(&mut decompressed_bytes)
+            .with_context(|| {
+                format!(
+                    "failed decompressing chunk using zstd dictionary `{}`",
+                    self.key
+                )
+            })?;
+        anyhow::ensure!(
+            decompressed_bytes.len() <= max_size,
+            "chunk decompressed using zstd dictionary `{}` exceeds {max_size} bytes",
+            self.key
+        );
+        Ok(decompressed_bytes)
+    }
+}
+
+/// Format used by [`CompressingObjectStore`] to compress written objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    Zstd,
+}
+
+/// Configuration of [`CompressingObjectStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// Format used to compress written objects.
+    pub format: CompressionFormat,
+    /// Compression level for [`CompressionFormat::Gzip`], from 0 (no compression) to 9 (best compression).
+    pub gzip_level: u32,
+    /// Compression level for [`CompressionFormat::Zstd`], from 1 to 22 (best compression).
+    pub zstd_level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            format: CompressionFormat::Gzip,
+            gzip_level: Compression::default().level(),
+            zstd_level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl CompressionConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.gzip_level <= Compression::best().level(),
+            "gzip compression level {} is out of range 0..=9",
+            self.gzip_level
+        );
+        let zstd_levels = zstd::compression_level_range();
+        anyhow::ensure!(
+            zstd_levels.contains(&self.zstd_level),
+            "zstd compression level {} is out of range {zstd_levels:?}",
+            self.zstd_level
+        );
+        Ok(())
+    }
+}
+
+/// [`ObjectStore`] compressing objects written to the wrapped store according to [`CompressionConfig`].
+/// The compression format of read objects is detected automatically, so objects written with any format
+/// and level can be read back. Objects not compressed in any of the supported formats are returned as is.
+///
+/// The store should wrap raw serialized objects; objects already compressed by [`StoredObject::serialize()`]
+/// (e.g., gzipped storage log chunks) would be decompressed on read.
+///
+/// [`StoredObject::serialize()`]: zksync_object_store::StoredObject::serialize()
+#[derive(Debug)]
+pub struct CompressingObjectStore<S> {
+    inner: S,
+    config: CompressionConfig,
+}
+
+impl<S: ObjectStore> CompressingObjectStore<S> {
+    /// Wraps the provided store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compression levels in the `config` are out of range.
+    pub fn new(inner: S, config: CompressionConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        Ok(Self { inner, config })
+    }
+
+    fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self.config.format {
+            CompressionFormat::Gzip => {
+                let level = Compression::new(self.config.gzip_level);
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            CompressionFormat::Zstd => zstd::encode_all(bytes, self.config.zstd_level),
+        }
+    }
+
+    fn decompress(bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        let mut decompressed_bytes = Vec::new();
+        if bytes.starts_with(GZIP_MAGIC) {
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed_bytes)?;
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            decompressed_bytes = zstd::decode_all(bytes.as_slice())?;
+        } else {
+            return Ok(bytes);
+        }
+        Ok(decompressed_bytes)
+    }
+}
+
+#[async_trait]
+impl<S: ObjectStore> ObjectStore for CompressingObjectStore<S> {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let bytes = self.inner.get_raw(bucket, key).await?;
+        Self::decompress(bytes).map_err(|err| ObjectStoreError::Serialization(err.into()))
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        let compressed_value = self
+            .compress(&value)
+            .map_err(|err| ObjectStoreError::Serialization(err.into()))?;
+        self.inner.put_raw(bucket, key, compressed_value).await
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        self.inner.remove_raw(bucket, key).await
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        self.inner.storage_prefix_raw(bucket)
+    }
+}
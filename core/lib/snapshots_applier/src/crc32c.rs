@@ -0,0 +1,332 @@
+//! Object store verifying CRC32C checksums of streamed objects.
+
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use zksync_object_store::{
+    Bucket, GcsObjectStream, GoogleCloudStorage, ObjectStore, ObjectStoreError,
+};
+
+/// Reversed CRC32C (Castagnoli) polynomial.
+const CRC32C_POLYNOMIAL: u32 = 0x82f6_3b78;
+/// Default size of the buffer used to read streamed objects; see [`Crc32cVerifyingStore::with_read_buffer_size()`].
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 64 * 1_024;
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0_u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32C_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+/// Incremental CRC32C checksum computation.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32c(u32);
+
+impl Crc32c {
+    pub fn new() -> Self {
+        Self(!0)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            let idx = (self.0 ^ u32::from(byte)) & 0xff;
+            self.0 = (self.0 >> 8) ^ CRC32C_TABLE[idx as usize];
+        }
+    }
+
+    pub fn finalize(self) -> u32 {
+        !self.0
+    }
+}
+
+/// Object streamed from a [`StreamingObjectSource`].
+pub struct StreamedObject {
+    /// CRC32C checksum of the object as reported by the backend. `None` if the backend doesn't provide it.
+    pub crc32c: Option<u32>,
+    /// Object size in bytes as reported by the backend. `None` if the backend doesn't provide it.
+    pub size: Option<u64>,
+    /// Object contents.
+    pub reader: Box<dyn AsyncRead + Send + Unpin>,
+}
+
+impl fmt::Debug for StreamedObject {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("StreamedObject")
+            .field("crc32c", &self.crc32c)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Object store backend able to stream objects together with their checksums (e.g., GCS, which reports
+/// CRC32C checksums in object metadata).
+#[async_trait]
+pub trait StreamingObjectSource: fmt::Debug + Send + Sync + 'static {
+    /// Starts streaming the object with the specified key.
+    async fn stream(&self, bucket: Bucket, key: &str) -> Result<StreamedObject, ObjectStoreError>;
+
+    /// Returns the size of the object with the specified key without streaming it. The default implementation
+    /// returns [`ObjectStoreError::Unsupported`].
+    async fn get_size(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let err = format!("getting size of `{bucket}/{key}` is not supported by {self:?}");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+}
+
+#[async_trait]
+impl StreamingObjectSource for GoogleCloudStorage {
+    async fn stream(&self, bucket: Bucket, key: &str) -> Result<StreamedObject, ObjectStoreError> {
+        let GcsObjectStream {
+            crc32c,
+            size,
+            reader,
+        } = self.stream_raw(bucket, key).await?;
+        Ok(StreamedObject {
+            crc32c,
+            size: Some(size),
+            reader,
+        })
+    }
+
+    async fn get_size(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        self.get_size_raw(bucket, key).await
+    }
+}
+
+/// Reader verifying the CRC32C checksum and size of a [`StreamedObject`] as it is read. Once the object
+/// is read in full, the reader returns an [`io::ErrorKind::InvalidData`] error if the checksum or size
+/// doesn't match the ones reported by the backend. An object larger than the reported size is rejected
+/// as soon as the excess bytes are streamed.
+pub struct Crc32cVerifyingReader<R> {
+    object_name: String,
+    expected_crc32c: Option<u32>,
+    expected_size: Option<u64>,
+    crc32c: Crc32c,
+    read_size: u64,
+    inner: R,
+}
+
+impl<R> fmt::Debug for Crc32cVerifyingReader<R> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Crc32cVerifyingReader")
+            .field("object_name", &self.object_name)
+            .field("expected_crc32c", &self.expected_crc32c)
+            .field("expected_size", &self.expected_size)
+            .field("read_size", &self.read_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Crc32cVerifyingReader<R> {
+    pub fn new(
+        object_name: String,
+        expected_crc32c: Option<u32>,
+        expected_size: Option<u64>,
+        inner: R,
+    ) -> Self {
+        Self {
+            object_name,
+            expected_crc32c,
+            expected_size,
+            crc32c: Crc32c::new(),
+            read_size: 0,
+            inner,
+        }
+    }
+
+    fn check_size(&self, is_finished: bool) -> io::Result<()> {
+        let Some(expected_size) = self.expected_size else {
+            return Ok(());
+        };
+        let is_mismatch = if is_finished {
+            self.read_size != expected_size
+        } else {
+            self.read_size > expected_size
+        };
+        if is_mismatch {
+            let object_name = &self.object_name;
+            let err = if is_finished {
+                format!(
+                    "size mismatch for object `{object_name}`: expected {expected_size} bytes, got {}",
+                    self.read_size
+                )
+            } else {
+                format!(
+                    "size mismatch for object `{object_name}`: expected {expected_size} bytes, got at least {}",
+                    self.read_size
+                )
+            };
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+        Ok(())
+    }
+
+    fn check_crc32c(&self) -> io::Result<()> {
+        let Some(expected_crc32c) = self.expected_crc32c else {
+            return Ok(());
+        };
+        let actual_crc32c = self.crc32c.finalize();
+        if actual_crc32c != expected_crc32c {
+            let err = format!(
+                "CRC32C mismatch for object `{}`: expected {expected_crc32c:#010x}, got {actual_crc32c:#010x}",
+                self.object_name
+            );
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+        Ok(())
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Crc32cVerifyingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled_len = buf.filled().len();
+        let has_capacity = buf.remaining() > 0;
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        let new_bytes = &buf.filled()[filled_len..];
+        if new_bytes.is_empty() {
+            if has_capacity {
+                // The object is read in full.
+                this.check_size(true)?;
+                this.check_crc32c()?;
+            }
+        } else {
+            this.crc32c.update(new_bytes);
+            this.read_size += new_bytes.len() as u64;
+            this.check_size(false)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Read-only [`ObjectStore`] fetching objects from a [`StreamingObjectSource`] and verifying their CRC32C checksums.
+///
+/// The checksum is computed incrementally while the object is streamed (see [`Crc32cVerifyingReader`]), so verification
+/// doesn't require an additional pass over the object, and a corrupted object is rejected before it's decoded.
+/// Objects can also be streamed without buffering via [`Self::stream()`]. A checksum mismatch is treated
+/// as a transient error since it's usually caused by corruption during transfer; thus, the object will be re-fetched.
+#[derive(Debug)]
+pub struct Crc32cVerifyingStore<S> {
+    source: S,
+    read_buffer_size: usize,
+}
+
+impl<S: StreamingObjectSource> Crc32cVerifyingStore<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            read_buffer_size: DEFAULT_READ_BUFFER_SIZE,
+        }
+    }
+
+    /// Sets the size of the buffer used to read streamed objects in [`ObjectStore::get_raw()`]. Objects are read
+    /// from the source in reads of at most this size, so it can be tuned to network characteristics.
+    /// The default value is [`DEFAULT_READ_BUFFER_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    #[must_use]
+    pub fn with_read_buffer_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "read buffer size must be positive");
+        self.read_buffer_size = size;
+        self
+    }
+
+    /// Starts streaming the object with the specified key, verifying its checksum and size as it is read.
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors returned by the source.
+    pub async fn stream(
+        &self,
+        bucket: Bucket,
+        key: &str,
+    ) -> Result<Crc32cVerifyingReader<Box<dyn AsyncRead + Send + Unpin>>, ObjectStoreError> {
+        let StreamedObject {
+            crc32c,
+            size,
+            reader,
+        } = self.source.stream(bucket, key).await?;
+        let object_name = format!("{bucket}/{key}");
+        Ok(Crc32cVerifyingReader::new(
+            object_name,
+            crc32c,
+            size,
+            reader,
+        ))
+    }
+}
+
+#[async_trait]
+impl<S: StreamingObjectSource> ObjectStore for Crc32cVerifyingStore<S> {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let mut reader = self.stream(bucket, key).await?;
+        let mut bytes = vec![];
+        let mut buffer = vec![0_u8; self.read_buffer_size];
+        loop {
+            let read_bytes = reader
+                .read(&mut buffer)
+                .await
+                .map_err(|err| ObjectStoreError::Other(err.into()))?;
+            if read_bytes == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buffer[..read_bytes]);
+        }
+        Ok(bytes)
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        let err =
+            format!("cannot put `{bucket}/{key}`: CRC32C-verifying object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        let err =
+            format!("cannot remove `{bucket}/{key}`: CRC32C-verifying object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        self.source.get_size(bucket, key).await
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        format!("crc32c/{bucket}")
+    }
+}
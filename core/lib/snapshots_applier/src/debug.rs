@@ -0,0 +1,105 @@
+//! Internal state of the snapshot applier exported for debugging.
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::Serialize;
+
+/// Snapshot of the snapshot applier internal counters returned by [`SnapshotsApplierDebugHandle::debug_state()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SnapshotsApplierDebugState {
+    /// Number of storage log chunks currently downloaded from the object store.
+    pub in_flight_downloads: usize,
+    /// Number of storage log chunks that are downloaded and decoded, but not yet persisted in Postgres.
+    pub decoded_chunks_pending_insert: usize,
+    /// Total number of bytes downloaded from the object store.
+    pub bytes_downloaded: u64,
+    /// ID of the storage logs chunk which processing was started most recently.
+    pub current_chunk_id: Option<u64>,
+    /// Total number of Postgres blocks loaded into the buffer cache by prewarm hints.
+    pub prewarmed_blocks: u64,
+}
+
+#[derive(Debug, Default)]
+struct DebugCounters {
+    in_flight_downloads: AtomicUsize,
+    decoded_chunks_pending_insert: AtomicUsize,
+    bytes_downloaded: AtomicU64,
+    /// Chunk ID + 1; 0 means no chunk processing was started.
+    current_chunk_id: AtomicU64,
+    prewarmed_blocks: AtomicU64,
+}
+
+/// Handle allowing to read the internal state of a running snapshot applier, e.g. to diagnose stalled recovery.
+/// Should be cloned before running the applier.
+///
+/// The handle is cheaply cloneable; clones share the same state. Counters are updated independently, so the state
+/// may be slightly inconsistent across counters.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotsApplierDebugHandle {
+    counters: Arc<DebugCounters>,
+}
+
+impl SnapshotsApplierDebugHandle {
+    /// Returns the current internal state of the applier.
+    pub fn debug_state(&self) -> SnapshotsApplierDebugState {
+        let counters = &self.counters;
+        let current_chunk_id = counters.current_chunk_id.load(Ordering::Relaxed);
+        SnapshotsApplierDebugState {
+            in_flight_downloads: counters.in_flight_downloads.load(Ordering::Relaxed),
+            decoded_chunks_pending_insert: counters
+                .decoded_chunks_pending_insert
+                .load(Ordering::Relaxed),
+            bytes_downloaded: counters.bytes_downloaded.load(Ordering::Relaxed),
+            current_chunk_id: current_chunk_id.checked_sub(1),
+            prewarmed_blocks: counters.prewarmed_blocks.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn chunk_started(&self, chunk_id: u64) {
+        self.counters
+            .current_chunk_id
+            .store(chunk_id + 1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bytes_downloaded(&self, byte_count: usize) {
+        self.counters
+            .bytes_downloaded
+            .fetch_add(byte_count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn blocks_prewarmed(&self, block_count: u64) {
+        self.counters
+            .prewarmed_blocks
+            .fetch_add(block_count, Ordering::Relaxed);
+    }
+
+    /// Records a download start. The download is considered finished once the returned guard is dropped.
+    pub(crate) fn download_started(&self) -> CounterGuard<'_> {
+        CounterGuard::new(&self.counters.in_flight_downloads)
+    }
+
+    /// Records a decoded chunk. The chunk is considered inserted once the returned guard is dropped.
+    pub(crate) fn chunk_decoded(&self) -> CounterGuard<'_> {
+        CounterGuard::new(&self.counters.decoded_chunks_pending_insert)
+    }
+}
+
+/// Guard decrementing a counter on drop. Ensures that counters are correct if processing fails or is cancelled.
+#[derive(Debug)]
+pub(crate) struct CounterGuard<'a>(&'a AtomicUsize);
+
+impl<'a> CounterGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for CounterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,88 @@
+//! Preflight check of available disk space.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Provider of filesystem stats used by [`DiskSpaceCheck`].
+pub trait DiskStatsProvider: fmt::Debug + Send + Sync {
+    /// Returns the number of bytes available to unprivileged users on the filesystem containing `path`.
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+}
+
+/// [`DiskStatsProvider`] returning actual filesystem stats. Only supported on Unix.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilesystemStats;
+
+impl DiskStatsProvider for FilesystemStats {
+    #[cfg(unix)]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+        let c_path = CString::new(path.as_os_str().as_bytes())?;
+        let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `c_path` is a valid NUL-terminated string, and `stats` points to memory suitable for `statvfs`.
+        let ret = unsafe { libc::statvfs(c_path.as_ptr(), stats.as_mut_ptr()) };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs` has succeeded, so `stats` is initialized.
+        let stats = unsafe { stats.assume_init() };
+        // Field types differ across platforms, so the casts may be no-op on some of them.
+        #[allow(clippy::unnecessary_cast)]
+        let available_space = stats.f_bavail as u64 * stats.f_frsize as u64;
+        Ok(available_space)
+    }
+
+    #[cfg(not(unix))]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        let err = format!(
+            "getting available disk space for `{}` is not supported on this platform",
+            path.display()
+        );
+        Err(io::Error::new(io::ErrorKind::Unsupported, err))
+    }
+}
+
+/// Preflight check comparing available disk space with the estimated footprint of the recovered data.
+/// Allows refusing to start recovery instead of failing mid-recovery because the disk is full.
+///
+/// The footprint is estimated from sizes of storage log chunk objects left to process (as reported
+/// by the object store) multiplied by [`Self::footprint_multiplier`], plus [`Self::reserve`]. If the object store
+/// cannot report object sizes, only the reserve is checked.
+#[derive(Debug)]
+pub struct DiskSpaceCheck {
+    /// Path on the checked volume, e.g. the Postgres data directory.
+    pub path: PathBuf,
+    /// Ratio of the disk footprint of applied storage logs to the size of storage log chunk objects.
+    /// Chunk objects are compressed, and Postgres stores each storage log in several tables with indices,
+    /// so the ratio is significantly greater than 1.
+    pub footprint_multiplier: f64,
+    /// Disk space in bytes required to be left free in addition to the estimated footprint.
+    pub reserve: u64,
+    /// Provider of filesystem stats.
+    pub stats_provider: Box<dyn DiskStatsProvider>,
+}
+
+impl DiskSpaceCheck {
+    const DEFAULT_FOOTPRINT_MULTIPLIER: f64 = 5.0;
+
+    /// Creates a check for the volume containing the specified `path` with the default footprint multiplier
+    /// and no reserve.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            footprint_multiplier: Self::DEFAULT_FOOTPRINT_MULTIPLIER,
+            reserve: 0,
+            stats_provider: Box::new(FilesystemStats),
+        }
+    }
+
+    /// Computes the required disk space in bytes given the total size of storage log chunk objects
+    /// left to process.
+    pub(crate) fn required_space(&self, chunks_size: u64) -> u64 {
+        let footprint = chunks_size as f64 * self.footprint_multiplier;
+        (footprint as u64).saturating_add(self.reserve)
+    }
+}
@@ -0,0 +1,109 @@
+//! Recovery errors with operator-facing remediation hints.
+
+use std::{error, fmt};
+
+use zksync_dal::SqlxError;
+use zksync_object_store::ObjectStoreError;
+use zksync_web3_decl::jsonrpsee::core::ClientError as RpcError;
+
+/// Error of an external resource used during snapshot recovery, classified by its likely cause.
+/// Each variant provides a remediation hint included into the error message.
+///
+/// Errors returned by [`SnapshotsApplierConfig::run()`](crate::SnapshotsApplierConfig::run()) contain
+/// this error in their chain if they were caused by an object store, Postgres or the main node.
+#[derive(Debug)]
+pub enum RecoveryError {
+    /// Snapshot object is missing in the object store.
+    ObjectNotFound(ObjectStoreError),
+    /// Snapshot object cannot be decoded.
+    CorruptedObject(ObjectStoreError),
+    /// Object store cannot be accessed (e.g., because of network or permission issues).
+    ObjectStoreAccess(ObjectStoreError),
+    /// Object store doesn't support the requested operation.
+    UnsupportedObjectStore(ObjectStoreError),
+    /// Postgres error.
+    Database(SqlxError),
+    /// Main node API error.
+    MainNode(RpcError),
+}
+
+impl RecoveryError {
+    /// Returns an operator-facing hint on how to remediate this error.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::ObjectNotFound(_) => {
+                "check that the object store bucket is configured correctly and that the snapshot is fully uploaded"
+            }
+            Self::CorruptedObject(_) => {
+                "the snapshot object is corrupted or has an unexpected format; check that the snapshot creator version \
+                 matches the node version, or recover from another snapshot"
+            }
+            Self::ObjectStoreAccess(_) => {
+                "check network connectivity to the object store and verify that the node has access to it \
+                 (e.g., check the IAM role or service account credentials)"
+            }
+            Self::UnsupportedObjectStore(_) => {
+                "the configured object store doesn't support this operation; use a different object store"
+            }
+            Self::Database(_) => {
+                "check Postgres connectivity and that DB migrations are applied"
+            }
+            Self::MainNode(_) => {
+                "check that the main node URL is correct and that the main node is reachable"
+            }
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::ObjectNotFound(_) => "snapshot object is not found in object store",
+            Self::CorruptedObject(_) => "snapshot object is corrupted",
+            Self::ObjectStoreAccess(_) => "failed accessing object store",
+            Self::UnsupportedObjectStore(_) => "unsupported object store operation",
+            Self::Database(_) => "Postgres error",
+            Self::MainNode(_) => "main node API error",
+        }
+    }
+}
+
+impl fmt::Display for RecoveryError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} (hint: {})", self.description(), self.hint())
+    }
+}
+
+impl error::Error for RecoveryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::ObjectNotFound(err)
+            | Self::CorruptedObject(err)
+            | Self::ObjectStoreAccess(err)
+            | Self::UnsupportedObjectStore(err) => Some(err),
+            Self::Database(err) => Some(err),
+            Self::MainNode(err) => Some(err),
+        }
+    }
+}
+
+impl From<ObjectStoreError> for RecoveryError {
+    fn from(err: ObjectStoreError) -> Self {
+        match err {
+            ObjectStoreError::KeyNotFound(_) => Self::ObjectNotFound(err),
+            ObjectStoreError::Serialization(_) => Self::CorruptedObject(err),
+            ObjectStoreError::Other(_) => Self::ObjectStoreAccess(err),
+            ObjectStoreError::Unsupported(_) => Self::UnsupportedObjectStore(err),
+        }
+    }
+}
+
+impl From<SqlxError> for RecoveryError {
+    fn from(err: SqlxError) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<RpcError> for RecoveryError {
+    fn from(err: RpcError) -> Self {
+        Self::MainNode(err)
+    }
+}
@@ -0,0 +1,262 @@
+//! Registry of decoders for snapshot objects of different data formats.
+
+use std::{collections::HashMap, fmt};
+
+use anyhow::Context as _;
+use zksync_object_store::StoredObject;
+use zksync_protobuf::ProtoFmt;
+use zksync_types::{
+    snapshots::{SnapshotFactoryDependencies, SnapshotStorageLogsChunk},
+    StorageValue, H256,
+};
+
+/// Function decoding a snapshot object from raw bytes retrieved from the object store.
+pub type SnapshotObjectDecoder<T> = fn(Vec<u8>, &DecodingLimits) -> anyhow::Result<T>;
+
+/// Limits enforced by [`SnapshotObjectDecoder`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodingLimits {
+    /// Maximum size of a single storage value in bytes. Only applies to data formats with variable-size values;
+    /// values in the default format always have the [`StorageValue`] size.
+    pub max_storage_value_size: usize,
+    /// Maximum size of a storage logs chunk in bytes after decompressing it with a compression dictionary
+    /// (see [`SnapshotObjectType::DictionaryCompressedStorageLogsChunk`]).
+    pub max_decompressed_chunk_size: usize,
+}
+
+impl Default for DecodingLimits {
+    fn default() -> Self {
+        Self {
+            max_storage_value_size: StorageValue::len_bytes(),
+            max_decompressed_chunk_size: 256 << 20, // 256 MiB
+        }
+    }
+}
+
+impl DecodingLimits {
+    /// Converts a variable-size storage value into a [`StorageValue`]. Values shorter than [`StorageValue`]
+    /// are left-padded with zeros, i.e., interpreted as big-endian integers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value exceeds [`Self::max_storage_value_size`] or doesn't fit into
+    /// a [`StorageValue`].
+    pub fn storage_value(
+        &self,
+        hashed_key: H256,
+        bytes: &[u8],
+    ) -> Result<StorageValue, ValueTooLarge> {
+        let max_size = self.max_storage_value_size.min(StorageValue::len_bytes());
+        if bytes.len() > max_size {
+            return Err(ValueTooLarge {
+                hashed_key,
+                size: bytes.len(),
+                max_size,
+            });
+        }
+        let mut value = StorageValue::zero();
+        value.as_bytes_mut()[StorageValue::len_bytes() - bytes.len()..].copy_from_slice(bytes);
+        Ok(value)
+    }
+}
+
+/// Error returned by [`DecodingLimits::storage_value()`] if a storage value is too large.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "storage value for hashed key {hashed_key:?} has {size} bytes, while at most {max_size} bytes are allowed"
+)]
+pub struct ValueTooLarge {
+    pub hashed_key: H256,
+    pub size: usize,
+    pub max_size: usize,
+}
+
+/// Type of snapshot objects decoded using a [`SnapshotDecoderRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapshotObjectType {
+    StorageLogsChunk,
+    /// Storage logs chunk compressed with a dictionary referenced by the snapshot header. Decoders for this type
+    /// receive the decompressed chunk payload.
+    DictionaryCompressedStorageLogsChunk,
+    FactoryDependencies,
+}
+
+impl fmt::Display for SnapshotObjectType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::StorageLogsChunk => "storage logs chunk",
+            Self::DictionaryCompressedStorageLogsChunk => {
+                "dictionary-compressed storage logs chunk"
+            }
+            Self::FactoryDependencies => "factory dependencies",
+        })
+    }
+}
+
+/// Registry of decoders for snapshot objects keyed by the data format version (see [`SnapshotHeader::format_version`])
+/// and the object type. New formats are supported by registering decoders for them.
+///
+/// The default registry contains decoders for format version 0, i.e. objects serialized as [`StoredObject`]s
+/// and dictionary-compressed storage logs chunks encoded as Protobuf messages, and for version
+/// [`SnapshotStorageLogsChunk::DELTA_ENCODED_INDICES_FORMAT_VERSION`], which differs from version 0 only
+/// by delta-encoded enumeration indices in storage logs chunks.
+///
+/// [`SnapshotHeader::format_version`]: zksync_types::snapshots::SnapshotHeader::format_version
+#[derive(Debug, Clone)]
+pub struct SnapshotDecoderRegistry {
+    storage_logs_chunk_decoders: HashMap<u32, SnapshotObjectDecoder<SnapshotStorageLogsChunk>>,
+    decompressed_storage_logs_chunk_decoders:
+        HashMap<u32, SnapshotObjectDecoder<SnapshotStorageLogsChunk>>,
+    factory_deps_decoders: HashMap<u32, SnapshotObjectDecoder<SnapshotFactoryDependencies>>,
+}
+
+impl Default for SnapshotDecoderRegistry {
+    fn default() -> Self {
+        let mut this = Self::empty();
+        this.register_storage_logs_chunk_decoder(0, decode_stored_object);
+        this.register_decompressed_storage_logs_chunk_decoder(0, decode_protobuf);
+        this.register_factory_deps_decoder(0, decode_stored_object);
+
+        let delta_version = SnapshotStorageLogsChunk::DELTA_ENCODED_INDICES_FORMAT_VERSION;
+        this.register_storage_logs_chunk_decoder(delta_version, |bytes, limits| {
+            let mut chunk: SnapshotStorageLogsChunk = decode_stored_object(bytes, limits)?;
+            chunk.restore_absolute_indices();
+            Ok(chunk)
+        });
+        this.register_decompressed_storage_logs_chunk_decoder(delta_version, |bytes, limits| {
+            let mut chunk: SnapshotStorageLogsChunk = decode_protobuf(bytes, limits)?;
+            chunk.restore_absolute_indices();
+            Ok(chunk)
+        });
+        this.register_factory_deps_decoder(delta_version, decode_stored_object);
+        this
+    }
+}
+
+impl SnapshotDecoderRegistry {
+    /// Creates a registry without any decoders.
+    pub fn empty() -> Self {
+        Self {
+            storage_logs_chunk_decoders: HashMap::new(),
+            decompressed_storage_logs_chunk_decoders: HashMap::new(),
+            factory_deps_decoders: HashMap::new(),
+        }
+    }
+
+    /// Registers a decoder for storage logs chunks of the specified format version, replacing the previously
+    /// registered decoder (if any).
+    pub fn register_storage_logs_chunk_decoder(
+        &mut self,
+        format_version: u32,
+        decoder: SnapshotObjectDecoder<SnapshotStorageLogsChunk>,
+    ) -> &mut Self {
+        self.storage_logs_chunk_decoders
+            .insert(format_version, decoder);
+        self
+    }
+
+    /// Registers a decoder for payloads of dictionary-compressed storage logs chunks of the specified format version,
+    /// replacing the previously registered decoder (if any).
+    pub fn register_decompressed_storage_logs_chunk_decoder(
+        &mut self,
+        format_version: u32,
+        decoder: SnapshotObjectDecoder<SnapshotStorageLogsChunk>,
+    ) -> &mut Self {
+        self.decompressed_storage_logs_chunk_decoders
+            .insert(format_version, decoder);
+        self
+    }
+
+    /// Registers a decoder for factory dependencies of the specified format version, replacing the previously
+    /// registered decoder (if any).
+    pub fn register_factory_deps_decoder(
+        &mut self,
+        format_version: u32,
+        decoder: SnapshotObjectDecoder<SnapshotFactoryDependencies>,
+    ) -> &mut Self {
+        self.factory_deps_decoders.insert(format_version, decoder);
+        self
+    }
+
+    /// Checks whether the registry has a decoder for the specified format version and object type.
+    pub fn supports(&self, format_version: u32, object_type: SnapshotObjectType) -> bool {
+        match object_type {
+            SnapshotObjectType::StorageLogsChunk => self
+                .storage_logs_chunk_decoders
+                .contains_key(&format_version),
+            SnapshotObjectType::DictionaryCompressedStorageLogsChunk => self
+                .decompressed_storage_logs_chunk_decoders
+                .contains_key(&format_version),
+            SnapshotObjectType::FactoryDependencies => {
+                self.factory_deps_decoders.contains_key(&format_version)
+            }
+        }
+    }
+
+    /// Returns the decoder for storage logs chunks of the specified format version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no decoder for the format version.
+    pub fn storage_logs_chunk_decoder(
+        &self,
+        format_version: u32,
+    ) -> anyhow::Result<SnapshotObjectDecoder<SnapshotStorageLogsChunk>> {
+        let decoder = self.storage_logs_chunk_decoders.get(&format_version);
+        decoder.copied().ok_or_else(|| {
+            Self::unsupported_format(format_version, SnapshotObjectType::StorageLogsChunk)
+        })
+    }
+
+    /// Returns the decoder for payloads of dictionary-compressed storage logs chunks of the specified format version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no decoder for the format version.
+    pub fn decompressed_storage_logs_chunk_decoder(
+        &self,
+        format_version: u32,
+    ) -> anyhow::Result<SnapshotObjectDecoder<SnapshotStorageLogsChunk>> {
+        let decoder = self
+            .decompressed_storage_logs_chunk_decoders
+            .get(&format_version);
+        decoder.copied().ok_or_else(|| {
+            Self::unsupported_format(
+                format_version,
+                SnapshotObjectType::DictionaryCompressedStorageLogsChunk,
+            )
+        })
+    }
+
+    /// Returns the decoder for factory dependencies of the specified format version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no decoder for the format version.
+    pub fn factory_deps_decoder(
+        &self,
+        format_version: u32,
+    ) -> anyhow::Result<SnapshotObjectDecoder<SnapshotFactoryDependencies>> {
+        let decoder = self.factory_deps_decoders.get(&format_version);
+        decoder.copied().ok_or_else(|| {
+            Self::unsupported_format(format_version, SnapshotObjectType::FactoryDependencies)
+        })
+    }
+
+    fn unsupported_format(format_version: u32, object_type: SnapshotObjectType) -> anyhow::Error {
+        anyhow::anyhow!(
+            "no decoder is registered for {object_type} of format version {format_version}"
+        )
+    }
+}
+
+fn decode_stored_object<T: StoredObject>(
+    bytes: Vec<u8>,
+    _limits: &DecodingLimits,
+) -> anyhow::Result<T> {
+    T::deserialize(bytes).map_err(|err| anyhow::anyhow!(err))
+}
+
+fn decode_protobuf<T: ProtoFmt>(bytes: Vec<u8>, _limits: &DecodingLimits) -> anyhow::Result<T> {
+    zksync_protobuf::decode(&bytes).context("failed decoding Protobuf message")
+}
@@ -0,0 +1,117 @@
+//! Health check for snapshot recovery.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::watch;
+use zksync_health_check::{async_trait, CheckHealth, Health, HealthStatus};
+use zksync_types::{snapshots::SnapshotRecoveryStatus, L1BatchNumber, MiniblockNumber};
+
+use crate::SnapshotsApplierOutcome;
+
+/// Health details for snapshot recovery.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub(crate) enum SnapshotsApplierHealthDetails {
+    NotStarted,
+    Recovery {
+        l1_batch_number: L1BatchNumber,
+        miniblock_number: MiniblockNumber,
+        storage_logs_chunk_count: usize,
+        storage_logs_chunks_left_to_process: usize,
+    },
+    Finished {
+        outcome: SnapshotsApplierOutcome,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl From<&SnapshotsApplierHealthDetails> for Health {
+    fn from(details: &SnapshotsApplierHealthDetails) -> Self {
+        let status = match details {
+            SnapshotsApplierHealthDetails::Finished {
+                outcome:
+                    SnapshotsApplierOutcome::Ok | SnapshotsApplierOutcome::InitializedWithoutSnapshot,
+            } => HealthStatus::Ready,
+            _ => HealthStatus::NotReady,
+        };
+        Self::from(status).with_details(details)
+    }
+}
+
+/// Health check for snapshot recovery. Reports [`HealthStatus::NotReady`] while recovery is in progress
+/// (with the progress in details), and [`HealthStatus::Ready`] once the node storage is initialized
+/// (either from a snapshot, or from genesis).
+///
+/// The check is cheaply cloneable; clones share the same state. Unlike [`ReactiveHealthCheck`], the check
+/// retains the final recovery health after the snapshot applier has finished.
+///
+/// [`ReactiveHealthCheck`]: zksync_health_check::ReactiveHealthCheck
+#[derive(Debug, Clone)]
+pub struct SnapshotsApplierHealthCheck {
+    details: Arc<watch::Sender<SnapshotsApplierHealthDetails>>,
+}
+
+impl Default for SnapshotsApplierHealthCheck {
+    fn default() -> Self {
+        let (details, _) = watch::channel(SnapshotsApplierHealthDetails::NotStarted);
+        Self {
+            details: Arc::new(details),
+        }
+    }
+}
+
+impl SnapshotsApplierHealthCheck {
+    #[cfg(feature = "progress-server")]
+    pub(crate) fn details(&self) -> SnapshotsApplierHealthDetails {
+        self.details.borrow().clone()
+    }
+
+    pub(crate) fn recovery_started(&self, status: &SnapshotRecoveryStatus) {
+        self.details
+            .send_replace(SnapshotsApplierHealthDetails::Recovery {
+                l1_batch_number: status.l1_batch_number,
+                miniblock_number: status.miniblock_number,
+                storage_logs_chunk_count: status.storage_logs_chunks_processed.len(),
+                storage_logs_chunks_left_to_process: status.storage_logs_chunks_left_to_process(),
+            });
+    }
+
+    pub(crate) fn chunk_recovered(&self) {
+        self.details.send_modify(|details| {
+            if let SnapshotsApplierHealthDetails::Recovery {
+                storage_logs_chunks_left_to_process,
+                ..
+            } = details
+            {
+                *storage_logs_chunks_left_to_process =
+                    storage_logs_chunks_left_to_process.saturating_sub(1);
+            }
+        });
+    }
+
+    pub(crate) fn finished(&self, outcome: SnapshotsApplierOutcome) {
+        self.details
+            .send_replace(SnapshotsApplierHealthDetails::Finished { outcome });
+    }
+
+    pub(crate) fn failed(&self, err: &anyhow::Error) {
+        self.details
+            .send_replace(SnapshotsApplierHealthDetails::Failed {
+                error: format!("{err:#}"),
+            });
+    }
+}
+
+#[async_trait]
+impl CheckHealth for SnapshotsApplierHealthCheck {
+    fn name(&self) -> &'static str {
+        "snapshot_recovery"
+    }
+
+    async fn check_health(&self) -> Health {
+        Health::from(&*self.details.borrow())
+    }
+}
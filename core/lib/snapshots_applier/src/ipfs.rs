@@ -0,0 +1,129 @@
+//! Read-only object store fetching snapshot objects from IPFS.
+
+use std::{collections::HashMap, fmt, time::SystemTime};
+
+use async_trait::async_trait;
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError};
+
+use crate::{parse_retry_after, ObjectStoreThrottled};
+
+/// IPFS API used by [`IpfsObjectStore`].
+#[async_trait]
+pub trait IpfsGateway: fmt::Debug + Send + Sync + 'static {
+    /// Fetches content with the specified CID.
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>, ObjectStoreError>;
+}
+
+/// [`IpfsGateway`] implementation using an HTTP gateway (e.g., a local IPFS node or a public gateway).
+/// Content is fetched from `{base_url}/ipfs/{cid}`. HTTP 429 responses with a `Retry-After` header are reported
+/// as [`ObjectStoreThrottled`] errors.
+#[derive(Debug)]
+pub struct HttpIpfsGateway {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl HttpIpfsGateway {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+impl IpfsGateway for HttpIpfsGateway {
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let url = format!("{}/ipfs/{cid}", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|err| ObjectStoreError::Other(err.into()))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            let err = format!("CID `{cid}` is not found on IPFS gateway");
+            return Err(ObjectStoreError::KeyNotFound(err.into()));
+        }
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, SystemTime::now()));
+            if let Some(retry_after) = retry_after {
+                let err = ObjectStoreThrottled { retry_after };
+                return Err(ObjectStoreError::Other(err.into()));
+            }
+        }
+        let response = response
+            .error_for_status()
+            .map_err(|err| ObjectStoreError::Other(err.into()))?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|err| ObjectStoreError::Other(err.into()))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Read-only [`ObjectStore`] resolving object keys to IPFS content IDs (CIDs) and fetching objects
+/// via an [`IpfsGateway`]. Allows bootstrapping nodes from snapshots distributed via IPFS.
+///
+/// Objects are addressed by `{bucket}/{key}` strings, e.g. `storage_logs_snapshots/snapshot_l1_batch_1_factory_deps.proto.gzip`.
+/// Since CIDs are content-derived, object integrity is ensured as long as the CID mapping is trusted
+/// and the gateway verifies the fetched content.
+#[derive(Debug)]
+pub struct IpfsObjectStore {
+    gateway: Box<dyn IpfsGateway>,
+    cids: HashMap<String, String>,
+}
+
+impl IpfsObjectStore {
+    /// Creates a store with the specified mapping from object keys to CIDs.
+    pub fn new(gateway: impl IpfsGateway, cids: HashMap<String, String>) -> Self {
+        Self {
+            gateway: Box::new(gateway),
+            cids,
+        }
+    }
+
+    /// Returns the object key used in the CID mapping for the specified bucket and key.
+    pub fn object_key(bucket: Bucket, key: &str) -> String {
+        format!("{bucket}/{key}")
+    }
+}
+
+#[async_trait]
+impl ObjectStore for IpfsObjectStore {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let object_key = Self::object_key(bucket, key);
+        let cid = self.cids.get(&object_key).ok_or_else(|| {
+            let err = format!("no CID is specified for object `{object_key}`");
+            ObjectStoreError::KeyNotFound(err.into())
+        })?;
+        tracing::debug!("Fetching object `{object_key}` from IPFS (CID: {cid})");
+        self.gateway.fetch(cid).await
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot put `{bucket}/{key}`: IPFS object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot remove `{bucket}/{key}`: IPFS object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        format!("ipfs/{bucket}")
+    }
+}
@@ -1,29 +1,161 @@
 //! Logic for applying application-level snapshots to Postgres storage.
 
-use std::{collections::HashMap, fmt, time::Duration};
+use std::{
+    cmp::{self, Reverse},
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
 
 use anyhow::Context as _;
 use async_trait::async_trait;
-use tokio::sync::Semaphore;
-use zksync_dal::{ConnectionPool, SqlxError, StorageProcessor};
-use zksync_object_store::{ObjectStore, ObjectStoreError};
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+use tokio::{
+    sync::{watch, Semaphore},
+    time::Instant,
+};
+use zksync_dal::{ConnectionPool, IsolationLevel, SqlxError, StorageProcessor};
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError, StoredObject};
 use zksync_types::{
     api::en::SyncBlock,
     snapshots::{
-        SnapshotFactoryDependencies, SnapshotHeader, SnapshotRecoveryStatus, SnapshotStorageLog,
-        SnapshotStorageLogsChunk, SnapshotStorageLogsStorageKey,
+        SnapshotFactoryDependencies, SnapshotHeader, SnapshotRecoveryCheckpoint,
+        SnapshotRecoveryStatus, SnapshotStorageLog, SnapshotStorageLogsChunk,
+        SnapshotStorageLogsStorageKey,
     },
-    web3::futures,
-    MiniblockNumber, H256,
+    tokens::TokenInfo,
+    web3::{futures, signing::keccak256},
+    L1BatchNumber, MiniblockNumber, ProtocolVersionId, StorageKey, StorageValue,
+    ACCOUNT_CODE_STORAGE_ADDRESS, H256,
 };
 use zksync_utils::bytecode::hash_bytecode;
 use zksync_web3_decl::jsonrpsee::core::{client::Error, ClientError as RpcError};
 
-use self::metrics::{InitialStage, StorageLogsChunksStage, METRICS};
+#[cfg(feature = "chaos")]
+pub use self::chaos::FailureInjection;
+#[cfg(feature = "clickhouse")]
+pub use self::clickhouse::{
+    ClickHouseClient, ClickHouseStorageLogRow, ClickHouseStorageLogsSink, HttpClickHouseClient,
+};
+#[cfg(feature = "ipfs")]
+pub use self::ipfs::{HttpIpfsGateway, IpfsGateway, IpfsObjectStore};
+#[cfg(feature = "opentelemetry")]
+pub use self::otel::opentelemetry_layer;
+#[cfg(feature = "rocksdb")]
+pub use self::sink::RocksdbStorageLogsSink;
+pub use self::{
+    acceptance::AcceptanceQuery,
+    audit::{AppliedChunk, AppliedManifest},
+    backoff::{BackoffStrategy, ConstantBackoff, ExponentialBackoff},
+    cancel::{CancellationReason, SnapshotRecoveryCancelled},
+    checksum::{combine_checksums, state_checksum, storage_logs_checksum},
+    compression::{CompressingObjectStore, CompressionConfig, CompressionFormat},
+    crc32c::{
+        Crc32cVerifyingReader, Crc32cVerifyingStore, StreamedObject, StreamingObjectSource,
+        DEFAULT_READ_BUFFER_SIZE,
+    },
+    debug::{SnapshotsApplierDebugHandle, SnapshotsApplierDebugState},
+    disk::{DiskSpaceCheck, DiskStatsProvider, FilesystemStats},
+    error::RecoveryError,
+    format::{
+        DecodingLimits, SnapshotDecoderRegistry, SnapshotObjectDecoder, SnapshotObjectType,
+        ValueTooLarge,
+    },
+    health::SnapshotsApplierHealthCheck,
+    locality::{ChunkLocalityFn, LocalityOrdering},
+    manifest::{verify_snapshot_manifest, ManifestIssue},
+    path_template::PathTemplate,
+    pipe::PipeObjectStore,
+    plan::{PlannedChunk, PlannedObject, RecoveryPlan},
+    reader::{
+        SnapshotContentsSummary, SnapshotReader, StateComparison, StateConflict, StateRelation,
+    },
+    replay::{
+        RecoveryTrace, RecoveryTraceRecorder, RecoveryTraceReplayer, TracedObjectStoreErrorKind,
+        TracedObjectStoreResponse, TracedRpcResponse,
+    },
+    retry::RetryBudget,
+    sink::{
+        AccountStatsSink, ShardedPostgresStorageLogsSink, StorageLogsShardMap, StorageLogsSink,
+    },
+    standby::{StandbyChunk, StandbyChunkReceiver, StandbyForwardingSink, STANDBY_ACK_BYTE},
+    summary::{RecoverySummary, RecoverySummaryFieldDiff, RecoverySummaryMismatch},
+    throttle::{parse_retry_after, ObjectStoreThrottled},
+    watchdog::SnapshotRecoveryStalled,
+};
+use self::{
+    compression::CompressionDictionary,
+    metrics::{InitialStage, StorageLogsChunksStage, METRICS},
+    ramp::ConcurrencyRamp,
+    retry::{RetryTokenBucket, RetryingObjectStore},
+    watchdog::ProgressWatchdog,
+};
 
+mod acceptance;
+mod audit;
+mod backoff;
+mod cancel;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod checksum;
+#[cfg(feature = "clickhouse")]
+mod clickhouse;
+mod compression;
+mod crc32c;
+mod debug;
+mod disk;
+mod error;
+mod format;
+mod health;
+#[cfg(feature = "ipfs")]
+mod ipfs;
+mod locality;
+mod manifest;
 mod metrics;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod path_template;
+mod pipe;
+mod plan;
+#[cfg(feature = "progress-server")]
+mod progress;
+mod proofs;
+mod ramp;
+mod reader;
+mod replay;
+mod retry;
+mod sink;
+mod standby;
+mod summary;
 #[cfg(test)]
 mod tests;
+mod throttle;
+mod watchdog;
+
+/// Enumeration index of the first storage log. Enumeration indices are assigned sequentially starting from this value
+/// (cf. `rollup_last_leaf_index` in L1 batch metadata, which is the index of the next leaf).
+const FIRST_ENUMERATION_INDEX: u64 = 1;
+
+/// Postgres tables written or read by the snapshot applier.
+const REQUIRED_TABLES: &[&str] = &[
+    "snapshot_recovery",
+    "storage_logs",
+    "initial_writes",
+    "factory_deps",
+    "tokens",
+    "miniblocks",
+    "l1_batches",
+];
+
+/// Postgres error code for serialization failures.
+const SERIALIZATION_FAILURE_CODE: &str = "40001";
+/// Postgres error code for detected deadlocks.
+const DEADLOCK_DETECTED_CODE: &str = "40P01";
 
 #[derive(Debug, thiserror::Error)]
 enum SnapshotsApplierError {
@@ -38,27 +170,37 @@ enum SnapshotsApplierError {
 
 impl SnapshotsApplierError {
     fn object_store(err: ObjectStoreError, context: String) -> Self {
-        match err {
-            ObjectStoreError::KeyNotFound(_) | ObjectStoreError::Serialization(_) => {
-                Self::Fatal(anyhow::Error::from(err).context(context))
-            }
-            ObjectStoreError::Other(_) => {
-                Self::Retryable(anyhow::Error::from(err).context(context))
-            }
+        let is_retryable = matches!(err, ObjectStoreError::Other(_));
+        let err = anyhow::Error::from(RecoveryError::from(err)).context(context);
+        if is_retryable {
+            Self::Retryable(err)
+        } else {
+            Self::Fatal(err)
         }
     }
 
     fn db(err: SqlxError, context: impl Into<String>) -> Self {
         let context = context.into();
-        match err {
-            SqlxError::Database(_)
-            | SqlxError::RowNotFound
-            | SqlxError::ColumnNotFound(_)
-            | SqlxError::Configuration(_)
-            | SqlxError::TypeNotFound { .. } => {
-                Self::Fatal(anyhow::Error::from(err).context(context))
-            }
-            _ => Self::Retryable(anyhow::Error::from(err).context(context)),
+        let is_fatal = match &err {
+            // Serialization failures and deadlocks can occur for concurrent transactions with stricter
+            // isolation levels (see `SnapshotsApplierConfig::chunk_transaction_isolation_level`).
+            SqlxError::Database(db_err) => !matches!(
+                db_err.code().as_deref(),
+                Some(SERIALIZATION_FAILURE_CODE | DEADLOCK_DETECTED_CODE)
+            ),
+            _ => matches!(
+                err,
+                SqlxError::RowNotFound
+                    | SqlxError::ColumnNotFound(_)
+                    | SqlxError::Configuration(_)
+                    | SqlxError::TypeNotFound { .. }
+            ),
+        };
+        let err = anyhow::Error::from(RecoveryError::from(err)).context(context);
+        if is_fatal {
+            Self::Fatal(err)
+        } else {
+            Self::Retryable(err)
         }
     }
 }
@@ -71,18 +213,23 @@ impl From<SnapshotsApplierOutcome> for SnapshotsApplierError {
 
 impl From<RpcError> for SnapshotsApplierError {
     fn from(error: RpcError) -> Self {
-        match error {
-            Error::Transport(_) | Error::RequestTimeout | Error::RestartNeeded(_) => {
-                Self::Retryable(error.into())
-            }
-            _ => Self::Fatal(error.into()),
+        let is_retryable = matches!(
+            error,
+            Error::Transport(_) | Error::RequestTimeout | Error::RestartNeeded(_)
+        );
+        let error = anyhow::Error::from(RecoveryError::from(error));
+        if is_retryable {
+            Self::Retryable(error)
+        } else {
+            Self::Fatal(error)
         }
     }
 }
 
 /// Non-erroneous outcomes of the snapshot application.
 #[must_use = "Depending on app, `NoSnapshotsOnMainNode` may be considered an error"]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SnapshotsApplierOutcome {
     /// The node DB was successfully recovered from a snapshot.
     Ok,
@@ -93,11 +240,266 @@ pub enum SnapshotsApplierOutcome {
 }
 
 /// Main node API used by the [`SnapshotsApplier`].
+///
+/// Only [`Self::fetch_l2_block()`] and [`Self::fetch_newest_snapshot()`] are required for basic recovery.
+/// Other methods are only called for specific snapshots or with specific config options enabled; by default,
+/// they return an error indicating that the method is not supported.
 #[async_trait]
 pub trait SnapshotsApplierMainNodeClient: fmt::Debug + Send + Sync {
     async fn fetch_l2_block(&self, number: MiniblockNumber) -> Result<Option<SyncBlock>, RpcError>;
 
     async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError>;
+
+    /// Fetches metadata for all tokens known as of the specified miniblock. Only called for snapshots
+    /// with [`SnapshotHeader::includes_tokens`] set.
+    async fn fetch_tokens(
+        &self,
+        _at_miniblock: MiniblockNumber,
+    ) -> Result<Vec<TokenInfo>, RpcError> {
+        Err(unsupported_method("fetch_tokens"))
+    }
+
+    /// Fetches the value of the storage slot with the specified `key` as of the specified miniblock.
+    /// Used to reconcile applied storage logs with the main node; only called if
+    /// [`VerificationConfig::main_node_reconciliation_sample_size`] is positive.
+    async fn fetch_storage_value_at(
+        &self,
+        _key: &StorageKey,
+        _miniblock_number: MiniblockNumber,
+    ) -> Result<StorageValue, RpcError> {
+        Err(unsupported_method("fetch_storage_value_at"))
+    }
+
+    /// Fetches the root hash of the specified L1 batch. Returns `Ok(None)` if the batch is unknown
+    /// to the main node or doesn't have a root hash yet. Only called if
+    /// [`SnapshotsApplierConfig::main_node_head_check_interval`] is set.
+    async fn fetch_l1_batch_root_hash(
+        &self,
+        _number: L1BatchNumber,
+    ) -> Result<Option<H256>, RpcError> {
+        Err(unsupported_method("fetch_l1_batch_root_hash"))
+    }
+}
+
+fn unsupported_method(method: &str) -> RpcError {
+    Error::Custom(format!(
+        "`{method}` is not supported by this main node client"
+    ))
+}
+
+/// L1 (Ethereum) API optionally used by the [`SnapshotsApplier`] to verify snapshots against on-chain data.
+#[async_trait]
+pub trait SnapshotsApplierL1Client: fmt::Debug + Send + Sync {
+    /// Fetches the commitment of the specified L1 batch as committed on L1. Returns `Ok(None)`
+    /// if the batch is not committed on L1.
+    async fn fetch_l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> anyhow::Result<Option<H256>>;
+
+    /// Checks whether the specified L1 batch is finalized on L1, i.e., is executed in a finalized L1 block
+    /// and thus cannot be reverted by an L1 reorg.
+    async fn is_l1_batch_finalized(&self, number: L1BatchNumber) -> anyhow::Result<bool>;
+}
+
+/// Codec transforming storage log values read from a snapshot before they are persisted to Postgres.
+///
+/// Can be used by deployments storing values in a packed / encoded form. Unlike validation, a codec
+/// may change values; the decoded value is what ends up in the node storage.
+pub trait ValueCodec: fmt::Debug + Send + Sync {
+    /// Decodes a value for the storage log with the specified `key`.
+    fn decode(&self, key: &StorageKey, value: StorageValue) -> anyhow::Result<StorageValue>;
+}
+
+/// [`ValueCodec`] returning values as is. This is the default codec used by [`SnapshotsApplierConfig`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdentityValueCodec;
+
+impl ValueCodec for IdentityValueCodec {
+    fn decode(&self, _key: &StorageKey, value: StorageValue) -> anyhow::Result<StorageValue> {
+        Ok(value)
+    }
+}
+
+/// Components of the node storage populated by the snapshot applier. By default, all components are populated.
+///
+/// Disabling components allows specialized bootstraps to write only the data they need. Note that if
+/// [`Self::recovery_status`] is disabled, recovery progress is not persisted, and the applier restarts
+/// recovery from scratch on each run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotsApplierComponents {
+    /// Storage logs and initial writes.
+    pub storage_logs: bool,
+    /// Factory dependencies (contract bytecodes).
+    pub factory_deps: bool,
+    /// Token metadata (only applied if the snapshot includes it).
+    pub tokens: bool,
+    /// Snapshot recovery status, including the progress of processing storage log chunks.
+    pub recovery_status: bool,
+}
+
+impl Default for SnapshotsApplierComponents {
+    fn default() -> Self {
+        Self {
+            storage_logs: true,
+            factory_deps: true,
+            tokens: true,
+            recovery_status: true,
+        }
+    }
+}
+
+/// Checks of the snapshot data and the recovered storage performed by the snapshot applier
+/// (see [`SnapshotsApplierConfig::verification`]).
+#[derive(Debug)]
+pub struct VerificationConfig {
+    /// Fraction of storage log chunks (from 0 to 1) re-verified against the object store when the applier
+    /// is restarted after recovery is complete. Allows detecting post-hoc corruption of the node storage.
+    /// Set to 0 to disable verification.
+    pub restart_verification_fraction: f64,
+    /// Whether to re-read the number of persisted storage logs after inserting each chunk and compare it
+    /// with the chunk size before marking the chunk as processed. Guards against silent partial inserts
+    /// at the cost of an extra DB query per chunk.
+    pub verify_chunks_before_marking_processed: bool,
+    /// Whether to check integrity of each storage logs chunk after it's fetched and before it's applied
+    /// (storage keys and enumeration indices are unique within the chunk, and no key is initially written
+    /// after the snapshot L1 batch). Since verification is interleaved with application, each chunk
+    /// is downloaded once. Recovery is aborted with a fatal error on the first chunk failing the check;
+    /// chunks applied before that remain applied.
+    pub verify_chunks_inline: bool,
+    /// Verify Merkle proofs included into storage log chunks by the snapshot creator against the root hash
+    /// of the snapshot L1 batch. Enabled by default; may be disabled to save CPU on the chunk processing path
+    /// if snapshots come from a trusted source.
+    pub verify_storage_log_proofs: bool,
+    /// Whether to check that each contract bytecode hash referenced by applied storage logs (i.e., a value
+    /// in the account code storage) has a corresponding factory dependency. The check is performed for each
+    /// chunk before it's marked as processed; a missing dependency aborts recovery with a fatal error.
+    /// Requires factory dependencies to be recovered (see [`SnapshotsApplierComponents::factory_deps`]).
+    pub verify_factory_deps_completeness: bool,
+    /// Acceptance queries run against the recovered storage once recovery is complete. If any query fails,
+    /// recovery is not declared successful, and the applier returns a fatal error. Queries are run each time
+    /// the applier is started for recovered storage, so a failed acceptance test will not be masked by a restart.
+    pub acceptance_queries: Vec<AcceptanceQuery>,
+    /// Expected checksum of the recovered state, i.e., [`state_checksum()`] of the storage logs and initial writes
+    /// persisted in Postgres (see [`RecoverySummary::state_checksum`]). If set and the checksum of the recovered state differs, recovery
+    /// is not declared successful, and the applier returns a fatal error. This allows pinning the exact state
+    /// when bootstrapping multiple nodes.
+    pub expected_state_checksum: Option<H256>,
+    /// Number of processed storage log chunks between incremental verification checkpoints. At each checkpoint,
+    /// the applier runs a lightweight integrity check of applied storage logs (enumeration indices start from 1,
+    /// and no storage logs from processed chunks are missing) and persists the set of chunks covered by the check
+    /// in Postgres. On resume, [`SnapshotsApplierConfig::repair_processed_chunks`] skips chunks covered by the latest checkpoint.
+    /// Set to 0 to disable checkpoints.
+    pub verification_checkpoint_interval: usize,
+    /// Whether to check that all tables used by the applier exist in Postgres before starting recovery.
+    /// Allows failing early with a clear error if DB migrations are not applied. Disabled by default.
+    pub check_db_schema: bool,
+    /// Whether to check that storage log chunks referenced by the snapshot header resolve under the object store
+    /// prefix (see [`ObjectStore::storage_prefix_raw()`]) before starting recovery. Allows failing early
+    /// if the object store points to the right bucket, but uses a wrong prefix. Only the first referenced chunk
+    /// is checked: its filepath must start with the prefix, and the corresponding object must exist in the store.
+    pub check_storage_prefix: bool,
+    /// Number of storage logs from a random applied chunk compared with values returned by the main node
+    /// after recovery. Allows cross-checking the object store contents against a trusted peer.
+    /// Set to 0 to disable reconciliation.
+    pub main_node_reconciliation_sample_size: usize,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            restart_verification_fraction: 0.0,
+            verify_chunks_before_marking_processed: false,
+            verify_chunks_inline: false,
+            verify_storage_log_proofs: true,
+            verify_factory_deps_completeness: false,
+            acceptance_queries: vec![],
+            expected_state_checksum: None,
+            verification_checkpoint_interval: 0,
+            check_db_schema: false,
+            check_storage_prefix: false,
+            main_node_reconciliation_sample_size: 0,
+        }
+    }
+}
+
+/// Destinations for applied storage logs besides Postgres (see [`SnapshotsApplierConfig::sinks`]).
+#[derive(Debug)]
+pub struct SinksConfig {
+    /// Additional destinations for applied storage logs besides Postgres.
+    pub storage_logs_sinks: Vec<Box<dyn StorageLogsSink>>,
+    /// Address of a standby node to which applied storage log chunks are forwarded (see [`StandbyForwardingSink`]
+    /// for the protocol). Allows bootstrapping the standby node in lockstep, with snapshot objects downloaded once.
+    pub standby_address: Option<std::net::SocketAddr>,
+}
+
+impl Default for SinksConfig {
+    fn default() -> Self {
+        Self {
+            storage_logs_sinks: vec![],
+            standby_address: None,
+        }
+    }
+}
+
+/// Limits on the rate and concurrency of snapshot recovery (see [`SnapshotsApplierConfig::throttling`]).
+#[derive(Debug)]
+pub struct ThrottlingConfig {
+    /// Upper bound for a random delay before the applier starts making requests. Allows staggering recovery
+    /// of many nodes from the same object store (e.g., after a fleet-wide restart). Set to zero to start
+    /// immediately.
+    pub max_start_jitter: Duration,
+    /// Maximum number of storage log chunks downloaded and inserted concurrently. Since each chunk insertion
+    /// holds a DB connection, the value is clamped to the connection pool size. If not set, the pool size is used.
+    pub max_concurrency: Option<usize>,
+    /// Number of processed storage log chunks over which chunk processing concurrency is linearly increased
+    /// from 1 to the maximum concurrency. Set to 0 to start with the full concurrency.
+    pub concurrency_ramp_chunks: usize,
+    /// Maximum number of factory dependency shards fetched concurrently. Only relevant for snapshots
+    /// with sharded factory dependencies.
+    pub factory_deps_concurrency: usize,
+    /// Maximum number of concurrent object store requests per bucket. Allows throttling buckets independently
+    /// if they are served by backends with different rate limits. Requests to buckets not present in the map
+    /// are not limited.
+    pub object_store_bucket_concurrency: HashMap<Bucket, usize>,
+}
+
+impl Default for ThrottlingConfig {
+    fn default() -> Self {
+        Self {
+            max_start_jitter: Duration::ZERO,
+            max_concurrency: None,
+            concurrency_ramp_chunks: 0,
+            factory_deps_concurrency: 4,
+            object_store_bucket_concurrency: HashMap::new(),
+        }
+    }
+}
+
+/// Order in which storage log chunks are scheduled for processing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageLogsChunkOrder {
+    /// Chunks are processed in the order of their IDs.
+    #[default]
+    Sequential,
+    /// Largest chunks (by the object size reported by the object store) are processed first. This reduces
+    /// the straggler effect at the end of recovery. Falls back to [`Self::Sequential`] if the object store
+    /// cannot report object sizes.
+    LargestFirst,
+    /// Chunks are grouped by the locality of their objects in the object store (see [`LocalityOrdering`]).
+    Locality(LocalityOrdering),
+}
+
+/// Handling of snapshots for L1 batches that are not finalized on L1 (e.g., snapshots exported during an L1 reorg).
+/// Only applies if [`SnapshotsApplierConfig::l1_client`] is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinalizedSnapshotPolicy {
+    /// Recovery is refused with a fatal error. The snapshot L1 batch may be orphaned, so recovering to it
+    /// may lead to a node state diverging from the canonical chain.
+    #[default]
+    Refuse,
+    /// Recovery proceeds with a warning.
+    Allow,
 }
 
 /// Snapshot applier configuration options.
@@ -106,6 +508,169 @@ pub struct SnapshotsApplierConfig {
     pub retry_count: usize,
     pub initial_retry_backoff: Duration,
     pub retry_backoff_multiplier: f32,
+    /// Number of retries for individual object store requests failing with a transient error. These retries
+    /// are performed before retrying the entire recovery.
+    pub object_store_retry_count: usize,
+    /// Initial backoff for retrying object store requests. The backoff is multiplied by [`Self::retry_backoff_multiplier`]
+    /// after each retry.
+    pub object_store_initial_retry_backoff: Duration,
+    /// Whether to retry object store requests throttled by the backend (see [`ObjectStoreThrottled`]) after the delay
+    /// requested by the backend (e.g., in the `Retry-After` HTTP header) instead of using the object store backoff.
+    /// Disabled by default.
+    pub respect_retry_after: bool,
+    /// Maximum duration of the entire recovery, including all retries. If not set, recovery is not time-limited.
+    pub max_recovery_duration: Option<Duration>,
+    /// Receiver of the process shutdown signal. Once `true` is sent, the applier stops starting new storage log
+    /// chunks and waits for in-flight chunks to commit for up to [`Self::shutdown_grace_period`]. Chunks
+    /// not committed by then are rolled back, and recovery is cancelled with [`CancellationReason::Shutdown`];
+    /// it can be resumed on the next start. If not set, shutdown signals are not handled.
+    pub stop_receiver: Option<watch::Receiver<bool>>,
+    /// Grace period for committing in-flight storage log chunks after a shutdown signal is received
+    /// via [`Self::stop_receiver`].
+    pub shutdown_grace_period: Duration,
+    /// Codec applied to each storage log value before it is persisted.
+    pub value_codec: Box<dyn ValueCodec>,
+    /// Decoders for snapshot objects keyed by the snapshot data format version. The default registry supports
+    /// format version 0.
+    pub decoder_registry: SnapshotDecoderRegistry,
+    /// Maximum size of a single storage value in bytes. Values exceeding it are rejected with a [`ValueTooLarge`]
+    /// error. Only applies to snapshot data formats with variable-size values; see [`DecodingLimits`].
+    pub max_storage_value_size: usize,
+    /// Maximum size of a storage logs chunk in bytes after decompressing it with the compression dictionary
+    /// referenced by the snapshot header. Chunks exceeding it are rejected; see [`DecodingLimits`].
+    pub max_decompressed_chunk_size: usize,
+    /// Checks of the snapshot data and the recovered storage.
+    pub verification: VerificationConfig,
+    /// Maximum age of an in-progress recovery (i.e., time elapsed since it was started, as measured by the DB clock)
+    /// for the applier to resume it. If an incomplete recovery is older, all its persisted data is deleted,
+    /// and recovery is restarted from the newest snapshot advertised by the main node. If not set, incomplete
+    /// recovery is always resumed. Ignored if [`Self::chunk_id_range`] is set, since other appliers may be
+    /// working on the same recovery.
+    pub max_resume_age: Option<Duration>,
+    /// Destinations for applied storage logs besides Postgres.
+    pub sinks: SinksConfig,
+    /// Health check updated with the recovery progress. Should be cloned before running the applier
+    /// in order to be included into the app health.
+    pub health_check: SnapshotsApplierHealthCheck,
+    /// Limits on the rate and concurrency of recovery.
+    pub throttling: ThrottlingConfig,
+    /// Maximum duration to wait for the main node to advertise a snapshot when starting fresh recovery. The main node
+    /// is polled with the backoff used for retrying recovery (see [`Self::retry_backoff_strategy`]). If not set
+    /// or if no snapshot is advertised in time, the applier returns [`SnapshotsApplierOutcome::NoSnapshotsOnMainNode`].
+    pub snapshot_wait_timeout: Option<Duration>,
+    /// L1 client used to verify the snapshot L1 batch commitment against the one committed on L1 before
+    /// applying the snapshot. If not set, the commitment is not verified.
+    pub l1_client: Option<Box<dyn SnapshotsApplierL1Client>>,
+    /// Handling of snapshots for L1 batches not finalized on L1, as reported by [`Self::l1_client`].
+    pub non_finalized_snapshot_policy: NonFinalizedSnapshotPolicy,
+    /// Components of the node storage populated by the applier.
+    pub components: SnapshotsApplierComponents,
+    /// Whether to check on startup that storage log chunks marked as processed are actually persisted
+    /// in Postgres, and to reset the processed flag for chunks that are not (so that they are re-applied).
+    /// Requires fetching all processed chunks from the object store.
+    pub repair_processed_chunks: bool,
+    /// Whether to distrust processed flags for storage log chunks persisted in Postgres when resuming recovery,
+    /// and to re-derive them from the persisted storage logs instead. This allows resuming recovery if the flags
+    /// are corrupted (e.g., have a wrong length, or are reset after a crash). Requires the snapshot being recovered
+    /// to be the newest snapshot on the main node, and fetching all storage log chunks from the object store.
+    /// If set, [`Self::repair_processed_chunks`] has no effect.
+    pub reconcile_progress: bool,
+    /// Whether to re-verify the most recently processed storage log chunk when resuming recovery, i.e. check that
+    /// all its storage logs are persisted in Postgres. Chunks are started in the order of their IDs, so the processed
+    /// chunk with the greatest ID is considered the most recent one. If the chunk is only partially persisted,
+    /// its persisted data is removed, and the chunk is re-applied. This is a cheaper alternative
+    /// to [`Self::repair_processed_chunks`], which checks all processed chunks.
+    pub reverify_last_processed_chunk: bool,
+    /// If set, recovery is aborted with a [`SnapshotRecoveryStalled`] error (and then retried) if no storage log
+    /// chunks are processed during this timeout.
+    pub stall_timeout: Option<Duration>,
+    /// If set, the root hash of the snapshot L1 batch is periodically re-fetched from the main node with this interval
+    /// while storage logs are recovered. If the main node returns a different root hash (e.g., because of a reorg
+    /// at the snapshot boundary), recovery is aborted with a fatal error to avoid recovering to an orphaned state.
+    /// Transient RPC errors and a missing root hash are tolerated unless they persist for several consecutive checks.
+    pub main_node_head_check_interval: Option<Duration>,
+    /// Whether to apply storage logs and initial writes into staging tables, which are moved into `storage_logs`
+    /// and `initial_writes` in a single DB transaction once all chunks are applied. This way, the live tables
+    /// are only modified once all storage logs are recovered.
+    ///
+    /// Cannot be used together with options reading or modifying applied storage logs before all chunks are applied
+    /// (e.g., [`Self::reconcile_progress`] or [`VerificationConfig::verify_chunks_before_marking_processed`]),
+    /// or with [`Self::chunk_id_range`].
+    pub apply_via_staging_tables: bool,
+    /// Isolation level of DB transactions applying storage log chunks. If not set, the default isolation level
+    /// configured for the database is used. With stricter isolation levels, concurrent chunk transactions
+    /// may fail with serialization errors; such errors are retried.
+    pub chunk_transaction_isolation_level: Option<IsolationLevel>,
+    /// Path to write the manifest of applied data to once recovery is complete (see [`AppliedManifest`]).
+    /// Chunks applied by previous applier runs (or by other appliers if recovery is sharded) are re-fetched
+    /// from the object store to compute their digests. If not set, no manifest is written.
+    pub applied_manifest_path: Option<PathBuf>,
+    /// Number of threads used to compute checksums of storage log chunks (see [`storage_logs_checksum()`]).
+    /// Checksums don't depend on this value.
+    pub checksum_parallelism: usize,
+    /// Postgres sequence generating enumeration indices for new storage keys, if the node DB maintains one.
+    /// If set, once all storage logs are applied, the sequence is set to the maximum applied enumeration index + 1,
+    /// so that indices assigned by subsequent live inserts don't collide with recovered ones. If the sequence
+    /// is already ahead of this value, it is left intact, and a warning is reported.
+    pub enumeration_index_sequence: Option<String>,
+    /// Postgres relations (tables or indices, e.g. `storage_logs_pkey`) loaded into the buffer cache using
+    /// `pg_prewarm` after each storage logs chunk is applied, so that reads after recovery are fast.
+    /// Prewarming is a hint: if it fails (e.g., because the `pg_prewarm` extension is not installed),
+    /// a warning is logged, and recovery continues. Since each relation is prewarmed as a whole, this should
+    /// only be used for relations fitting into the buffer cache.
+    pub prewarm_relations: Vec<String>,
+    /// Timeout for decoding a single storage logs chunk. Guards against malformed chunks that take
+    /// an excessive amount of CPU or memory to decode (e.g., because of a huge decompressed size). Decoding
+    /// is performed on a blocking thread, which cannot be cancelled; thus, on timeout the decoding thread
+    /// continues running in the background until it completes. If not set, decoding is not time-limited.
+    pub chunk_decode_timeout: Option<Duration>,
+    /// Recovery progress tracked outside the node storage (e.g., by an orchestrator coordinating recovery
+    /// of multiple nodes). If set, storage log chunks marked as processed in this status are skipped in addition
+    /// to the chunks marked as processed in Postgres. The status must correspond to the recovered snapshot.
+    pub initial_status: Option<SnapshotRecoveryStatus>,
+    /// If set, recovery refuses to start if available disk space on the specified volume is lower than
+    /// the estimated footprint of the recovered data.
+    pub disk_space_check: Option<DiskSpaceCheck>,
+    /// Order in which storage log chunks are scheduled for processing.
+    pub chunk_order: StorageLogsChunkOrder,
+    /// Handle exposing the internal applier state for debugging. Should be cloned before running the applier.
+    pub debug_handle: SnapshotsApplierDebugHandle,
+    /// **Dangerous.** Whether to apply snapshots with a protocol version newer than the latest version supported
+    /// by this node. The node will most probably be unable to process blocks after recovery from such a snapshot.
+    pub allow_newer_protocol_version: bool,
+    /// Whether to escalate warning-level conditions to fatal errors, e.g. for CI or other environments
+    /// that should be reproducible. Such conditions include applying a snapshot allowed by a lenient config option
+    /// (e.g., [`Self::allow_newer_protocol_version`]), inconsistent persisted recovery progress repaired on resume,
+    /// and degraded checks (e.g., chunk sizes unavailable for [`Self::disk_space_check`]). Transient errors
+    /// retried by the applier and adjustments of the config itself (e.g., clamping [`ThrottlingConfig::max_concurrency`])
+    /// are not escalated.
+    pub fail_on_warnings: bool,
+    /// Custom backoff strategy for retrying the entire recovery. If not set, exponential backoff with jitter
+    /// based on [`Self::initial_retry_backoff`] and [`Self::retry_backoff_multiplier`] is used.
+    pub retry_backoff_strategy: Option<Box<dyn BackoffStrategy>>,
+    /// Custom backoff strategy for retrying individual object store requests. If not set, exponential backoff
+    /// with jitter based on [`Self::object_store_initial_retry_backoff`] and [`Self::retry_backoff_multiplier`]
+    /// is used.
+    pub object_store_retry_backoff_strategy: Option<Box<dyn BackoffStrategy>>,
+    /// Budget shared by object store request retries and retries of the entire recovery. Allows capping
+    /// the aggregate number of retries caused by a flaky backend: once the budget is exhausted, transient errors
+    /// are not retried. If not set, only per-operation retry counts apply.
+    pub retry_budget: Option<RetryBudget>,
+    /// Range of storage log chunk IDs applied by this applier. Allows sharding recovery of a large snapshot
+    /// across multiple processes sharing the same Postgres: each process is configured with its own range
+    /// (ranges should be disjoint and cover all chunks). Recovery initialization and processing of each chunk
+    /// are guarded by Postgres advisory locks, so overlapping ranges don't lead to conflicts. Checks requiring
+    /// the entire snapshot to be applied are performed by the applier that finishes last. If not set, all chunks
+    /// are applied.
+    pub chunk_id_range: Option<Range<u64>>,
+    /// Address to serve recovery progress on (`GET /recovery/progress`) while the applier is running. The progress
+    /// is returned as JSON combining details of [`Self::health_check`] and the state of [`Self::debug_handle`],
+    /// so that it can be polled by operators without DB access. If not set, progress is not served.
+    #[cfg(feature = "progress-server")]
+    pub progress_server_address: Option<std::net::SocketAddr>,
+    /// Failures injected into object store reads, main node RPC calls and DB inserts for chaos testing.
+    #[cfg(feature = "chaos")]
+    pub failure_injection: FailureInjection,
 }
 
 impl Default for SnapshotsApplierConfig {
@@ -114,6 +679,51 @@ impl Default for SnapshotsApplierConfig {
             retry_count: 5,
             initial_retry_backoff: Duration::from_secs(2),
             retry_backoff_multiplier: 2.0,
+            object_store_retry_count: 3,
+            object_store_initial_retry_backoff: Duration::from_millis(500),
+            respect_retry_after: false,
+            max_recovery_duration: None,
+            stop_receiver: None,
+            shutdown_grace_period: Duration::from_secs(10),
+            value_codec: Box::new(IdentityValueCodec),
+            decoder_registry: SnapshotDecoderRegistry::default(),
+            max_storage_value_size: DecodingLimits::default().max_storage_value_size,
+            max_decompressed_chunk_size: DecodingLimits::default().max_decompressed_chunk_size,
+            verification: VerificationConfig::default(),
+            max_resume_age: None,
+            sinks: SinksConfig::default(),
+            health_check: SnapshotsApplierHealthCheck::default(),
+            throttling: ThrottlingConfig::default(),
+            snapshot_wait_timeout: None,
+            l1_client: None,
+            non_finalized_snapshot_policy: NonFinalizedSnapshotPolicy::default(),
+            components: SnapshotsApplierComponents::default(),
+            repair_processed_chunks: false,
+            reconcile_progress: false,
+            reverify_last_processed_chunk: false,
+            stall_timeout: None,
+            main_node_head_check_interval: None,
+            apply_via_staging_tables: false,
+            chunk_transaction_isolation_level: None,
+            applied_manifest_path: None,
+            checksum_parallelism: 1,
+            enumeration_index_sequence: None,
+            prewarm_relations: vec![],
+            chunk_decode_timeout: None,
+            initial_status: None,
+            disk_space_check: None,
+            chunk_order: StorageLogsChunkOrder::default(),
+            debug_handle: SnapshotsApplierDebugHandle::default(),
+            allow_newer_protocol_version: false,
+            fail_on_warnings: false,
+            retry_backoff_strategy: None,
+            object_store_retry_backoff_strategy: None,
+            retry_budget: None,
+            chunk_id_range: None,
+            #[cfg(feature = "progress-server")]
+            progress_server_address: None,
+            #[cfg(feature = "chaos")]
+            failure_injection: FailureInjection::default(),
         }
     }
 }
@@ -123,70 +733,338 @@ impl SnapshotsApplierConfig {
     fn for_tests() -> Self {
         Self {
             initial_retry_backoff: Duration::from_millis(5),
+            object_store_initial_retry_backoff: Duration::from_millis(5),
             ..Self::default()
         }
     }
 
+    /// Returns the backoff strategy for retrying the entire recovery.
+    fn retry_backoff(&self) -> Box<dyn BackoffStrategy + '_> {
+        match &self.retry_backoff_strategy {
+            Some(strategy) => Box::new(strategy.as_ref()),
+            None => Box::new(
+                ExponentialBackoff::new(self.initial_retry_backoff, self.retry_backoff_multiplier)
+                    .with_jitter(ExponentialBackoff::DEFAULT_JITTER),
+            ),
+        }
+    }
+
+    /// Checks that [`Self::apply_via_staging_tables`] is not used with incompatible options.
+    fn check_staging_tables_compatibility(&self) -> anyhow::Result<()> {
+        if !self.apply_via_staging_tables {
+            return Ok(());
+        }
+        let incompatible_options = [
+            ("reconcile_progress", self.reconcile_progress),
+            ("repair_processed_chunks", self.repair_processed_chunks),
+            (
+                "reverify_last_processed_chunk",
+                self.reverify_last_processed_chunk,
+            ),
+            (
+                "verify_chunks_before_marking_processed",
+                self.verification.verify_chunks_before_marking_processed,
+            ),
+            (
+                "verification_checkpoint_interval",
+                self.verification.verification_checkpoint_interval != 0,
+            ),
+            ("chunk_id_range", self.chunk_id_range.is_some()),
+        ];
+        for (name, is_set) in incompatible_options {
+            anyhow::ensure!(
+                !is_set,
+                "`apply_via_staging_tables` cannot be used together with `{name}`"
+            );
+        }
+        Ok(())
+    }
+
+    fn decoding_limits(&self) -> DecodingLimits {
+        DecodingLimits {
+            max_storage_value_size: self.max_storage_value_size,
+            max_decompressed_chunk_size: self.max_decompressed_chunk_size,
+        }
+    }
+
+    /// Returns the backoff strategy for retrying object store requests.
+    fn object_store_retry_backoff(&self) -> Box<dyn BackoffStrategy + '_> {
+        match &self.object_store_retry_backoff_strategy {
+            Some(strategy) => Box::new(strategy.as_ref()),
+            None => Box::new(
+                ExponentialBackoff::new(
+                    self.object_store_initial_retry_backoff,
+                    self.retry_backoff_multiplier,
+                )
+                .with_jitter(ExponentialBackoff::DEFAULT_JITTER),
+            ),
+        }
+    }
+
+    /// Reports a warning-level condition. If [`Self::fail_on_warnings`] is set, the condition is escalated
+    /// to an error instead.
+    fn report_warning(&self, message: fmt::Arguments<'_>) -> anyhow::Result<()> {
+        if self.fail_on_warnings {
+            anyhow::bail!("{message} (escalated to an error since `fail_on_warnings` is set)");
+        }
+        tracing::warn!("{message}");
+        Ok(())
+    }
+
+    /// Returns the storage logs chunk processing concurrency for a connection pool with the specified size.
+    fn effective_concurrency(&self, pool_size: u32) -> usize {
+        let pool_size = pool_size as usize;
+        let concurrency = match self.throttling.max_concurrency {
+            Some(concurrency) if concurrency > pool_size => {
+                tracing::warn!(
+                    "Configured snapshot recovery concurrency {concurrency} exceeds the connection pool size; \
+                     clamping concurrency to {pool_size} to prevent pool exhaustion"
+                );
+                pool_size
+            }
+            Some(concurrency) => concurrency,
+            None => pool_size,
+        };
+        concurrency.max(1)
+    }
+
+    /// Chooses a start delay uniformly distributed in `[0, max_start_jitter]`.
+    fn start_delay(&self, rng: &mut impl Rng) -> Duration {
+        if self.throttling.max_start_jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        rng.gen_range(Duration::ZERO..=self.throttling.max_start_jitter)
+    }
+
     /// Runs the snapshot applier with these options.
+    #[tracing::instrument(name = "snapshot_recovery", skip_all)]
     pub async fn run(
-        self,
+        mut self,
         connection_pool: &ConnectionPool,
         main_node_client: &dyn SnapshotsApplierMainNodeClient,
         blob_store: &dyn ObjectStore,
     ) -> anyhow::Result<SnapshotsApplierOutcome> {
-        let mut backoff = self.initial_retry_backoff;
+        if let Some(address) = self.sinks.standby_address {
+            tracing::info!("Forwarding applied storage log chunks to standby node at {address}");
+            let sink = StandbyForwardingSink::new(address);
+            self.sinks.storage_logs_sinks.push(Box::new(sink));
+        }
+        #[cfg(feature = "progress-server")]
+        let _progress_server = self
+            .progress_server_address
+            .map(|address| {
+                let health_check = self.health_check.clone();
+                progress::ProgressServerGuard::spawn(
+                    address,
+                    health_check,
+                    self.debug_handle.clone(),
+                )
+            })
+            .transpose()?;
+
+        self.check_staging_tables_compatibility()?;
+        let start_delay = self.start_delay(&mut rand::thread_rng());
+        if !start_delay.is_zero() {
+            tracing::info!("Delaying snapshot recovery start by {start_delay:?}");
+            tokio::time::sleep(start_delay).await;
+        }
+        #[cfg(feature = "chaos")]
+        let main_node_client =
+            &chaos::FailingMainNodeClient::new(main_node_client, self.failure_injection);
+
+        let deadline = self
+            .max_recovery_duration
+            .map(|duration| Instant::now() + duration);
+        let backoff_strategy = self.retry_backoff();
+        let retry_budget = self.retry_budget.map(RetryTokenBucket::new);
         let mut last_error = None;
         for retry_id in 0..self.retry_count {
-            let result =
-                SnapshotsApplier::load_snapshot(connection_pool, main_node_client, blob_store)
-                    .await;
+            let load_future = SnapshotsApplier::load_snapshot(
+                &self,
+                deadline,
+                retry_budget.as_ref(),
+                connection_pool,
+                main_node_client,
+                blob_store,
+            );
+            let result = if let Some(deadline) = deadline {
+                tokio::time::timeout_at(deadline, load_future)
+                    .await
+                    .unwrap_or_else(|_| {
+                        let reason = CancellationReason::Deadline {
+                            max_recovery_duration: self.max_recovery_duration.unwrap_or_default(),
+                        };
+                        let err = SnapshotRecoveryCancelled::from(reason);
+                        Err(SnapshotsApplierError::Fatal(err.into()))
+                    })
+            } else {
+                load_future.await
+            };
 
             match result {
-                Ok(()) => return Ok(SnapshotsApplierOutcome::Ok),
+                Ok(()) => {
+                    self.health_check.finished(SnapshotsApplierOutcome::Ok);
+                    return Ok(SnapshotsApplierOutcome::Ok);
+                }
                 Err(SnapshotsApplierError::Fatal(err)) => {
+                    if let Some(reason) = CancellationReason::from_error(&err) {
+                        tracing::warn!("Snapshots recovery was cancelled: {reason}");
+                    }
                     tracing::error!("Fatal error occurred during snapshots recovery: {err:?}");
+                    self.health_check.failed(&err);
                     return Err(err);
                 }
                 Err(SnapshotsApplierError::EarlyReturn(outcome)) => {
                     tracing::info!("Cancelled snapshots recovery with the outcome: {outcome:?}");
+                    self.health_check.finished(outcome);
                     return Ok(outcome);
                 }
                 Err(SnapshotsApplierError::Retryable(err)) => {
                     tracing::warn!("Retryable error occurred during snapshots recovery: {err:?}");
+                    if retry_budget
+                        .as_ref()
+                        .is_some_and(|budget| !budget.try_acquire())
+                    {
+                        tracing::warn!(
+                            "Not retrying snapshots recovery since the retry budget is exhausted"
+                        );
+                        let reason = CancellationReason::RetryBudgetExhausted;
+                        last_error = Some(err.context(SnapshotRecoveryCancelled::from(reason)));
+                        break;
+                    }
+                    let backoff = backoff_strategy.retry_delay(retry_id + 1);
+                    if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                        tracing::warn!(
+                            "Not retrying snapshots recovery since it would overrun the deadline"
+                        );
+                        let reason = CancellationReason::Deadline {
+                            max_recovery_duration: self.max_recovery_duration.unwrap_or_default(),
+                        };
+                        last_error = Some(err.context(SnapshotRecoveryCancelled::from(reason)));
+                        break;
+                    }
                     last_error = Some(err);
                     tracing::info!(
                         "Recovering from error; attempt {retry_id} / {}, retrying in {backoff:?}",
                         self.retry_count
                     );
                     tokio::time::sleep(backoff).await;
-                    backoff = backoff.mul_f32(self.retry_backoff_multiplier);
                 }
             }
         }
 
         let last_error = last_error.unwrap(); // `unwrap()` is safe: `last_error` was assigned at least once
+        if let Some(reason) = CancellationReason::from_error(&last_error) {
+            tracing::warn!("Snapshots recovery was cancelled: {reason}");
+        }
         tracing::error!("Snapshot recovery run out of retries; last error: {last_error:?}");
+        self.health_check.failed(&last_error);
         Err(last_error)
     }
 }
 
+/// Returns the number of storage log chunks from the `header` that are not yet applied according
+/// to the `status` loaded from Postgres. Does not start recovery or access any external resources,
+/// so it can be used for pre-flight scheduling decisions.
+pub fn estimate_chunks_remaining(
+    status: &SnapshotRecoveryStatus,
+    header: &SnapshotHeader,
+) -> usize {
+    header
+        .storage_logs_chunks
+        .iter()
+        .filter(|chunk| {
+            let is_processed = usize::try_from(chunk.chunk_id)
+                .ok()
+                .and_then(|idx| status.storage_logs_chunks_processed.get(idx));
+            is_processed != Some(&true)
+        })
+        .count()
+}
+
+/// Returns the snapshot recovery status persisted in Postgres, or `None` if the node storage
+/// was not recovered from a snapshot (or recovery hasn't started yet). The status may correspond to
+/// an incomplete recovery; use [`SnapshotRecoveryStatus::storage_logs_chunks_left_to_process()`] to check.
+///
+/// # Errors
+///
+/// Propagates DB errors.
+pub async fn applied_snapshot_status(
+    connection_pool: &ConnectionPool,
+) -> anyhow::Result<Option<SnapshotRecoveryStatus>> {
+    let mut storage = connection_pool
+        .access_storage_tagged("snapshots_applier")
+        .await?;
+    storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .context("failed fetching applied snapshot status from DB")
+}
+
 /// Applying application-level storage snapshots to the Postgres storage.
 #[derive(Debug)]
 struct SnapshotsApplier<'a> {
+    config: &'a SnapshotsApplierConfig,
     connection_pool: &'a ConnectionPool,
-    blob_store: &'a dyn ObjectStore,
+    blob_store: RetryingObjectStore<'a>,
     applied_snapshot_status: SnapshotRecoveryStatus,
+    /// Storage log chunks addressed by their content hash, keyed by the chunk ID.
+    content_addressed_chunks: HashMap<u64, ContentAddressedChunk>,
+    /// Template for storage logs chunk keys specified in the snapshot header.
+    path_template: Option<PathTemplate>,
+    checkpoint_state: tokio::sync::Mutex<CheckpointState>,
+    /// Chunks applied by this applier; only recorded if the applied data manifest is written.
+    applied_chunks: Mutex<Vec<AppliedChunk>>,
+    /// Data format version of snapshot objects.
+    format_version: u32,
+    /// Dictionary used to decompress storage log chunks, if specified in the snapshot header.
+    compression_dictionary: Option<CompressionDictionary>,
+}
+
+/// State of incremental verification checkpoints.
+#[derive(Debug, Default)]
+struct CheckpointState {
+    /// Latest persisted checkpoint.
+    checkpoint: SnapshotRecoveryCheckpoint,
+    /// IDs and storage log counts of chunks processed since the latest checkpoint.
+    pending_chunks: Vec<(u64, u64)>,
+}
+
+/// Storage logs chunk stored in the object store under its content hash rather than under the standard key.
+#[derive(Debug)]
+struct ContentAddressedChunk {
+    /// Object key as specified in the snapshot header.
+    key: String,
+    /// Keccak-256 hash of the chunk object.
+    hash: H256,
 }
 
 impl<'a> SnapshotsApplier<'a> {
     /// Recovers [`SnapshotRecoveryStatus`] from the storage and the main node.
     async fn prepare_applied_snapshot_status(
+        config: &SnapshotsApplierConfig,
         storage: &mut StorageProcessor<'_>,
         main_node_client: &dyn SnapshotsApplierMainNodeClient,
-    ) -> Result<(SnapshotRecoveryStatus, bool), SnapshotsApplierError> {
+    ) -> Result<(SnapshotRecoveryStatus, Option<SnapshotHeader>), SnapshotsApplierError> {
         let latency =
             METRICS.initial_stage_duration[&InitialStage::FetchMetadataFromMainNode].start();
 
+        let status_l1_batches = storage
+            .snapshot_recovery_dal()
+            .get_recovery_status_l1_batches(2)
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching snapshot recovery status rows")
+            })?;
+        if let [first_l1_batch, second_l1_batch] = status_l1_batches.as_slice() {
+            let err = anyhow::anyhow!(
+                "snapshot recovery status is ambiguous: there are multiple status rows \
+                 (for L1 batches #{first_l1_batch} and #{second_l1_batch})"
+            );
+            return Err(SnapshotsApplierError::Fatal(err));
+        }
+
         let applied_snapshot_status = storage
             .snapshot_recovery_dal()
             .get_applied_snapshot_status()
@@ -200,36 +1078,114 @@ impl<'a> SnapshotsApplier<'a> {
                 .storage_logs_chunks_processed
                 .contains(&false)
             {
-                return Err(SnapshotsApplierOutcome::Ok.into());
+                // Recovery is complete; the caller is responsible for returning early.
+                return Ok((applied_snapshot_status, None));
             }
 
-            let latency = latency.observe();
-            tracing::info!("Re-initialized snapshots applier after reset/failure in {latency:?}");
-
-            Ok((applied_snapshot_status, false))
-        } else {
-            let is_genesis_needed =
-                storage
-                    .blocks_dal()
-                    .is_genesis_needed()
-                    .await
-                    .map_err(|err| {
-                        SnapshotsApplierError::db(err, "failed checking genesis L1 batch in DB")
-                    })?;
-            if !is_genesis_needed {
-                return Err(SnapshotsApplierOutcome::InitializedWithoutSnapshot.into());
+            if !Self::is_recovery_stale(config, storage).await? {
+                Self::check_resumed_recovery_timestamps(&applied_snapshot_status, main_node_client)
+                    .await?;
+                let latency = latency.observe();
+                tracing::info!(
+                    "Re-initialized snapshots applier after reset/failure in {latency:?}"
+                );
+                return Ok((applied_snapshot_status, None));
             }
+            Self::ensure_no_post_recovery_data(storage).await?;
+            storage
+                .snapshot_recovery_dal()
+                .delete_recovery_data()
+                .await
+                .map_err(|err| {
+                    SnapshotsApplierError::db(err, "failed deleting stale recovery data")
+                })?;
+            tracing::info!(
+                "Deleted data of stale recovery from L1 batch #{}; restarting recovery from the newest snapshot",
+                applied_snapshot_status.l1_batch_number
+            );
+        }
+
+        let is_genesis_needed = storage
+            .blocks_dal()
+            .is_genesis_needed()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed checking genesis L1 batch in DB")
+            })?;
+        if !is_genesis_needed {
+            return Err(SnapshotsApplierOutcome::InitializedWithoutSnapshot.into());
+        }
+
+        let latency = latency.observe();
+        tracing::info!("Initialized fresh snapshots applier in {latency:?}");
+        let (status, header) =
+            SnapshotsApplier::create_fresh_recovery_status(config, main_node_client).await?;
+        Ok((status, Some(header)))
+    }
+
+    /// Checks that the node storage contains no miniblocks or L1 batches. These are only created after
+    /// recovery is complete, so their presence means that wiping recovery data would corrupt the node state.
+    async fn ensure_no_post_recovery_data(
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let sealed_miniblock_number = storage
+            .blocks_dal()
+            .get_sealed_miniblock_number()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching sealed miniblock number")
+            })?;
+        let sealed_l1_batch_number = storage
+            .blocks_dal()
+            .get_sealed_l1_batch_number()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching sealed L1 batch number")
+            })?;
+        if sealed_miniblock_number.is_some() || sealed_l1_batch_number.is_some() {
+            let err = anyhow::anyhow!(
+                "refusing to restart stale recovery: node storage contains post-recovery data \
+                 (last miniblock: {sealed_miniblock_number:?}, last L1 batch: {sealed_l1_batch_number:?})"
+            );
+            return Err(SnapshotsApplierError::Fatal(err));
+        }
+        Ok(())
+    }
 
-            let latency = latency.observe();
-            tracing::info!("Initialized fresh snapshots applier in {latency:?}");
-            Ok((
-                SnapshotsApplier::create_fresh_recovery_status(main_node_client).await?,
-                true,
-            ))
+    /// Checks whether an incomplete recovery is older than [`SnapshotsApplierConfig::max_resume_age`].
+    async fn is_recovery_stale(
+        config: &SnapshotsApplierConfig,
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<bool, SnapshotsApplierError> {
+        let Some(max_resume_age) = config.max_resume_age else {
+            return Ok(false);
+        };
+        if config.chunk_id_range.is_some() {
+            return Ok(false);
+        }
+        let age = storage
+            .snapshot_recovery_dal()
+            .get_recovery_age()
+            .await
+            .map_err(|err| SnapshotsApplierError::db(err, "failed fetching recovery age"))?;
+        // The age is `None` only if the recovery status was concurrently deleted, which shouldn't happen.
+        let Some(age) = age else {
+            return Ok(false);
+        };
+        let is_stale = age > max_resume_age;
+        if is_stale {
+            tracing::warn!(
+                "Incomplete recovery was started {age:?} ago, which exceeds max resume age {max_resume_age:?}"
+            );
         }
+        Ok(is_stale)
     }
 
+    #[tracing::instrument(skip_all)]
     async fn load_snapshot(
+        config: &'a SnapshotsApplierConfig,
+        deadline: Option<Instant>,
+        retry_budget: Option<&'a RetryTokenBucket>,
         connection_pool: &'a ConnectionPool,
         main_node_client: &dyn SnapshotsApplierMainNodeClient,
         blob_store: &'a dyn ObjectStore,
@@ -237,19 +1193,151 @@ impl<'a> SnapshotsApplier<'a> {
         let mut storage = connection_pool
             .access_storage_tagged("snapshots_applier")
             .await?;
+        if config.verification.check_db_schema {
+            Self::check_db_schema(&mut storage).await?;
+        }
         let mut storage_transaction = storage.start_transaction().await.map_err(|err| {
             SnapshotsApplierError::db(err, "failed starting initial DB transaction")
         })?;
+        if config.chunk_id_range.is_some() {
+            // Other processes may recover the same storage concurrently; ensure that only one of them initializes recovery.
+            storage_transaction
+                .snapshot_recovery_dal()
+                .lock_recovery_initialization()
+                .await
+                .map_err(|err| {
+                    SnapshotsApplierError::db(err, "failed locking recovery initialization")
+                })?;
+        }
 
-        let (applied_snapshot_status, created_from_scratch) =
-            Self::prepare_applied_snapshot_status(&mut storage_transaction, main_node_client)
-                .await?;
+        let (mut applied_snapshot_status, fresh_header) = Self::prepare_applied_snapshot_status(
+            config,
+            &mut storage_transaction,
+            main_node_client,
+        )
+        .await?;
+        if let Some(initial_status) = &config.initial_status {
+            Self::seed_recovery_progress(initial_status, &mut applied_snapshot_status)?;
+        }
+        let created_from_scratch = fresh_header.is_some();
+        let (includes_tokens, factory_deps_shards) =
+            fresh_header.as_ref().map_or((false, vec![]), |header| {
+                (header.includes_tokens, header.factory_deps_shards.clone())
+            });
+        let header = if fresh_header.is_some() {
+            fresh_header
+        } else if config.reconcile_progress {
+            let header = Self::fetch_header_for_reconciliation(
+                config,
+                main_node_client,
+                &mut applied_snapshot_status,
+            )
+            .await?;
+            Some(header)
+        } else if Self::needs_header_on_resume(config, &applied_snapshot_status) {
+            Self::fetch_header_on_resume(main_node_client, &applied_snapshot_status).await?
+        } else {
+            None
+        };
+        let (path_template, content_addressed_chunks) =
+            Self::storage_logs_chunk_keys(header.as_ref())?;
+        // If the header is not available on resume, the snapshot is assumed to use the base format.
+        let format_version = header.as_ref().map_or(0, |header| header.format_version);
+        Self::check_format_version(config, format_version, created_from_scratch)?;
+        let checkpoint = if created_from_scratch {
+            None
+        } else {
+            storage_transaction
+                .snapshot_recovery_dal()
+                .get_verification_checkpoint()
+                .await
+                .map_err(|err| {
+                    SnapshotsApplierError::db(err, "failed fetching verification checkpoint")
+                })?
+        };
+        let chunk_count = applied_snapshot_status.storage_logs_chunks_processed.len();
+        let checkpoint = checkpoint
+            .filter(|checkpoint| checkpoint.verified_chunks.len() == chunk_count)
+            .unwrap_or_else(|| SnapshotRecoveryCheckpoint {
+                verified_chunks: vec![false; chunk_count],
+                storage_logs_count: 0,
+            });
 
         let mut recovery = Self {
+            config,
             connection_pool,
-            blob_store,
+            blob_store: RetryingObjectStore::new(blob_store, config, deadline)
+                .with_retry_budget(retry_budget),
             applied_snapshot_status,
+            content_addressed_chunks,
+            path_template,
+            checkpoint_state: tokio::sync::Mutex::new(CheckpointState {
+                checkpoint,
+                pending_chunks: vec![],
+            }),
+            applied_chunks: Mutex::default(),
+            format_version,
+            compression_dictionary: None,
         };
+        if let Some(key) = header
+            .as_ref()
+            .and_then(|header| header.storage_logs_compression_dictionary.as_ref())
+        {
+            recovery.compression_dictionary =
+                Some(recovery.fetch_compression_dictionary(key).await?);
+        }
+
+        if !created_from_scratch && config.reconcile_progress {
+            recovery
+                .reconcile_progress(&mut storage_transaction)
+                .await?;
+        } else if !created_from_scratch && config.repair_processed_chunks {
+            recovery
+                .repair_processed_chunks(&mut storage_transaction)
+                .await?;
+        } else if !created_from_scratch && config.reverify_last_processed_chunk {
+            recovery
+                .reverify_last_processed_chunk(&mut storage_transaction)
+                .await?;
+        }
+
+        let is_recovery_complete =
+            !created_from_scratch && recovery.chunks_to_process().next().is_none();
+        if is_recovery_complete {
+            // Persist processed flags if they were reconciled.
+            storage_transaction.commit().await.map_err(|err| {
+                SnapshotsApplierError::db(err, "failed committing initial DB transaction")
+            })?;
+            drop(storage);
+            if config.components.storage_logs && recovery.are_all_chunks_processed().await? {
+                recovery.swap_staging_tables().await?;
+                // Restart verification is explicitly requested for each restart, so it's not covered by the marker.
+                recovery.verify_applied_storage_logs().await?;
+                if recovery.are_post_recovery_checks_passed().await? {
+                    tracing::info!("Post-recovery checks have already passed; skipping");
+                } else {
+                    recovery.check_enumeration_index_base().await?;
+                    recovery.check_state_checksum().await?;
+                    recovery.run_acceptance_queries().await?;
+                    recovery.mark_post_recovery_checks_as_passed().await?;
+                }
+                // Synchronization is idempotent and cheap, so it's performed on each restart.
+                recovery.sync_enumeration_index_sequence().await?;
+                if let Some(path) = &config.applied_manifest_path {
+                    recovery.write_applied_manifest(path).await?;
+                }
+            }
+            return Err(SnapshotsApplierOutcome::Ok.into());
+        }
+
+        if let (true, Some(header)) = (config.verification.check_storage_prefix, &header) {
+            if config.components.storage_logs {
+                recovery.check_storage_prefix(header).await?;
+            }
+        }
+        if let Some(disk_space_check) = &config.disk_space_check {
+            recovery.check_disk_space(disk_space_check).await?;
+        }
 
         METRICS.storage_logs_chunks_count.set(
             recovery
@@ -257,231 +1345,2297 @@ impl<'a> SnapshotsApplier<'a> {
                 .storage_logs_chunks_processed
                 .len(),
         );
-        METRICS.storage_logs_chunks_left_to_process.set(
-            recovery
-                .applied_snapshot_status
-                .storage_logs_chunks_left_to_process(),
-        );
+        METRICS
+            .storage_logs_chunks_left_to_process
+            .set(recovery.chunks_to_process().count());
+        config
+            .health_check
+            .recovery_started(&recovery.applied_snapshot_status);
 
         if created_from_scratch {
-            recovery
-                .recover_factory_deps(&mut storage_transaction)
-                .await?;
-            storage_transaction
-                .snapshot_recovery_dal()
-                .insert_initial_recovery_status(&recovery.applied_snapshot_status)
-                .await
-                .map_err(|err| {
-                    SnapshotsApplierError::db(err, "failed persisting initial recovery status")
-                })?;
+            if config.components.factory_deps {
+                recovery
+                    .recover_factory_deps(&mut storage_transaction, &factory_deps_shards)
+                    .await?;
+            }
+            if includes_tokens && config.components.tokens {
+                recovery
+                    .recover_tokens(&mut storage_transaction, main_node_client)
+                    .await?;
+            }
+            if config.components.recovery_status {
+                storage_transaction
+                    .snapshot_recovery_dal()
+                    .insert_initial_recovery_status(&recovery.applied_snapshot_status)
+                    .await
+                    .map_err(|err| {
+                        SnapshotsApplierError::db(err, "failed persisting initial recovery status")
+                    })?;
+            }
         }
         storage_transaction.commit().await.map_err(|err| {
             SnapshotsApplierError::db(err, "failed committing initial DB transaction")
         })?;
         drop(storage);
 
-        recovery.recover_storage_logs().await?;
+        if let Some(interval) = config.main_node_head_check_interval {
+            tokio::select! {
+                result = recovery.recover_storage_logs() => result?,
+                err = recovery.watch_main_node_root_hash(main_node_client, interval) => {
+                    return Err(err);
+                }
+            }
+        } else {
+            recovery.recover_storage_logs().await?;
+        }
+        if config.components.storage_logs && recovery.are_all_chunks_processed().await? {
+            recovery.reconcile_with_main_node(main_node_client).await?;
+            recovery.check_state_checksum().await?;
+            recovery.sync_enumeration_index_sequence().await?;
+            recovery.run_acceptance_queries().await?;
+            recovery.mark_post_recovery_checks_as_passed().await?;
+            if let Some(path) = &config.applied_manifest_path {
+                recovery.write_applied_manifest(path).await?;
+            }
+        }
         Ok(())
     }
 
-    async fn create_fresh_recovery_status(
-        main_node_client: &dyn SnapshotsApplierMainNodeClient,
-    ) -> Result<SnapshotRecoveryStatus, SnapshotsApplierError> {
-        let snapshot_response = main_node_client.fetch_newest_snapshot().await?;
+    /// Issues prewarm hints for the configured relations after the specified chunk is applied.
+    async fn prewarm_relations(&self, chunk_id: u64) -> Result<(), SnapshotsApplierError> {
+        let relations = &self.config.prewarm_relations;
+        if relations.is_empty() {
+            return Ok(());
+        }
 
-        let snapshot = snapshot_response.ok_or(SnapshotsApplierOutcome::NoSnapshotsOnMainNode)?;
-        let l1_batch_number = snapshot.l1_batch_number;
-        let miniblock_number = snapshot.miniblock_number;
-        tracing::info!(
-            "Found snapshot with data up to L1 batch #{l1_batch_number}, storage_logs are divided into {} chunk(s)",
-            snapshot.storage_logs_chunks.len()
-        );
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        for relation in relations {
+            match storage.system_dal().prewarm_relation(relation).await {
+                Ok(block_count) => {
+                    tracing::debug!(
+                        "Prewarmed {block_count} blocks of relation `{relation}` after applying chunk {chunk_id}"
+                    );
+                    self.config.debug_handle.blocks_prewarmed(block_count);
+                }
+                Err(err) => {
+                    self.config.report_warning(format_args!(
+                        "Failed prewarming relation `{relation}`: {err}"
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
 
-        let miniblock = main_node_client
-            .fetch_l2_block(miniblock_number)
-            .await?
+    /// Checks the recovered state checksum against [`VerificationConfig::expected_state_checksum`].
+    async fn check_state_checksum(&self) -> Result<(), SnapshotsApplierError> {
+        let Some(expected_checksum) = self.config.verification.expected_state_checksum else {
+            return Ok(());
+        };
+        let status = &self.applied_snapshot_status;
+        let checksum = self.recovered_state_checksum().await?;
+        if checksum != expected_checksum {
+            let err = anyhow::anyhow!(
+                "checksum {checksum:?} of the state recovered for L1 batch #{} differs from the expected {expected_checksum:?}",
+                status.l1_batch_number
+            );
+            return Err(SnapshotsApplierError::Fatal(err));
+        }
+        tracing::info!("Checked recovered state checksum {checksum:?}");
+        Ok(())
+    }
+
+    /// Computes the checksum of the state recovered into Postgres.
+    async fn recovered_state_checksum(&self) -> Result<H256, SnapshotsApplierError> {
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        checksum::recovered_state_checksum(
+            &mut storage,
+            self.applied_snapshot_status.miniblock_number,
+            self.config.checksum_parallelism,
+        )
+        .await
+        .map_err(|err| SnapshotsApplierError::db(err, "failed computing recovered state checksum"))
+    }
+
+    /// Synchronizes the enumeration index sequence (if configured) with the maximum applied enumeration index.
+    async fn sync_enumeration_index_sequence(&self) -> Result<(), SnapshotsApplierError> {
+        let Some(sequence) = &self.config.enumeration_index_sequence else {
+            return Ok(());
+        };
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let max_index = storage
+            .storage_logs_dedup_dal()
+            .max_enumeration_index()
+            .await;
+        let expected_next_index = max_index.map_or(FIRST_ENUMERATION_INDEX, |index| index + 1);
+
+        let next_index = storage
+            .snapshot_recovery_dal()
+            .get_sequence_next_value(sequence)
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching enumeration index sequence state")
+            })?;
+        let Some(next_index) = next_index else {
+            let err = anyhow::anyhow!("enumeration index sequence `{sequence}` doesn't exist");
+            return Err(err.into());
+        };
+
+        match next_index.cmp(&expected_next_index) {
+            cmp::Ordering::Equal => {
+                tracing::info!(
+                    "Enumeration index sequence `{sequence}` is in sync with recovered storage (next index: {next_index})"
+                );
+            }
+            cmp::Ordering::Greater => {
+                self.config.report_warning(format_args!(
+                    "enumeration index sequence `{sequence}` is ahead of recovered storage: its next index is {next_index}, \
+                     while the maximum applied enumeration index is {}; enumeration indices will have a gap",
+                    expected_next_index - 1
+                ))?;
+            }
+            cmp::Ordering::Less => {
+                let is_set = storage
+                    .snapshot_recovery_dal()
+                    .set_sequence_next_value(sequence, expected_next_index)
+                    .await
+                    .map_err(|err| {
+                        SnapshotsApplierError::db(err, "failed setting enumeration index sequence")
+                    })?;
+                if !is_set {
+                    let err =
+                        anyhow::anyhow!("enumeration index sequence `{sequence}` was dropped");
+                    return Err(err.into());
+                }
+                tracing::info!(
+                    "Set next index of enumeration index sequence `{sequence}` to {expected_next_index} (was {next_index})"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs configured acceptance queries against the recovered storage.
+    async fn run_acceptance_queries(&self) -> Result<(), SnapshotsApplierError> {
+        let queries = &self.config.verification.acceptance_queries;
+        if queries.is_empty() {
+            return Ok(());
+        }
+
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        for (i, query) in queries.iter().enumerate() {
+            query
+                .run(&mut storage, miniblock_number)
+                .await
+                .with_context(|| format!("acceptance query #{i} ({query:?}) failed"))?;
+        }
+        tracing::info!("{} acceptance queries passed", queries.len());
+        Ok(())
+    }
+
+    /// Number of consecutive failed main node root hash checks after which
+    /// [`Self::watch_main_node_root_hash()`] returns an error.
+    const MAX_CONSECUTIVE_ROOT_HASH_CHECK_FAILURES: usize = 5;
+
+    /// Periodically checks that the main node reports the same root hash for the snapshot L1 batch.
+    /// Only returns on divergence, a non-retryable RPC error, or after [`Self::MAX_CONSECUTIVE_ROOT_HASH_CHECK_FAILURES`]
+    /// consecutive transient failures. A missing root hash is treated as a transient failure, since the main node
+    /// may be temporarily lagging (e.g., if it's load-balanced among replicas).
+    async fn watch_main_node_root_hash(
+        &self,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+        interval: Duration,
+    ) -> SnapshotsApplierError {
+        let l1_batch_number = self.applied_snapshot_status.l1_batch_number;
+        let expected_root_hash = self.applied_snapshot_status.l1_batch_root_hash;
+        let mut consecutive_failures = 0;
+        loop {
+            tokio::time::sleep(interval).await;
+            let err = match main_node_client
+                .fetch_l1_batch_root_hash(l1_batch_number)
+                .await
+            {
+                Ok(Some(root_hash)) if root_hash == expected_root_hash => {
+                    tracing::debug!(
+                        "Main node reports the expected root hash for snapshot L1 batch #{l1_batch_number}"
+                    );
+                    consecutive_failures = 0;
+                    continue;
+                }
+                Ok(Some(root_hash)) => {
+                    let reason = CancellationReason::MainNodeDiverged {
+                        l1_batch_number,
+                        expected_root_hash,
+                        root_hash,
+                    };
+                    let err = SnapshotRecoveryCancelled::from(reason);
+                    return SnapshotsApplierError::Fatal(err.into());
+                }
+                Ok(None) => {
+                    let reason = CancellationReason::MainNodeMissingL1Batch { l1_batch_number };
+                    SnapshotsApplierError::Fatal(SnapshotRecoveryCancelled::from(reason).into())
+                }
+                Err(err) => match SnapshotsApplierError::from(err) {
+                    err @ SnapshotsApplierError::Retryable(_) => err,
+                    err => return err,
+                },
+            };
+
+            consecutive_failures += 1;
+            if consecutive_failures >= Self::MAX_CONSECUTIVE_ROOT_HASH_CHECK_FAILURES {
+                return err;
+            }
+            tracing::warn!(
+                "Failed checking root hash for snapshot L1 batch #{l1_batch_number} on main node \
+                 ({consecutive_failures}/{} consecutive failures): {err}",
+                Self::MAX_CONSECUTIVE_ROOT_HASH_CHECK_FAILURES
+            );
+        }
+    }
+
+    /// Writes the manifest of applied data to the specified path.
+    async fn write_applied_manifest(&self, path: &Path) -> Result<(), SnapshotsApplierError> {
+        let mut chunks = std::mem::take(&mut *self.applied_chunks.lock().unwrap());
+        let recorded_chunk_ids: HashSet<_> = chunks.iter().map(|chunk| chunk.chunk_id).collect();
+        let chunk_count = self
+            .applied_snapshot_status
+            .storage_logs_chunks_processed
+            .len() as u64;
+        for chunk_id in (0..chunk_count).filter(|id| !recorded_chunk_ids.contains(id)) {
+            let chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+            chunks.push(AppliedChunk::new(
+                chunk_id,
+                &chunk.storage_logs,
+                self.config.checksum_parallelism,
+            ));
+        }
+
+        let status = &self.applied_snapshot_status;
+        let state_checksum = self.recovered_state_checksum().await?;
+        let manifest = AppliedManifest::new(
+            status.l1_batch_number,
+            status.miniblock_number,
+            state_checksum,
+            chunks,
+        );
+        manifest.write_to_file(path)?;
+        tracing::info!(
+            "Wrote manifest of applied data with digest {:?} to `{}`",
+            manifest.digest,
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Checks that the first storage logs chunk referenced by the snapshot header resolves under the object store prefix.
+    /// Content-addressed chunks are skipped since their filepaths are hashes rather than paths.
+    async fn check_storage_prefix(
+        &self,
+        header: &SnapshotHeader,
+    ) -> Result<(), SnapshotsApplierError> {
+        let chunk = header
+            .storage_logs_chunks
+            .iter()
+            .find(|chunk| !self.content_addressed_chunks.contains_key(&chunk.chunk_id));
+        let Some(chunk) = chunk else {
+            return Ok(());
+        };
+        let bucket = SnapshotStorageLogsChunk::BUCKET;
+        let prefix = self.blob_store.storage_prefix_raw(bucket);
+        let key = self.storage_logs_chunk_key(chunk.chunk_id);
+        let full_key = format!("{prefix}/{key}");
+
+        // Chunk filepaths are ignored if the path template is specified.
+        let is_under_prefix = self.path_template.is_some()
+            || chunk
+                .filepath
+                .strip_prefix(&prefix)
+                .is_some_and(|path| path.starts_with('/'));
+        if !is_under_prefix {
+            let err = anyhow::anyhow!(
+                "filepath `{}` of storage logs chunk {} doesn't resolve under the object store prefix `{prefix}` \
+                 (attempted full key: `{full_key}`); check the object store configuration",
+                chunk.filepath,
+                chunk.chunk_id
+            );
+            return Err(err.into());
+        }
+
+        match self.blob_store.get_size_raw(bucket, &key).await {
+            Ok(_) => {
+                tracing::info!(
+                    "Checked that storage logs chunk {} resolves under the object store prefix: `{full_key}`",
+                    chunk.chunk_id
+                );
+                Ok(())
+            }
+            Err(ObjectStoreError::KeyNotFound(_)) => {
+                let err = anyhow::anyhow!(
+                    "storage logs chunk {} is not found in the object store (attempted full key: `{full_key}`); \
+                     check the object store configuration",
+                    chunk.chunk_id
+                );
+                Err(err.into())
+            }
+            Err(ObjectStoreError::Unsupported(_)) => {
+                tracing::info!(
+                    "Object store doesn't support getting object sizes; skipped checking existence of `{full_key}`"
+                );
+                Ok(())
+            }
+            Err(err) => Err(SnapshotsApplierError::object_store(
+                err,
+                format!("failed checking existence of `{full_key}`"),
+            )),
+        }
+    }
+
+    /// Checks that available disk space is sufficient for the storage log chunks left to process.
+    async fn check_disk_space(&self, check: &DiskSpaceCheck) -> Result<(), SnapshotsApplierError> {
+        let chunk_ids = self.chunks_to_process();
+        let size_futures = chunk_ids.map(|chunk_id| {
+            let key = self.storage_logs_chunk_key(chunk_id);
+            async move {
+                self.blob_store
+                    .get_size_raw(SnapshotStorageLogsChunk::BUCKET, &key)
+                    .await
+            }
+        });
+        let chunks_size = match futures::future::try_join_all(size_futures).await {
+            Ok(sizes) => sizes.into_iter().sum(),
+            Err(err) => {
+                self.config.report_warning(format_args!(
+                    "Failed getting sizes of storage log chunks: {err}; only checking the disk space reserve"
+                ))?;
+                0
+            }
+        };
+        let required_space = check.required_space(chunks_size);
+
+        let path = &check.path;
+        let available_space = check
+            .stats_provider
+            .available_space(path)
+            .with_context(|| {
+                format!(
+                    "failed getting available disk space for `{}`",
+                    path.display()
+                )
+            })?;
+        if available_space < required_space {
+            let err = anyhow::anyhow!(
+                "insufficient disk space for `{}`: {available_space} bytes are available, while recovery \
+                 is estimated to require {required_space} bytes",
+                path.display()
+            );
+            return Err(err.into());
+        }
+        tracing::info!(
+            "Checked disk space for `{}`: {available_space} bytes are available, recovery is estimated \
+             to require {required_space} bytes",
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Merges recovery progress from an externally provided status into the `status` loaded from Postgres
+    /// or created from the snapshot header.
+    fn seed_recovery_progress(
+        seed: &SnapshotRecoveryStatus,
+        status: &mut SnapshotRecoveryStatus,
+    ) -> anyhow::Result<()> {
+        let is_same_snapshot = seed.l1_batch_number == status.l1_batch_number
+            && seed.l1_batch_root_hash == status.l1_batch_root_hash
+            && seed.miniblock_number == status.miniblock_number
+            && seed.miniblock_hash == status.miniblock_hash;
+        anyhow::ensure!(
+            is_same_snapshot,
+            "provided initial recovery status (L1 batch #{}, miniblock #{}) doesn't match \
+             the recovered snapshot (L1 batch #{}, miniblock #{})",
+            seed.l1_batch_number,
+            seed.miniblock_number,
+            status.l1_batch_number,
+            status.miniblock_number
+        );
+        anyhow::ensure!(
+            seed.storage_logs_chunks_processed.len() == status.storage_logs_chunks_processed.len(),
+            "provided initial recovery status has {} storage logs chunks, while the recovered snapshot has {}",
+            seed.storage_logs_chunks_processed.len(),
+            status.storage_logs_chunks_processed.len()
+        );
+
+        let processed_flags = status.storage_logs_chunks_processed.iter_mut();
+        for (is_processed, &is_seeded) in processed_flags.zip(&seed.storage_logs_chunks_processed) {
+            *is_processed |= is_seeded;
+        }
+        tracing::info!(
+            "Seeded recovery progress from the provided status; {} storage logs chunks are left to process",
+            status.storage_logs_chunks_left_to_process()
+        );
+        Ok(())
+    }
+
+    /// Checks that all tables used by the applier exist in Postgres.
+    async fn check_db_schema(
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let missing_tables = storage
+            .system_dal()
+            .get_missing_tables(REQUIRED_TABLES)
+            .await
+            .map_err(|err| SnapshotsApplierError::db(err, "failed checking Postgres schema"))?;
+        if !missing_tables.is_empty() {
+            let err = anyhow::anyhow!(
+                "Postgres schema is missing tables required for snapshot recovery: {missing_tables:?}; \
+                 make sure that DB migrations are applied"
+            );
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Checks whether the snapshot header is required to resume recovery, i.e., whether any storage log chunks
+    /// will be fetched from the object store.
+    fn needs_header_on_resume(
+        config: &SnapshotsApplierConfig,
+        status: &SnapshotRecoveryStatus,
+    ) -> bool {
+        status.storage_logs_chunks_left_to_process() > 0
+            || config.repair_processed_chunks
+            || config.reverify_last_processed_chunk
+            || config.verification.restart_verification_fraction > 0.0
+    }
+
+    /// Fetches the snapshot header from the main node when resuming recovery. The header is only returned
+    /// if it corresponds to the snapshot being recovered.
+    async fn fetch_header_on_resume(
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+        status: &SnapshotRecoveryStatus,
+    ) -> Result<Option<SnapshotHeader>, SnapshotsApplierError> {
+        let header = main_node_client.fetch_newest_snapshot().await?;
+        let header = header.filter(|header| {
+            let is_same_snapshot = header.l1_batch_number == status.l1_batch_number;
+            if !is_same_snapshot {
+                tracing::info!(
+                    "Newest snapshot on main node (L1 batch #{}) differs from the recovered one (L1 batch #{}); \
+                     assuming standard keys for storage logs chunks",
+                    header.l1_batch_number,
+                    status.l1_batch_number
+                );
+            }
+            is_same_snapshot
+        });
+        if let Some(header) = &header {
+            Self::check_resumed_header(header, status)?;
+        }
+        Ok(header)
+    }
+
+    /// Fetches the snapshot header from the main node when resuming recovery with [`SnapshotsApplierConfig::reconcile_progress`]
+    /// set. Since persisted processed flags cannot be trusted, the header must be available to determine the number
+    /// of storage log chunks; `status` is adjusted to have the correct number of flags.
+    async fn fetch_header_for_reconciliation(
+        config: &SnapshotsApplierConfig,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+        status: &mut SnapshotRecoveryStatus,
+    ) -> Result<SnapshotHeader, SnapshotsApplierError> {
+        let header = main_node_client
+            .fetch_newest_snapshot()
+            .await?
+            .context("main node doesn't have a snapshot")?;
+        if header.l1_batch_number != status.l1_batch_number {
+            let err = anyhow::anyhow!(
+                "cannot reconcile recovery progress: newest snapshot on main node (L1 batch #{}) differs \
+                 from the recovered one (L1 batch #{})",
+                header.l1_batch_number,
+                status.l1_batch_number
+            );
+            return Err(err.into());
+        }
+        Self::check_storage_logs_chunk_ids(&header)?;
+
+        let chunk_count = header.storage_logs_chunks.len();
+        let flags = &mut status.storage_logs_chunks_processed;
+        if flags.len() != chunk_count {
+            config.report_warning(format_args!(
+                "Persisted recovery status has {} processed flags, while the snapshot header has {chunk_count} \
+                 storage logs chunks; resizing flags",
+                flags.len()
+            ))?;
+            flags.resize(chunk_count, false);
+        }
+        Self::check_resumed_header(&header, status)?;
+        Ok(header)
+    }
+
+    /// Checks that the snapshot header fetched on resume is consistent with the recovery status persisted
+    /// in Postgres. Recovery progress is tracked by chunk IDs rather than filepaths, so chunk filepaths
+    /// may change between restarts (e.g., if the snapshot was re-uploaded under new keys); in this case,
+    /// the remaining chunks are fetched using the new filepaths. The set of chunk IDs must stay the same.
+    fn check_resumed_header(
+        header: &SnapshotHeader,
+        status: &SnapshotRecoveryStatus,
+    ) -> anyhow::Result<()> {
+        Self::check_storage_logs_chunk_ids(header)?;
+
+        let chunk_count = status.storage_logs_chunks_processed.len();
+        let mut chunk_ids: Vec<_> = header
+            .storage_logs_chunks
+            .iter()
+            .map(|chunk| chunk.chunk_id)
+            .collect();
+        chunk_ids.sort_unstable();
+        anyhow::ensure!(
+            chunk_ids.iter().copied().eq(0..chunk_count as u64),
+            "storage logs chunks in the snapshot header for L1 batch #{} (IDs: {chunk_ids:?}) don't match \
+             {chunk_count} chunks in the persisted recovery status; the snapshot was probably modified \
+             after recovery has started",
+            header.l1_batch_number
+        );
+        Ok(())
+    }
+
+    /// Determines how storage logs chunk keys are computed based on the snapshot header (if any). If the header
+    /// specifies a path template, it's used for all chunks. Otherwise, content-addressed chunks are extracted
+    /// from the header; other chunks use standard keys.
+    fn storage_logs_chunk_keys(
+        header: Option<&SnapshotHeader>,
+    ) -> anyhow::Result<(Option<PathTemplate>, HashMap<u64, ContentAddressedChunk>)> {
+        let Some(header) = header else {
+            return Ok((None, HashMap::new()));
+        };
+        if let Some(template) = &header.storage_logs_path_template {
+            let template = PathTemplate::new(template)?;
+            tracing::info!("Using path template `{template}` for storage logs chunk keys");
+            Ok((Some(template), HashMap::new()))
+        } else {
+            Ok((None, Self::content_addressed_chunks(header)?))
+        }
+    }
+
+    /// Extracts content-addressed storage log chunks from the snapshot header. A chunk is content-addressed
+    /// if its filepath is a `0x`-prefixed hex-encoded hash.
+    fn content_addressed_chunks(
+        header: &SnapshotHeader,
+    ) -> anyhow::Result<HashMap<u64, ContentAddressedChunk>> {
+        let mut chunks = HashMap::new();
+        for chunk in &header.storage_logs_chunks {
+            let Some(hex_hash) = chunk.filepath.strip_prefix("0x") else {
+                continue;
+            };
+            if hex_hash.len() != 2 * H256::len_bytes() {
+                continue;
+            }
+            let hash: H256 = hex_hash.parse().with_context(|| {
+                format!(
+                    "invalid content hash `{}` for storage logs chunk {}",
+                    chunk.filepath, chunk.chunk_id
+                )
+            })?;
+            let content_addressed_chunk = ContentAddressedChunk {
+                key: chunk.filepath.clone(),
+                hash,
+            };
+            chunks.insert(chunk.chunk_id, content_addressed_chunk);
+        }
+        Ok(chunks)
+    }
+
+    /// Fetches the newest snapshot from the main node, polling the main node for up to
+    /// [`SnapshotsApplierConfig::snapshot_wait_timeout`] if it doesn't advertise snapshots yet.
+    async fn wait_for_snapshot(
+        config: &SnapshotsApplierConfig,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> Result<SnapshotHeader, SnapshotsApplierError> {
+        let started_at = Instant::now();
+        let backoff = config.retry_backoff();
+        let mut poll_number = 0;
+        loop {
+            if let Some(snapshot) = main_node_client.fetch_newest_snapshot().await? {
+                return Ok(snapshot);
+            }
+            let Some(timeout) = config.snapshot_wait_timeout else {
+                break;
+            };
+            poll_number += 1;
+            let delay = backoff.retry_delay(poll_number);
+            if started_at.elapsed() + delay > timeout {
+                tracing::warn!("Main node didn't advertise a snapshot in {timeout:?}");
+                break;
+            }
+            tracing::info!("Main node doesn't have snapshots yet; polling it again in {delay:?}");
+            tokio::time::sleep(delay).await;
+        }
+        Err(SnapshotsApplierOutcome::NoSnapshotsOnMainNode.into())
+    }
+
+    async fn create_fresh_recovery_status(
+        config: &SnapshotsApplierConfig,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> Result<(SnapshotRecoveryStatus, SnapshotHeader), SnapshotsApplierError> {
+        let snapshot = Self::wait_for_snapshot(config, main_node_client).await?;
+        Self::check_storage_logs_chunk_ids(&snapshot)?;
+        if let Some(l1_client) = &config.l1_client {
+            Self::check_l1_commitment(l1_client.as_ref(), &snapshot).await?;
+            Self::check_l1_batch_finality(config, l1_client.as_ref(), &snapshot).await?;
+        }
+        let l1_batch_number = snapshot.l1_batch_number;
+        let miniblock_number = snapshot.miniblock_number;
+        tracing::info!(
+            "Found snapshot with data up to L1 batch #{l1_batch_number}, storage_logs are divided into {} chunk(s)",
+            snapshot.storage_logs_chunks.len()
+        );
+
+        let miniblock = main_node_client
+            .fetch_l2_block(miniblock_number)
+            .await?
+            .with_context(|| format!("miniblock #{miniblock_number} is missing on main node"))?;
+        let miniblock_hash = Self::check_snapshot_miniblock(&snapshot, &miniblock)?;
+
+        let status = SnapshotRecoveryStatus {
+            l1_batch_number,
+            l1_batch_timestamp: snapshot.last_l1_batch_with_metadata.header.timestamp,
+            l1_batch_root_hash: snapshot.last_l1_batch_with_metadata.metadata.root_hash,
+            miniblock_number: snapshot.miniblock_number,
+            miniblock_timestamp: miniblock.timestamp,
+            miniblock_hash,
+            protocol_version: snapshot
+                .last_l1_batch_with_metadata
+                .header
+                .protocol_version
+                .unwrap(),
+            storage_logs_chunks_processed: vec![false; snapshot.storage_logs_chunks.len()],
+        };
+        Self::check_protocol_version(config, status.protocol_version)?;
+        Self::check_timestamps(&status, &miniblock)?;
+        Ok((status, snapshot))
+    }
+
+    /// Runs [`Self::check_timestamps()`] for a resumed recovery, so that the persisted recovery status
+    /// is checked against the main node as well.
+    async fn check_resumed_recovery_timestamps(
+        status: &SnapshotRecoveryStatus,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> Result<(), SnapshotsApplierError> {
+        let miniblock_number = status.miniblock_number;
+        let miniblock = main_node_client
+            .fetch_l2_block(miniblock_number)
+            .await?
             .with_context(|| format!("miniblock #{miniblock_number} is missing on main node"))?;
-        let miniblock_hash = miniblock
-            .hash
-            .context("snapshot miniblock fetched from main node doesn't have hash set")?;
+        Self::check_timestamps(status, &miniblock)?;
+        Ok(())
+    }
+
+    /// Checks that the snapshot miniblock fetched from the main node corresponds to the snapshot and contains
+    /// all data required for recovery, so that recovery doesn't proceed with partial information. Returns
+    /// the miniblock hash.
+    fn check_snapshot_miniblock(
+        snapshot: &SnapshotHeader,
+        miniblock: &SyncBlock,
+    ) -> anyhow::Result<H256> {
+        let miniblock_number = snapshot.miniblock_number;
+        anyhow::ensure!(
+            miniblock.number == miniblock_number,
+            "main node returned miniblock #{} when requested snapshot miniblock #{miniblock_number}",
+            miniblock.number
+        );
+        anyhow::ensure!(
+            miniblock.l1_batch_number == snapshot.l1_batch_number,
+            "snapshot miniblock #{miniblock_number} fetched from main node belongs to L1 batch #{}, \
+             while the snapshot is created for L1 batch #{}",
+            miniblock.l1_batch_number,
+            snapshot.l1_batch_number
+        );
+        miniblock.hash.with_context(|| {
+            format!(
+                "snapshot miniblock #{miniblock_number} fetched from main node doesn't have hash set, \
+                 which is required for recovery"
+            )
+        })
+    }
+
+    /// Checks that the commitment of the snapshot L1 batch matches the commitment of this batch on L1.
+    async fn check_l1_commitment(
+        l1_client: &dyn SnapshotsApplierL1Client,
+        snapshot: &SnapshotHeader,
+    ) -> Result<(), SnapshotsApplierError> {
+        let l1_batch_number = snapshot.l1_batch_number;
+        let l1_commitment = l1_client
+            .fetch_l1_batch_commitment(l1_batch_number)
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::Retryable(
+                    err.context("failed fetching L1 batch commitment from L1"),
+                )
+            })?;
+        let l1_commitment = l1_commitment.with_context(|| {
+            format!("snapshot L1 batch #{l1_batch_number} is not committed on L1")
+        })?;
+
+        let snapshot_commitment = snapshot.last_l1_batch_with_metadata.metadata.commitment;
+        if l1_commitment != snapshot_commitment {
+            let err = anyhow::anyhow!(
+                "commitment for snapshot L1 batch #{l1_batch_number} ({snapshot_commitment:?}) \
+                 differs from its commitment on L1 ({l1_commitment:?})"
+            );
+            return Err(err.into());
+        }
+        tracing::info!("Verified commitment for snapshot L1 batch #{l1_batch_number} against L1");
+        Ok(())
+    }
+
+    /// Checks that the snapshot L1 batch is finalized on L1 according to [`SnapshotsApplierConfig::non_finalized_snapshot_policy`].
+    async fn check_l1_batch_finality(
+        config: &SnapshotsApplierConfig,
+        l1_client: &dyn SnapshotsApplierL1Client,
+        snapshot: &SnapshotHeader,
+    ) -> Result<(), SnapshotsApplierError> {
+        let l1_batch_number = snapshot.l1_batch_number;
+        let is_finalized = l1_client
+            .is_l1_batch_finalized(l1_batch_number)
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::Retryable(
+                    err.context("failed checking L1 batch finality on L1"),
+                )
+            })?;
+        if is_finalized {
+            tracing::info!("Snapshot L1 batch #{l1_batch_number} is finalized on L1");
+            return Ok(());
+        }
+
+        match config.non_finalized_snapshot_policy {
+            NonFinalizedSnapshotPolicy::Refuse => {
+                let err = anyhow::anyhow!(
+                    "snapshot L1 batch #{l1_batch_number} is not finalized on L1; the snapshot may have been \
+                     exported during an L1 reorg, and recovering to it is unsafe"
+                );
+                Err(err.into())
+            }
+            NonFinalizedSnapshotPolicy::Allow => {
+                config.report_warning(format_args!(
+                    "Snapshot L1 batch #{l1_batch_number} is not finalized on L1; proceeding with recovery \
+                     as configured"
+                ))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that storage log chunks in the snapshot header have unique IDs. A header referencing the same chunk ID
+    /// several times is ambiguous, so we refuse to apply it rather than choosing one of the chunks arbitrarily.
+    fn check_storage_logs_chunk_ids(snapshot: &SnapshotHeader) -> anyhow::Result<()> {
+        let mut filepaths_by_chunk_id = HashMap::with_capacity(snapshot.storage_logs_chunks.len());
+        for chunk in &snapshot.storage_logs_chunks {
+            if let Some(prev_filepath) =
+                filepaths_by_chunk_id.insert(chunk.chunk_id, &chunk.filepath)
+            {
+                anyhow::bail!(
+                    "snapshot header for L1 batch #{} is ambiguous: storage logs chunk {} is referenced several times \
+                     (filepaths: `{prev_filepath}`, `{}`)",
+                    snapshot.l1_batch_number,
+                    chunk.chunk_id,
+                    chunk.filepath
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that decoders for snapshot objects of the specified data format version are registered.
+    fn check_format_version(
+        config: &SnapshotsApplierConfig,
+        format_version: u32,
+        created_from_scratch: bool,
+    ) -> anyhow::Result<()> {
+        let registry = &config.decoder_registry;
+        let mut object_types = vec![SnapshotObjectType::StorageLogsChunk];
+        if created_from_scratch && config.components.factory_deps {
+            object_types.push(SnapshotObjectType::FactoryDependencies);
+        }
+        for object_type in object_types {
+            anyhow::ensure!(
+                registry.supports(format_version, object_type),
+                "snapshot uses data format version {format_version}, which is not supported for {object_type}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that the snapshot protocol version is supported by this node.
+    fn check_protocol_version(
+        config: &SnapshotsApplierConfig,
+        protocol_version: ProtocolVersionId,
+    ) -> anyhow::Result<()> {
+        let latest_version = ProtocolVersionId::latest();
+        if protocol_version <= latest_version {
+            return Ok(());
+        }
+        if config.allow_newer_protocol_version {
+            config.report_warning(format_args!(
+                "Applying snapshot with protocol version {protocol_version:?}, which is newer than the latest version \
+                 {latest_version:?} supported by this node, since this is allowed by the config"
+            ))?;
+            return Ok(());
+        }
+        anyhow::bail!(
+            "snapshot has protocol version {protocol_version:?}, which is newer than the latest version \
+             {latest_version:?} supported by this node; please upgrade the node"
+        );
+    }
+
+    /// Checks that timestamps in the recovery status are consistent with each other and with the snapshot miniblock
+    /// fetched from the main node. The snapshot miniblock is the last miniblock in the snapshot L1 batch,
+    /// so it cannot be older than the batch.
+    fn check_timestamps(
+        status: &SnapshotRecoveryStatus,
+        miniblock: &SyncBlock,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            miniblock.timestamp == status.miniblock_timestamp,
+            "snapshot miniblock #{} fetched from main node has timestamp {}, while recovery status has timestamp {}",
+            status.miniblock_number,
+            miniblock.timestamp,
+            status.miniblock_timestamp
+        );
+        anyhow::ensure!(
+            status.miniblock_timestamp >= status.l1_batch_timestamp,
+            "snapshot miniblock #{} has timestamp {} which is earlier than timestamp {} of its L1 batch #{}",
+            status.miniblock_number,
+            status.miniblock_timestamp,
+            status.l1_batch_timestamp,
+            status.l1_batch_number
+        );
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn recover_factory_deps(
+        &mut self,
+        storage: &mut StorageProcessor<'_>,
+        shards: &[String],
+    ) -> Result<(), SnapshotsApplierError> {
+        let latency = METRICS.initial_stage_duration[&InitialStage::ApplyFactoryDeps].start();
+
+        tracing::debug!("Fetching factory dependencies from object store");
+        let all_deps_hashmap = if shards.is_empty() {
+            let l1_batch_number = self.applied_snapshot_status.l1_batch_number;
+            let context = || {
+                format!(
+                    "cannot fetch factory deps for L1 batch #{l1_batch_number} from object store"
+                )
+            };
+            let key = SnapshotFactoryDependencies::encode_key(l1_batch_number);
+            let bytes = self
+                .blob_store
+                .get_raw(SnapshotFactoryDependencies::BUCKET, &key)
+                .await
+                .map_err(|err| SnapshotsApplierError::object_store(err, context()))?;
+            self.decode_factory_deps(bytes)
+                .map_err(|err| SnapshotsApplierError::object_store(err, context()))?
+                .factory_deps
+                .into_iter()
+                .map(|dep| (hash_bytecode(&dep.bytecode.0), dep.bytecode.0))
+                .collect()
+        } else {
+            self.fetch_factory_deps_shards(shards).await?
+        };
+        tracing::debug!(
+            "Fetched {} factory dependencies from object store",
+            all_deps_hashmap.len()
+        );
+        storage
+            .factory_deps_dal()
+            .insert_factory_deps(
+                self.applied_snapshot_status.miniblock_number,
+                &all_deps_hashmap,
+            )
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed persisting factory deps to DB")
+            })?;
+
+        let latency = latency.observe();
+        tracing::info!("Applied factory dependencies in {latency:?}");
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn recover_tokens(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> Result<(), SnapshotsApplierError> {
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        tracing::debug!("Fetching tokens as of miniblock #{miniblock_number} from main node");
+        let tokens = main_node_client.fetch_tokens(miniblock_number).await?;
+        let token_count = tokens.len();
+        storage.tokens_dal().add_tokens(tokens).await;
+        tracing::info!("Applied metadata for {token_count} token(s)");
+        Ok(())
+    }
+
+    fn decode_factory_deps(
+        &self,
+        bytes: Vec<u8>,
+    ) -> Result<SnapshotFactoryDependencies, ObjectStoreError> {
+        let decoder = self
+            .config
+            .decoder_registry
+            .factory_deps_decoder(self.format_version);
+        let limits = self.config.decoding_limits();
+        decoder
+            .and_then(|decoder| decoder(bytes, &limits))
+            .map_err(|err| ObjectStoreError::Serialization(err.into()))
+    }
+
+    /// Fetches factory dependency shards concurrently, deduplicating dependencies across shards.
+    async fn fetch_factory_deps_shards(
+        &self,
+        shards: &[String],
+    ) -> Result<HashMap<H256, Vec<u8>>, SnapshotsApplierError> {
+        let semaphore = Semaphore::new(self.config.throttling.factory_deps_concurrency.max(1));
+        let all_deps = Mutex::new(HashMap::new());
+        let tasks = shards
+            .iter()
+            .map(|key| self.fetch_factory_deps_shard(&semaphore, key, &all_deps));
+        futures::future::try_join_all(tasks).await?;
+        Ok(all_deps.into_inner().unwrap())
+    }
+
+    async fn fetch_factory_deps_shard(
+        &self,
+        semaphore: &Semaphore,
+        key: &str,
+        all_deps: &Mutex<HashMap<H256, Vec<u8>>>,
+    ) -> Result<(), SnapshotsApplierError> {
+        // `unwrap()` is safe: the semaphore is never closed
+        let _permit = semaphore.acquire().await.unwrap();
+
+        let context = || format!("cannot fetch factory deps shard `{key}` from object store");
+        let bytes = self
+            .blob_store
+            .get_raw(SnapshotFactoryDependencies::BUCKET, key)
+            .await
+            .map_err(|err| SnapshotsApplierError::object_store(err, context()))?;
+        let shard = self
+            .decode_factory_deps(bytes)
+            .map_err(|err| SnapshotsApplierError::object_store(err, context()))?;
+        let shard_deps: Vec<_> = shard
+            .factory_deps
+            .into_iter()
+            .map(|dep| (hash_bytecode(&dep.bytecode.0), dep.bytecode.0))
+            .collect();
+        tracing::debug!(
+            "Fetched {} factory dependencies from shard `{key}`",
+            shard_deps.len()
+        );
+
+        let mut all_deps = all_deps.lock().unwrap();
+        for (hash, bytecode) in shard_deps {
+            all_deps.entry(hash).or_insert(bytecode);
+        }
+        Ok(())
+    }
+
+    async fn insert_initial_writes_chunk(
+        &self,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let mut dal = storage.storage_logs_dedup_dal();
+        let result = if self.config.apply_via_staging_tables {
+            dal.insert_initial_writes_from_snapshot_into_staging(storage_logs)
+                .await
+        } else {
+            dal.insert_initial_writes_from_snapshot(storage_logs).await
+        };
+        result.map_err(|err| {
+            let context =
+                format!("failed persisting initial writes from storage logs chunk {chunk_id}");
+            SnapshotsApplierError::db(err, context)
+        })?;
+        Ok(())
+    }
+
+    async fn insert_storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        let mut dal = storage.storage_logs_dal();
+        let result = if self.config.apply_via_staging_tables {
+            dal.insert_storage_logs_from_snapshot_into_staging(miniblock_number, storage_logs)
+                .await
+        } else {
+            dal.insert_storage_logs_from_snapshot(miniblock_number, storage_logs)
+                .await
+        };
+        result.map_err(|err| {
+            let context = format!("failed persisting storage logs from chunk {chunk_id}");
+            SnapshotsApplierError::db(err, context)
+        })?;
+        Ok(())
+    }
+
+    /// Checks that all storage logs from the chunk are persisted in Postgres.
+    async fn check_persisted_storage_logs_count(
+        &self,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let hashed_keys: Vec<_> = storage_logs
+            .iter()
+            .map(|log| log.key.hashed_key())
+            .collect();
+        let persisted_count = storage
+            .storage_logs_dal()
+            .count_storage_logs_for_keys(
+                &hashed_keys,
+                self.applied_snapshot_status.miniblock_number,
+            )
+            .await
+            .map_err(|err| {
+                let context =
+                    format!("failed counting persisted storage logs for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+
+        if persisted_count != storage_logs.len() as u64 {
+            let err = anyhow::anyhow!(
+                "only {persisted_count} out of {} storage logs from chunk {chunk_id} are persisted in Postgres",
+                storage_logs.len()
+            );
+            return Err(SnapshotsApplierError::Retryable(err));
+        }
+        Ok(())
+    }
+
+    fn check_enumeration_indices(
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let min_index = storage_logs.iter().map(|log| log.enumeration_index).min();
+        if let Some(min_index) = min_index {
+            anyhow::ensure!(
+                min_index >= FIRST_ENUMERATION_INDEX,
+                "storage logs chunk {chunk_id} contains enumeration index {min_index}, while indices \
+                 must start from {FIRST_ENUMERATION_INDEX}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks invariants of a single storage logs chunk that can be verified without accessing Postgres.
+    fn check_storage_logs_chunk_integrity(
+        &self,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let snapshot_l1_batch_number = self.applied_snapshot_status.l1_batch_number;
+        let mut hashed_keys = HashSet::with_capacity(storage_logs.len());
+        let mut enumeration_indices = HashSet::with_capacity(storage_logs.len());
+        for log in storage_logs {
+            anyhow::ensure!(
+                hashed_keys.insert(log.key.hashed_key()),
+                "storage key {:?} is repeated in chunk {chunk_id}",
+                log.key
+            );
+            anyhow::ensure!(
+                enumeration_indices.insert(log.enumeration_index),
+                "enumeration index {} is repeated in chunk {chunk_id}",
+                log.enumeration_index
+            );
+            anyhow::ensure!(
+                log.l1_batch_number_of_initial_write <= snapshot_l1_batch_number,
+                "storage key {:?} is initially written in L1 batch #{}, which is after the snapshot L1 batch #{snapshot_l1_batch_number}",
+                log.key,
+                log.l1_batch_number_of_initial_write
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that all contract bytecodes referenced by the account code storage logs in a chunk are present
+    /// among factory dependencies.
+    async fn check_factory_deps_completeness(
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let code_hashes: HashSet<_> = storage_logs
+            .iter()
+            .filter(|log| *log.key.address() == ACCOUNT_CODE_STORAGE_ADDRESS)
+            .filter_map(|log| Self::referenced_bytecode_hash(log.value))
+            .collect();
+        if code_hashes.is_empty() {
+            return Ok(());
+        }
+
+        let code_hashes: Vec<_> = code_hashes.into_iter().collect();
+        let mut missing_hashes = storage
+            .factory_deps_dal()
+            .get_missing_factory_deps(&code_hashes)
+            .await
+            .map_err(|err| {
+                let context =
+                    format!("failed checking factory deps for storage logs chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        if !missing_hashes.is_empty() {
+            missing_hashes.sort_unstable();
+            let err = anyhow::anyhow!(
+                "storage logs chunk {chunk_id} references {} contract bytecode(s) missing from factory deps: {missing_hashes:?}",
+                missing_hashes.len()
+            );
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Extracts the bytecode hash from a value in the account code storage. Returns `None` if the value
+    /// doesn't reference a bytecode.
+    fn referenced_bytecode_hash(value: StorageValue) -> Option<H256> {
+        // Zero value means that there is no contract at the address, or its deployment has failed.
+        if value.is_zero() {
+            return None;
+        }
+        // The second byte of a versioned bytecode hash is set to 1 while the contract is being constructed;
+        // factory deps are keyed by hashes of constructed contracts.
+        let mut hash = value;
+        hash.0[1] = 0;
+        Some(hash)
+    }
+
+    /// Checks that the minimum enumeration index among all applied storage logs is equal to the expected base.
+    /// This catches off-by-one errors in the snapshot exporter that cannot be detected on the chunk level.
+    async fn check_enumeration_index_base(&self) -> Result<(), SnapshotsApplierError> {
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let min_index = storage
+            .storage_logs_dedup_dal()
+            .min_enumeration_index()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching min enumeration index")
+            })?;
+
+        if let Some(min_index) = min_index {
+            if min_index != FIRST_ENUMERATION_INDEX {
+                let err = anyhow::anyhow!(
+                    "minimum enumeration index among applied storage logs is {min_index}, while indices \
+                     must start from {FIRST_ENUMERATION_INDEX}; the snapshot is probably corrupted"
+                );
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_storage_log_values(
+        &self,
+        chunk_id: u64,
+        storage_logs: &mut [SnapshotStorageLog],
+    ) -> Result<(), SnapshotsApplierError> {
+        for log in storage_logs {
+            log.value = self
+                .config
+                .value_codec
+                .decode(&log.key, log.value)
+                .with_context(|| {
+                    format!(
+                        "failed decoding value for storage log {:?} from chunk {chunk_id}",
+                        log.key
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    async fn fetch_storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+    ) -> Result<SnapshotStorageLogsChunk, SnapshotsApplierError> {
+        let mut storage_snapshot_chunk =
+            if let Some(chunk) = self.content_addressed_chunks.get(&chunk_id) {
+                self.fetch_content_addressed_chunk(chunk_id, chunk).await?
+            } else {
+                let key = self.storage_logs_chunk_key(chunk_id);
+                let bytes = self
+                    .blob_store
+                    .get_raw(SnapshotStorageLogsChunk::BUCKET, &key)
+                    .await
+                    .map_err(|err| {
+                        let context = format!(
+                            "cannot fetch storage logs chunk {chunk_id} (`{key}`) from object store"
+                        );
+                        SnapshotsApplierError::object_store(err, context)
+                    })?;
+                self.decode_storage_logs_chunk(chunk_id, bytes).await?
+            };
+        Self::check_enumeration_indices(chunk_id, &storage_snapshot_chunk.storage_logs)?;
+        self.decode_storage_log_values(chunk_id, &mut storage_snapshot_chunk.storage_logs)?;
+        Ok(storage_snapshot_chunk)
+    }
+
+    async fn fetch_compression_dictionary(
+        &self,
+        key: &str,
+    ) -> Result<CompressionDictionary, SnapshotsApplierError> {
+        let object_type = SnapshotObjectType::DictionaryCompressedStorageLogsChunk;
+        if !self
+            .config
+            .decoder_registry
+            .supports(self.format_version, object_type)
+        {
+            let err = anyhow::anyhow!(
+                "snapshot uses data format version {}, which is not supported for {object_type}",
+                self.format_version
+            );
+            return Err(err.into());
+        }
+        let bytes = self
+            .blob_store
+            .get_raw(SnapshotStorageLogsChunk::BUCKET, key)
+            .await
+            .map_err(|err| {
+                let context = format!(
+                    "cannot fetch storage logs compression dictionary `{key}` from object store"
+                );
+                SnapshotsApplierError::object_store(err, context)
+            })?;
+        tracing::info!(
+            "Fetched storage logs compression dictionary `{key}` ({} bytes)",
+            bytes.len()
+        );
+        Ok(CompressionDictionary::new(key.to_owned(), &bytes))
+    }
+
+    /// Fetches a content-addressed storage logs chunk and checks that its hash matches the key.
+    async fn fetch_content_addressed_chunk(
+        &self,
+        chunk_id: u64,
+        chunk: &ContentAddressedChunk,
+    ) -> Result<SnapshotStorageLogsChunk, SnapshotsApplierError> {
+        let context = || {
+            format!(
+                "cannot fetch content-addressed storage logs chunk {chunk_id} (`{}`) from object store",
+                chunk.key
+            )
+        };
+        let bytes = self
+            .blob_store
+            .get_raw(SnapshotStorageLogsChunk::BUCKET, &chunk.key)
+            .await
+            .map_err(|err| SnapshotsApplierError::object_store(err, context()))?;
+
+        let actual_hash = H256(keccak256(&bytes));
+        if actual_hash != chunk.hash {
+            let err = anyhow::anyhow!(
+                "storage logs chunk {chunk_id} is corrupted: its content hash {actual_hash:?} differs \
+                 from the key `{}` in the snapshot header",
+                chunk.key
+            );
+            return Err(err.into());
+        }
+        self.decode_storage_logs_chunk(chunk_id, bytes).await
+    }
+
+    /// Decodes a storage logs chunk on a blocking thread, so that decoding doesn't block the async runtime.
+    /// Decoding is aborted with a fatal error if it doesn't finish in [`SnapshotsApplierConfig::chunk_decode_timeout`].
+    async fn decode_storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+        bytes: Vec<u8>,
+    ) -> Result<SnapshotStorageLogsChunk, SnapshotsApplierError> {
+        let byte_size = bytes.len();
+        let registry = &self.config.decoder_registry;
+        let limits = self.config.decoding_limits();
+        let decode_task = if let Some(dictionary) = &self.compression_dictionary {
+            let dictionary = dictionary.clone();
+            let decoder = registry.decompressed_storage_logs_chunk_decoder(self.format_version)?;
+            tokio::task::spawn_blocking(move || {
+                let decompressed_bytes = dictionary.decompress(&bytes, &limits)?;
+                decoder(decompressed_bytes, &limits)
+            })
+        } else {
+            let decoder = registry.storage_logs_chunk_decoder(self.format_version)?;
+            tokio::task::spawn_blocking(move || decoder(bytes, &limits))
+        };
+        let decode_result = if let Some(timeout) = self.config.chunk_decode_timeout {
+            tokio::time::timeout(timeout, decode_task)
+                .await
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "decoding storage logs chunk {chunk_id} didn't finish in {timeout:?}; \
+                         the chunk is probably malformed"
+                    )
+                })?
+        } else {
+            decode_task.await
+        };
+        let decode_result = decode_result
+            .with_context(|| format!("decoding storage logs chunk {chunk_id} panicked"))?;
+        let chunk = decode_result.map_err(|err| {
+            SnapshotsApplierError::object_store(
+                ObjectStoreError::Serialization(err.into()),
+                format!("failed decoding storage logs chunk {chunk_id}"),
+            )
+        })?;
+        METRICS.storage_logs_chunk_size.observe(byte_size);
+        METRICS
+            .storage_logs_chunk_log_count
+            .observe(chunk.storage_logs.len());
+        Ok(chunk)
+    }
+
+    #[tracing::instrument(level = "debug", err, skip(self))]
+    async fn recover_storage_logs_single_chunk(
+        &self,
+        concurrency_ramp: &ConcurrencyRamp,
+        watchdog: Option<&ProgressWatchdog>,
+        chunk_id: u64,
+    ) -> Result<(), SnapshotsApplierError> {
+        let _permit = concurrency_ramp.acquire().await;
+        if self.is_shutdown_requested() {
+            tracing::info!(
+                "Not processing storage logs chunk {chunk_id} since shutdown is requested"
+            );
+            return Ok(());
+        }
+
+        tracing::info!("Processing storage logs chunk {chunk_id}");
+        let debug_handle = &self.config.debug_handle;
+        debug_handle.chunk_started(chunk_id);
+        let latency =
+            METRICS.storage_logs_chunks_duration[&StorageLogsChunksStage::LoadFromGcs].start();
+
+        let download_guard = debug_handle.download_started();
+        let storage_snapshot_chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+        drop(download_guard);
+        let decoded_guard = debug_handle.chunk_decoded();
+        let storage_logs = &storage_snapshot_chunk.storage_logs;
+        if self.config.verification.verify_chunks_inline {
+            self.check_storage_logs_chunk_integrity(chunk_id, storage_logs)
+                .with_context(|| format!("storage logs chunk {chunk_id} failed integrity check"))?;
+        }
+        if self.config.verification.verify_storage_log_proofs {
+            let root_hash = self.applied_snapshot_status.l1_batch_root_hash;
+            proofs::verify_storage_log_proofs(chunk_id, &storage_snapshot_chunk, root_hash)?;
+        }
+        let latency = latency.observe();
+        tracing::info!(
+            "Loaded {} storage logs from GCS for chunk {chunk_id} in {latency:?}",
+            storage_logs.len()
+        );
+
+        let latency =
+            METRICS.storage_logs_chunks_duration[&StorageLogsChunksStage::SaveToPostgres].start();
+
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let mut storage_transaction =
+            Self::start_chunk_transaction(self.config, &mut storage, chunk_id).await?;
+        if self.config.chunk_id_range.is_some()
+            && !self
+                .lock_storage_logs_chunk(chunk_id, &mut storage_transaction)
+                .await?
+        {
+            return Ok(());
+        }
+
+        let components = self.config.components;
+        if components.storage_logs {
+            tracing::info!("Loading {} storage logs into Postgres", storage_logs.len());
+            #[cfg(feature = "chaos")]
+            self.config.failure_injection.db_insert(chunk_id)?;
+            self.insert_storage_logs_chunk(chunk_id, storage_logs, &mut storage_transaction)
+                .await?;
+            self.insert_initial_writes_chunk(chunk_id, storage_logs, &mut storage_transaction)
+                .await?;
+            if self
+                .config
+                .verification
+                .verify_chunks_before_marking_processed
+            {
+                self.check_persisted_storage_logs_count(
+                    chunk_id,
+                    storage_logs,
+                    &mut storage_transaction,
+                )
+                .await?;
+            }
+            if self.config.verification.verify_factory_deps_completeness {
+                Self::check_factory_deps_completeness(
+                    chunk_id,
+                    storage_logs,
+                    &mut storage_transaction,
+                )
+                .await?;
+            }
+        }
+        // Sinks must be written to before the chunk is marked as processed; otherwise, the sink data
+        // may be incomplete if the applier is interrupted. This makes delivery at-least-once, which
+        // is a part of the `StorageLogsSink` contract.
+        for sink in &self.config.sinks.storage_logs_sinks {
+            sink.write_storage_logs_chunk(&self.applied_snapshot_status, chunk_id, storage_logs)
+                .await
+                .with_context(|| {
+                    format!("failed writing storage logs chunk {chunk_id} to {sink:?}")
+                })?;
+        }
+
+        if components.recovery_status {
+            storage_transaction
+                .snapshot_recovery_dal()
+                .mark_storage_logs_chunk_as_processed(chunk_id)
+                .await
+                .map_err(|err| {
+                    let context =
+                        format!("failed marking storage logs chunk {chunk_id} as processed");
+                    SnapshotsApplierError::db(err, context)
+                })?;
+        }
+        // Dropping the transaction on an injected failure rolls it back, just like a failed commit.
+        #[cfg(feature = "chaos")]
+        self.config.failure_injection.db_commit(chunk_id)?;
+        storage_transaction.commit().await.map_err(|err| {
+            let context = format!("cannot commit DB transaction for storage logs chunk {chunk_id}");
+            SnapshotsApplierError::db(err, context)
+        })?;
+        drop(storage);
+        drop(decoded_guard);
+        self.prewarm_relations(chunk_id).await?;
+        if self.config.applied_manifest_path.is_some() {
+            let applied_chunk =
+                AppliedChunk::new(chunk_id, storage_logs, self.config.checksum_parallelism);
+            self.applied_chunks.lock().unwrap().push(applied_chunk);
+        }
+        self.record_processed_chunk(chunk_id, storage_logs.len() as u64)
+            .await?;
+
+        let chunks_left = METRICS.storage_logs_chunks_left_to_process.dec_by(1) - 1;
+        self.config.health_check.chunk_recovered();
+        concurrency_ramp.chunk_processed();
+        if let Some(watchdog) = watchdog {
+            watchdog.report_progress();
+        }
+        let latency = latency.observe();
+        tracing::info!("Saved storage logs for chunk {chunk_id} in {latency:?}, there are {chunks_left} left to process");
+
+        Ok(())
+    }
+
+    /// Starts a DB transaction for applying the specified storage logs chunk.
+    async fn start_chunk_transaction<'s>(
+        config: &SnapshotsApplierConfig,
+        storage: &'s mut StorageProcessor<'_>,
+        chunk_id: u64,
+    ) -> Result<StorageProcessor<'s>, SnapshotsApplierError> {
+        let transaction = if let Some(level) = config.chunk_transaction_isolation_level {
+            storage.start_transaction_with_isolation_level(level).await
+        } else {
+            storage.start_transaction().await
+        };
+        transaction.map_err(|err| {
+            let context = format!("cannot start DB transaction for storage logs chunk {chunk_id}");
+            SnapshotsApplierError::db(err, context)
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn recover_storage_logs(&self) -> Result<(), SnapshotsApplierError> {
+        if self.config.apply_via_staging_tables && self.config.components.storage_logs {
+            self.create_staging_tables().await?;
+        }
+        let concurrency_ramp = ConcurrencyRamp::new(
+            self.config
+                .effective_concurrency(self.connection_pool.max_size()),
+            self.config.throttling.concurrency_ramp_chunks,
+        );
+        let watchdog = self.config.stall_timeout.map(ProgressWatchdog::new);
+        let chunk_ids = self.chunks_to_process().collect();
+        let chunk_ids = self.order_storage_logs_chunks(chunk_ids).await?;
+        // Chunk processing is started in the order of `chunk_ids` since the concurrency limiter is fair.
+        let tasks = chunk_ids.into_iter().map(|chunk_id| {
+            self.recover_storage_logs_single_chunk(&concurrency_ramp, watchdog.as_ref(), chunk_id)
+        });
+        let all_tasks = async {
+            let all_tasks = futures::future::try_join_all(tasks);
+            if let Some(watchdog) = &watchdog {
+                tokio::select! {
+                    result = all_tasks => {
+                        result?;
+                    }
+                    stalled = watchdog.wait_for_stall() => {
+                        return Err(SnapshotsApplierError::Retryable(stalled.into()));
+                    }
+                }
+            } else {
+                all_tasks.await?;
+            }
+            Ok(())
+        };
+        if let Some(stop_receiver) = &self.config.stop_receiver {
+            self.process_chunks_until_shutdown(all_tasks, stop_receiver.clone())
+                .await?;
+        } else {
+            all_tasks.await?;
+        }
+
+        if self.config.components.storage_logs && self.are_all_chunks_processed().await? {
+            self.swap_staging_tables().await?;
+            self.check_enumeration_index_base().await?;
+        }
+        Ok(())
+    }
+
+    fn is_shutdown_requested(&self) -> bool {
+        let stop_receiver = self.config.stop_receiver.as_ref();
+        stop_receiver.is_some_and(|receiver| *receiver.borrow())
+    }
+
+    /// Drives storage log chunk processing until it completes or a shutdown signal is received. In the latter case,
+    /// waits for in-flight chunks for up to [`SnapshotsApplierConfig::shutdown_grace_period`]; new chunks are not
+    /// started since they check [`Self::is_shutdown_requested()`].
+    async fn process_chunks_until_shutdown(
+        &self,
+        all_tasks: impl Future<Output = Result<(), SnapshotsApplierError>>,
+        mut stop_receiver: watch::Receiver<bool>,
+    ) -> Result<(), SnapshotsApplierError> {
+        tokio::pin!(all_tasks);
+        let is_shutdown_requested = tokio::select! {
+            result = &mut all_tasks => return result,
+            stop = stop_receiver.wait_for(|&stop| stop) => stop.is_ok(),
+        };
+        if !is_shutdown_requested {
+            // The stop signal sender is dropped, so shutdown cannot be requested.
+            return all_tasks.await;
+        }
+
+        let grace_period = self.config.shutdown_grace_period;
+        tracing::info!(
+            "Shutdown requested during snapshot recovery; waiting up to {grace_period:?} for in-flight storage log chunks"
+        );
+        match tokio::time::timeout(grace_period, all_tasks).await {
+            Ok(result) => {
+                result?;
+                tracing::info!("All in-flight storage log chunks were committed before shutdown");
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "In-flight storage log chunks were not committed in {grace_period:?}; rolling them back"
+                );
+            }
+        }
+        let reason = CancellationReason::Shutdown { grace_period };
+        Err(SnapshotsApplierError::Fatal(
+            SnapshotRecoveryCancelled::from(reason).into(),
+        ))
+    }
+
+    async fn create_staging_tables(&self) -> Result<(), SnapshotsApplierError> {
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        storage
+            .snapshot_recovery_dal()
+            .create_staging_tables()
+            .await
+            .map_err(|err| SnapshotsApplierError::db(err, "failed creating staging tables"))
+    }
+
+    /// Moves storage logs and initial writes from staging tables into live tables if
+    /// [`SnapshotsApplierConfig::apply_via_staging_tables`] is set. This is a no-op if the staging tables
+    /// were already moved (e.g., if the applier was interrupted after moving them).
+    async fn swap_staging_tables(&self) -> Result<(), SnapshotsApplierError> {
+        if !self.config.apply_via_staging_tables {
+            return Ok(());
+        }
+
+        let latency = Instant::now();
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let mut transaction = storage.start_transaction().await.map_err(|err| {
+            SnapshotsApplierError::db(
+                err,
+                "failed starting DB transaction for swapping staging tables",
+            )
+        })?;
+        let swapped = transaction
+            .snapshot_recovery_dal()
+            .swap_staging_tables()
+            .await
+            .map_err(|err| SnapshotsApplierError::db(err, "failed swapping staging tables"))?;
+        transaction.commit().await.map_err(|err| {
+            SnapshotsApplierError::db(err, "failed committing swapped staging tables")
+        })?;
+        if swapped {
+            tracing::info!(
+                "Moved recovered storage logs from staging tables in {:?}",
+                latency.elapsed()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns IDs of storage log chunks that are not processed yet and are assigned to this applier
+    /// (see [`SnapshotsApplierConfig::chunk_id_range`]).
+    fn chunks_to_process(&self) -> impl Iterator<Item = u64> + '_ {
+        let chunk_id_range = self.config.chunk_id_range.as_ref();
+        self.applied_snapshot_status
+            .storage_logs_chunks_processed
+            .iter()
+            .enumerate()
+            .filter(|(_, is_processed)| !**is_processed)
+            .map(|(chunk_id, _)| chunk_id as u64)
+            .filter(move |chunk_id| chunk_id_range.map_or(true, |range| range.contains(chunk_id)))
+    }
+
+    /// Locks the specified storage logs chunk for the lifetime of `storage_transaction` so that it isn't
+    /// concurrently processed by another applier. Returns `false` if the chunk should be skipped, i.e.,
+    /// it is locked by another applier or was already processed by it.
+    async fn lock_storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+        storage_transaction: &mut StorageProcessor<'_>,
+    ) -> Result<bool, SnapshotsApplierError> {
+        let mut dal = storage_transaction.snapshot_recovery_dal();
+        let is_locked = dal
+            .try_lock_storage_logs_chunk(chunk_id)
+            .await
+            .map_err(|err| {
+                let context = format!("failed locking storage logs chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        if !is_locked {
+            tracing::info!(
+                "Storage logs chunk {chunk_id} is processed by another applier; skipping"
+            );
+            return Ok(false);
+        }
+
+        let is_processed = dal
+            .is_storage_logs_chunk_processed(chunk_id)
+            .await
+            .map_err(|err| {
+                let context =
+                    format!("failed checking whether storage logs chunk {chunk_id} is processed");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        if is_processed {
+            tracing::info!(
+                "Storage logs chunk {chunk_id} was processed by another applier; skipping"
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Checks whether all storage log chunks are processed, including ones processed by other appliers
+    /// if recovery is sharded. Checks that require the entire snapshot to be applied are only performed if this
+    /// returns `true`.
+    /// Checks whether post-recovery checks (enumeration index base, state checksum and acceptance queries) have already passed
+    /// for a complete recovery, e.g. before the applier was restarted.
+    async fn are_post_recovery_checks_passed(&self) -> Result<bool, SnapshotsApplierError> {
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        storage
+            .snapshot_recovery_dal()
+            .are_post_recovery_checks_passed()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed checking post-recovery checks marker")
+            })
+    }
+
+    async fn mark_post_recovery_checks_as_passed(&self) -> Result<(), SnapshotsApplierError> {
+        if !self.config.components.recovery_status {
+            return Ok(());
+        }
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        storage
+            .snapshot_recovery_dal()
+            .mark_post_recovery_checks_as_passed()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed marking post-recovery checks as passed")
+            })
+    }
+
+    async fn are_all_chunks_processed(&self) -> Result<bool, SnapshotsApplierError> {
+        if self.config.chunk_id_range.is_none() {
+            // Chunks are only processed by this applier, so they are all processed once this method is called.
+            return Ok(true);
+        }
+
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed fetching applied snapshot status from DB")
+            })?;
+        let all_processed = status.map_or(false, |status| {
+            status.storage_logs_chunks_left_to_process() == 0
+        });
+        if !all_processed {
+            tracing::info!(
+                "Some storage log chunks are still being processed by other appliers; skipping checks \
+                 requiring the entire snapshot to be applied"
+            );
+        }
+        Ok(all_processed)
+    }
 
-        Ok(SnapshotRecoveryStatus {
-            l1_batch_number,
-            l1_batch_timestamp: snapshot.last_l1_batch_with_metadata.header.timestamp,
-            l1_batch_root_hash: snapshot.last_l1_batch_with_metadata.metadata.root_hash,
-            miniblock_number: snapshot.miniblock_number,
-            miniblock_timestamp: miniblock.timestamp,
-            miniblock_hash,
-            protocol_version: snapshot
-                .last_l1_batch_with_metadata
-                .header
-                .protocol_version
-                .unwrap(),
-            storage_logs_chunks_processed: vec![false; snapshot.storage_logs_chunks.len()],
-        })
+    /// Orders storage log chunks for processing according to [`SnapshotsApplierConfig::chunk_order`].
+    async fn order_storage_logs_chunks(
+        &self,
+        chunk_ids: Vec<u64>,
+    ) -> Result<Vec<u64>, SnapshotsApplierError> {
+        match self.config.chunk_order {
+            StorageLogsChunkOrder::Sequential => Ok(chunk_ids),
+            StorageLogsChunkOrder::LargestFirst => {
+                self.order_largest_storage_logs_chunks_first(chunk_ids)
+                    .await
+            }
+            StorageLogsChunkOrder::Locality(ordering) => {
+                let chunks = chunk_ids
+                    .into_iter()
+                    .map(|chunk_id| (chunk_id, self.storage_logs_chunk_key(chunk_id)));
+                Ok(ordering.order(chunks))
+            }
+        }
     }
 
-    async fn recover_factory_deps(
-        &mut self,
-        storage: &mut StorageProcessor<'_>,
-    ) -> Result<(), SnapshotsApplierError> {
-        let latency = METRICS.initial_stage_duration[&InitialStage::ApplyFactoryDeps].start();
+    async fn order_largest_storage_logs_chunks_first(
+        &self,
+        chunk_ids: Vec<u64>,
+    ) -> Result<Vec<u64>, SnapshotsApplierError> {
+        let size_futures = chunk_ids.iter().map(|&chunk_id| {
+            let key = self.storage_logs_chunk_key(chunk_id);
+            async move {
+                self.blob_store
+                    .get_size_raw(SnapshotStorageLogsChunk::BUCKET, &key)
+                    .await
+            }
+        });
+        match futures::future::try_join_all(size_futures).await {
+            Ok(sizes) => {
+                let mut chunks_with_sizes: Vec<_> = chunk_ids.into_iter().zip(sizes).collect();
+                chunks_with_sizes.sort_by_key(|&(_, size)| Reverse(size));
+                let chunk_ids = chunks_with_sizes
+                    .into_iter()
+                    .map(|(chunk_id, _)| chunk_id)
+                    .collect();
+                Ok(chunk_ids)
+            }
+            Err(err) => {
+                self.config.report_warning(format_args!(
+                    "Failed getting sizes of storage log chunks: {err}; processing chunks in the order of their IDs"
+                ))?;
+                Ok(chunk_ids)
+            }
+        }
+    }
 
-        tracing::debug!("Fetching factory dependencies from object store");
+    /// Returns the object store key of the specified storage logs chunk.
+    fn storage_logs_chunk_key(&self, chunk_id: u64) -> String {
         let l1_batch_number = self.applied_snapshot_status.l1_batch_number;
-        let factory_deps: SnapshotFactoryDependencies =
-            self.blob_store.get(l1_batch_number).await.map_err(|err| {
-                let context = format!(
-                    "cannot fetch factory deps for L1 batch #{l1_batch_number} from object store"
-                );
-                SnapshotsApplierError::object_store(err, context)
-            })?;
-        tracing::debug!(
-            "Fetched {} factory dependencies from object store",
-            factory_deps.factory_deps.len()
+        if let Some(chunk) = self.content_addressed_chunks.get(&chunk_id) {
+            chunk.key.clone()
+        } else if let Some(template) = &self.path_template {
+            template.render(l1_batch_number, chunk_id)
+        } else {
+            SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+                l1_batch_number,
+                chunk_id,
+            })
+        }
+    }
+
+    /// Compares values of a sample of applied storage logs with values returned by the main node
+    /// as configured by [`VerificationConfig::main_node_reconciliation_sample_size`].
+    async fn reconcile_with_main_node(
+        &self,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> Result<(), SnapshotsApplierError> {
+        let sample_size = self
+            .config
+            .verification
+            .main_node_reconciliation_sample_size;
+        let chunk_count = self
+            .applied_snapshot_status
+            .storage_logs_chunks_processed
+            .len();
+        if sample_size == 0 || chunk_count == 0 {
+            return Ok(());
+        }
+
+        let chunk_id = rand::thread_rng().gen_range(0..chunk_count) as u64;
+        let chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+        let sampled_logs: Vec<_> = chunk
+            .storage_logs
+            .choose_multiple(&mut rand::thread_rng(), sample_size)
+            .collect();
+        tracing::info!(
+            "Reconciling {} storage log(s) from chunk {chunk_id} with main node",
+            sampled_logs.len()
         );
 
-        let all_deps_hashmap: HashMap<H256, Vec<u8>> = factory_deps
-            .factory_deps
-            .into_iter()
-            .map(|dep| (hash_bytecode(&dep.bytecode.0), dep.bytecode.0))
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        let hashed_keys: Vec<_> = sampled_logs
+            .iter()
+            .map(|log| log.key.hashed_key())
             .collect();
-        storage
-            .factory_deps_dal()
-            .insert_factory_deps(
-                self.applied_snapshot_status.miniblock_number,
-                &all_deps_hashmap,
-            )
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let values = storage
+            .storage_logs_dal()
+            .get_storage_values(&hashed_keys, miniblock_number)
             .await
             .map_err(|err| {
-                SnapshotsApplierError::db(err, "failed persisting factory deps to DB")
+                let context = format!("failed fetching storage values for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
             })?;
+        drop(storage);
 
-        let latency = latency.observe();
-        tracing::info!("Applied factory dependencies in {latency:?}");
+        for (log, hashed_key) in sampled_logs.iter().zip(&hashed_keys) {
+            let value = values.get(hashed_key).copied().flatten();
+            let main_node_value = main_node_client
+                .fetch_storage_value_at(&log.key, miniblock_number)
+                .await?;
+            if value != Some(main_node_value) {
+                let err = anyhow::anyhow!(
+                    "storage log for key {:?} has value {value:?} in Postgres, while the main node returns \
+                     {main_node_value:?} for miniblock #{miniblock_number}",
+                    log.key
+                );
+                return Err(err.into());
+            }
+        }
+        tracing::info!(
+            "Reconciled {} storage log(s) with main node",
+            sampled_logs.len()
+        );
+        Ok(())
+    }
+
+    /// Checks that storage log chunks marked as processed have all their storage logs persisted in Postgres.
+    /// Resets the processed flag for chunks that don't.
+    async fn repair_processed_chunks(
+        &mut self,
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        let verified_chunks = &self.checkpoint_state.get_mut().checkpoint.verified_chunks;
+        let processed_chunk_ids: Vec<_> = self
+            .applied_snapshot_status
+            .storage_logs_chunks_processed
+            .iter()
+            .enumerate()
+            .filter_map(|(chunk_id, &is_processed)| is_processed.then_some(chunk_id as u64))
+            .collect();
+        let (verified_chunk_ids, processed_chunk_ids): (Vec<_>, Vec<_>) = processed_chunk_ids
+            .into_iter()
+            .partition(|&chunk_id| verified_chunks[chunk_id as usize]);
+        tracing::info!(
+            "Checking consistency of {} processed storage logs chunk(s); skipping {} chunk(s) covered \
+             by the verification checkpoint",
+            processed_chunk_ids.len(),
+            verified_chunk_ids.len()
+        );
+
+        for chunk_id in processed_chunk_ids {
+            let chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+            let hashed_keys: Vec<_> = chunk
+                .storage_logs
+                .iter()
+                .map(|log| log.key.hashed_key())
+                .collect();
+            let persisted_count = storage
+                .storage_logs_dal()
+                .count_storage_logs_for_keys(&hashed_keys, miniblock_number)
+                .await
+                .map_err(|err| {
+                    let context = format!("failed counting storage logs for chunk {chunk_id}");
+                    SnapshotsApplierError::db(err, context)
+                })?;
+            if persisted_count == hashed_keys.len() as u64 {
+                continue;
+            }
 
+            self.config.report_warning(format_args!(
+                "Storage logs chunk {chunk_id} is marked as processed, but only {persisted_count} out of {} \
+                 storage logs are persisted; resetting processed flag",
+                hashed_keys.len()
+            ))?;
+            storage
+                .snapshot_recovery_dal()
+                .mark_storage_logs_chunk_as_unprocessed(chunk_id)
+                .await
+                .map_err(|err| {
+                    let context =
+                        format!("failed marking storage logs chunk {chunk_id} as unprocessed");
+                    SnapshotsApplierError::db(err, context)
+                })?;
+            self.applied_snapshot_status.storage_logs_chunks_processed[chunk_id as usize] = false;
+        }
         Ok(())
     }
 
-    async fn insert_initial_writes_chunk(
-        &self,
-        chunk_id: u64,
-        storage_logs: &[SnapshotStorageLog],
+    /// Re-derives processed flags for all storage log chunks from the storage logs persisted in Postgres, ignoring
+    /// the persisted flags and the verification checkpoint. A chunk is considered processed if all its storage logs
+    /// are persisted. Reconciled flags are persisted in Postgres.
+    async fn reconcile_progress(
+        &mut self,
         storage: &mut StorageProcessor<'_>,
     ) -> Result<(), SnapshotsApplierError> {
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        let chunk_count = self
+            .applied_snapshot_status
+            .storage_logs_chunks_processed
+            .len();
+        tracing::info!("Reconciling progress for {chunk_count} storage logs chunk(s)");
+
+        let mut changed_flag_count = 0;
+        for chunk_id in 0..chunk_count as u64 {
+            let chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+            let hashed_keys: Vec<_> = chunk
+                .storage_logs
+                .iter()
+                .map(|log| log.key.hashed_key())
+                .collect();
+            let persisted_count = storage
+                .storage_logs_dal()
+                .count_storage_logs_for_keys(&hashed_keys, miniblock_number)
+                .await
+                .map_err(|err| {
+                    let context = format!("failed counting storage logs for chunk {chunk_id}");
+                    SnapshotsApplierError::db(err, context)
+                })?;
+            let is_processed = persisted_count == hashed_keys.len() as u64;
+
+            let flag =
+                &mut self.applied_snapshot_status.storage_logs_chunks_processed[chunk_id as usize];
+            if *flag != is_processed {
+                self.config.report_warning(format_args!(
+                    "Storage logs chunk {chunk_id} has {persisted_count} out of {} storage logs persisted, \
+                     but is marked as {}processed; fixing processed flag",
+                    hashed_keys.len(),
+                    if *flag { "" } else { "not " }
+                ))?;
+                *flag = is_processed;
+                changed_flag_count += 1;
+            }
+        }
+
         storage
-            .storage_logs_dedup_dal()
-            .insert_initial_writes_from_snapshot(storage_logs)
+            .snapshot_recovery_dal()
+            .set_storage_logs_chunks_processed(
+                &self.applied_snapshot_status.storage_logs_chunks_processed,
+            )
             .await
             .map_err(|err| {
-                let context =
-                    format!("failed persisting initial writes from storage logs chunk {chunk_id}");
-                SnapshotsApplierError::db(err, context)
+                SnapshotsApplierError::db(err, "failed persisting reconciled processed flags")
             })?;
+        tracing::info!("Reconciled progress; fixed {changed_flag_count} processed flag(s)");
         Ok(())
     }
 
-    async fn insert_storage_logs_chunk(
-        &self,
-        chunk_id: u64,
-        storage_logs: &[SnapshotStorageLog],
+    /// Re-verifies the processed storage logs chunk with the greatest ID as configured by
+    /// [`SnapshotsApplierConfig::reverify_last_processed_chunk`].
+    async fn reverify_last_processed_chunk(
+        &mut self,
         storage: &mut StorageProcessor<'_>,
     ) -> Result<(), SnapshotsApplierError> {
+        let last_processed_chunk_id = self
+            .applied_snapshot_status
+            .storage_logs_chunks_processed
+            .iter()
+            .rposition(|&is_processed| is_processed);
+        let Some(chunk_id) = last_processed_chunk_id else {
+            return Ok(());
+        };
+        let chunk_id = chunk_id as u64;
+        let chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+        let hashed_keys: Vec<_> = chunk
+            .storage_logs
+            .iter()
+            .map(|log| log.key.hashed_key())
+            .collect();
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
+        let persisted_count = storage
+            .storage_logs_dal()
+            .count_storage_logs_for_keys(&hashed_keys, miniblock_number)
+            .await
+            .map_err(|err| {
+                let context = format!("failed counting storage logs for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        if persisted_count == hashed_keys.len() as u64 {
+            tracing::info!("Re-verified last processed storage logs chunk {chunk_id}");
+            return Ok(());
+        }
+
+        self.config.report_warning(format_args!(
+            "Last processed storage logs chunk {chunk_id} has only {persisted_count} out of {} storage logs persisted; \
+             removing its persisted data and resetting processed flag",
+            hashed_keys.len()
+        ))?;
         storage
             .storage_logs_dal()
-            .insert_storage_logs_from_snapshot(
-                self.applied_snapshot_status.miniblock_number,
-                storage_logs,
-            )
+            .delete_storage_logs_for_keys(&hashed_keys, miniblock_number)
+            .await
+            .map_err(|err| {
+                let context = format!("failed removing storage logs for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        storage
+            .storage_logs_dedup_dal()
+            .delete_initial_writes_for_keys(&hashed_keys)
+            .await
+            .map_err(|err| {
+                let context = format!("failed removing initial writes for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        storage
+            .snapshot_recovery_dal()
+            .mark_storage_logs_chunk_as_unprocessed(chunk_id)
             .await
             .map_err(|err| {
-                let context = format!("failed persisting storage logs from chunk {chunk_id}");
+                let context =
+                    format!("failed marking storage logs chunk {chunk_id} as unprocessed");
                 SnapshotsApplierError::db(err, context)
             })?;
+        self.applied_snapshot_status.storage_logs_chunks_processed[chunk_id as usize] = false;
         Ok(())
     }
 
-    #[tracing::instrument(level = "debug", err, skip(self))]
-    async fn recover_storage_logs_single_chunk(
+    /// Records a processed storage logs chunk and runs a verification checkpoint if
+    /// [`VerificationConfig::verification_checkpoint_interval`] chunks were processed since the latest one.
+    async fn record_processed_chunk(
         &self,
-        semaphore: &Semaphore,
         chunk_id: u64,
+        storage_logs_count: u64,
     ) -> Result<(), SnapshotsApplierError> {
-        // `unwrap()` is safe: the semaphore is never closed
-        let _permit = semaphore.acquire().await.unwrap();
+        let interval = self.config.verification.verification_checkpoint_interval;
+        let components = self.config.components;
+        if interval == 0 || !components.storage_logs || !components.recovery_status {
+            return Ok(());
+        }
 
-        tracing::info!("Processing storage logs chunk {chunk_id}");
-        let latency =
-            METRICS.storage_logs_chunks_duration[&StorageLogsChunksStage::LoadFromGcs].start();
+        // The lock is held during the checkpoint, so that checkpoints are persisted in order.
+        let mut state = self.checkpoint_state.lock().await;
+        state.pending_chunks.push((chunk_id, storage_logs_count));
+        if state.pending_chunks.len() < interval {
+            return Ok(());
+        }
 
-        let storage_key = SnapshotStorageLogsStorageKey {
-            chunk_id,
-            l1_batch_number: self.applied_snapshot_status.l1_batch_number,
-        };
-        let storage_snapshot_chunk: SnapshotStorageLogsChunk =
-            self.blob_store.get(storage_key).await.map_err(|err| {
-                let context =
-                    format!("cannot fetch storage logs {storage_key:?} from object store");
-                SnapshotsApplierError::object_store(err, context)
-            })?;
-        let storage_logs = &storage_snapshot_chunk.storage_logs;
-        let latency = latency.observe();
-        tracing::info!(
-            "Loaded {} storage logs from GCS for chunk {chunk_id} in {latency:?}",
-            storage_logs.len()
-        );
+        let mut checkpoint = state.checkpoint.clone();
+        for &(chunk_id, storage_logs_count) in &state.pending_chunks {
+            checkpoint.verified_chunks[chunk_id as usize] = true;
+            checkpoint.storage_logs_count += storage_logs_count;
+        }
+        self.persist_verification_checkpoint(&checkpoint).await?;
+        state.checkpoint = checkpoint;
+        state.pending_chunks.clear();
+        Ok(())
+    }
 
-        let latency =
-            METRICS.storage_logs_chunks_duration[&StorageLogsChunksStage::SaveToPostgres].start();
+    /// Runs a lightweight integrity check of applied storage logs and persists the checkpoint if it succeeds.
+    async fn persist_verification_checkpoint(
+        &self,
+        checkpoint: &SnapshotRecoveryCheckpoint,
+    ) -> Result<(), SnapshotsApplierError> {
+        // If recovery is sharded, storage logs with the lowest enumeration indices may be applied by another applier.
+        if self.config.chunk_id_range.is_none() {
+            self.check_enumeration_index_base().await?;
+        }
 
+        let miniblock_number = self.applied_snapshot_status.miniblock_number;
         let mut storage = self
             .connection_pool
             .access_storage_tagged("snapshots_applier")
             .await?;
-        let mut storage_transaction = storage.start_transaction().await.map_err(|err| {
-            let context = format!("cannot start DB transaction for storage logs chunk {chunk_id}");
-            SnapshotsApplierError::db(err, context)
-        })?;
-
-        tracing::info!("Loading {} storage logs into Postgres", storage_logs.len());
-        self.insert_storage_logs_chunk(chunk_id, storage_logs, &mut storage_transaction)
-            .await?;
-        self.insert_initial_writes_chunk(chunk_id, storage_logs, &mut storage_transaction)
-            .await?;
+        // Chunks not covered by the checkpoint may be concurrently persisted, so the count may be greater
+        // than the checkpoint count.
+        let persisted_count = storage
+            .storage_logs_dal()
+            .count_miniblock_storage_logs(miniblock_number)
+            .await
+            .map_err(|err| SnapshotsApplierError::db(err, "failed counting storage logs"))?;
+        if persisted_count < checkpoint.storage_logs_count {
+            let err = anyhow::anyhow!(
+                "verification checkpoint failed: only {persisted_count} storage logs are persisted in Postgres, \
+                 while processed chunks contain {} storage logs",
+                checkpoint.storage_logs_count
+            );
+            return Err(err.into());
+        }
 
-        storage_transaction
+        storage
             .snapshot_recovery_dal()
-            .mark_storage_logs_chunk_as_processed(chunk_id)
+            .save_verification_checkpoint(checkpoint)
+            .await
+            .map_err(|err| {
+                SnapshotsApplierError::db(err, "failed persisting verification checkpoint")
+            })?;
+        let verified_chunk_count = checkpoint
+            .verified_chunks
+            .iter()
+            .filter(|&&is_verified| is_verified)
+            .count();
+        tracing::info!(
+            "Persisted verification checkpoint covering {verified_chunk_count} storage logs chunk(s) \
+             with {} storage logs",
+            checkpoint.storage_logs_count
+        );
+        Ok(())
+    }
+
+    /// Checks that storage logs from the specified chunk are persisted in Postgres as is.
+    async fn verify_storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+        storage: &mut StorageProcessor<'_>,
+    ) -> Result<(), SnapshotsApplierError> {
+        let hashed_keys: Vec<_> = storage_logs
+            .iter()
+            .map(|log| log.key.hashed_key())
+            .collect();
+        let values = storage
+            .storage_logs_dal()
+            .get_storage_values(&hashed_keys, self.applied_snapshot_status.miniblock_number)
             .await
             .map_err(|err| {
-                let context = format!("failed marking storage logs chunk {chunk_id} as processed");
+                let context = format!("failed fetching storage values for chunk {chunk_id}");
+                SnapshotsApplierError::db(err, context)
+            })?;
+        let initial_writes = storage
+            .storage_logs_dal()
+            .get_l1_batches_and_indices_for_initial_writes(&hashed_keys)
+            .await
+            .map_err(|err| {
+                let context = format!("failed fetching initial writes for chunk {chunk_id}");
                 SnapshotsApplierError::db(err, context)
             })?;
-        storage_transaction.commit().await.map_err(|err| {
-            let context = format!("cannot commit DB transaction for storage logs chunk {chunk_id}");
-            SnapshotsApplierError::db(err, context)
-        })?;
 
-        let chunks_left = METRICS.storage_logs_chunks_left_to_process.dec_by(1) - 1;
-        let latency = latency.observe();
-        tracing::info!("Saved storage logs for chunk {chunk_id} in {latency:?}, there are {chunks_left} left to process");
+        for (log, hashed_key) in storage_logs.iter().zip(&hashed_keys) {
+            let value = values.get(hashed_key).copied().flatten();
+            if value != Some(log.value) {
+                let err = anyhow::anyhow!(
+                    "storage logs chunk {chunk_id} is corrupted: storage log for key {:?} has value {value:?} \
+                     in Postgres, while the snapshot has {:?}",
+                    log.key,
+                    log.value
+                );
+                return Err(err.into());
+            }
 
+            let initial_write = initial_writes.get(hashed_key).copied();
+            let expected_initial_write =
+                (log.l1_batch_number_of_initial_write, log.enumeration_index);
+            if initial_write != Some(expected_initial_write) {
+                let err = anyhow::anyhow!(
+                    "storage logs chunk {chunk_id} is corrupted: initial write for key {:?} is {initial_write:?} \
+                     in Postgres, while the snapshot has {expected_initial_write:?}",
+                    log.key
+                );
+                return Err(err.into());
+            }
+        }
         Ok(())
     }
 
-    async fn recover_storage_logs(self) -> Result<(), SnapshotsApplierError> {
-        let semaphore = Semaphore::new(self.connection_pool.max_size() as usize);
-        let tasks = self
+    async fn verify_applied_storage_logs_chunk(
+        &self,
+        semaphore: &Semaphore,
+        chunk_id: u64,
+    ) -> Result<(), SnapshotsApplierError> {
+        // `unwrap()` is safe: the semaphore is never closed
+        let _permit = semaphore.acquire().await.unwrap();
+
+        let storage_snapshot_chunk = self.fetch_storage_logs_chunk(chunk_id).await?;
+        let mut storage = self
+            .connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        self.verify_storage_logs_chunk(
+            chunk_id,
+            &storage_snapshot_chunk.storage_logs,
+            &mut storage,
+        )
+        .await?;
+        tracing::debug!("Verified applied storage logs chunk {chunk_id}");
+        Ok(())
+    }
+
+    /// Re-verifies a sample of storage log chunks for a complete recovery against the object store
+    /// as configured by [`VerificationConfig::restart_verification_fraction`].
+    async fn verify_applied_storage_logs(&self) -> Result<(), SnapshotsApplierError> {
+        let chunk_count = self
             .applied_snapshot_status
             .storage_logs_chunks_processed
-            .iter()
-            .enumerate()
-            .filter(|(_, is_processed)| !**is_processed)
-            .map(|(chunk_id, _)| {
-                self.recover_storage_logs_single_chunk(&semaphore, chunk_id as u64)
-            });
-        futures::future::try_join_all(tasks).await?;
+            .len();
+        let fraction = self
+            .config
+            .verification
+            .restart_verification_fraction
+            .clamp(0.0, 1.0);
+        let sample_count = ((chunk_count as f64) * fraction).ceil() as usize;
+        if sample_count == 0 {
+            return Ok(());
+        }
 
+        tracing::info!(
+            "Re-verifying {sample_count} out of {chunk_count} applied storage logs chunk(s)"
+        );
+        let semaphore = Semaphore::new(self.connection_pool.max_size() as usize);
+        // Sampled chunks are spread evenly among all chunks.
+        let tasks = (0..sample_count).map(|i| {
+            let chunk_id = (i * chunk_count / sample_count) as u64;
+            self.verify_applied_storage_logs_chunk(&semaphore, chunk_id)
+        });
+        futures::future::try_join_all(tasks).await?;
+        tracing::info!("Verified {sample_count} applied storage logs chunk(s)");
         Ok(())
     }
 }
@@ -0,0 +1,55 @@
+//! Ordering of storage log chunks by the locality of their objects in the object store.
+
+use std::collections::HashMap;
+
+/// Function mapping the object store key of a storage logs chunk to the chunk locality (e.g., a key prefix
+/// or an object store region).
+pub type ChunkLocalityFn = fn(&str) -> String;
+
+/// Ordering of storage log chunks grouping chunks with the same locality, so that fetches from the same
+/// prefix / region are performed together. This maximizes connection reuse and minimizes cross-region hops
+/// if chunks are stored with different latencies.
+///
+/// Localities are processed in the order of their first chunk ID; within a locality, chunks are processed
+/// in the order of their IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalityOrdering {
+    locality_fn: ChunkLocalityFn,
+}
+
+impl LocalityOrdering {
+    /// Creates an ordering based on the specified key → locality function.
+    pub fn new(locality_fn: ChunkLocalityFn) -> Self {
+        Self { locality_fn }
+    }
+
+    /// Returns the locality of the chunk with the specified object store key.
+    pub fn locality(&self, key: &str) -> String {
+        (self.locality_fn)(key)
+    }
+
+    /// Orders chunks specified as `(chunk_id, key)` tuples sorted by the chunk ID.
+    pub(crate) fn order(&self, chunks: impl Iterator<Item = (u64, String)>) -> Vec<u64> {
+        let mut locality_indices = HashMap::new();
+        let mut chunks_with_localities: Vec<_> = chunks
+            .map(|(chunk_id, key)| {
+                let next_index = locality_indices.len();
+                let locality_index = *locality_indices
+                    .entry(self.locality(&key))
+                    .or_insert(next_index);
+                (locality_index, chunk_id)
+            })
+            .collect();
+        // The sort is stable, so chunks in each locality retain their order.
+        chunks_with_localities.sort_by_key(|&(locality_index, _)| locality_index);
+        tracing::info!(
+            "Grouped {} storage log chunks into {} localities",
+            chunks_with_localities.len(),
+            locality_indices.len()
+        );
+        chunks_with_localities
+            .into_iter()
+            .map(|(_, chunk_id)| chunk_id)
+            .collect()
+    }
+}
@@ -0,0 +1,123 @@
+//! Structural verification of snapshot headers.
+
+use std::collections::HashSet;
+
+use zksync_types::{snapshots::SnapshotHeader, L1BatchNumber, MiniblockNumber, ProtocolVersionId};
+
+use crate::PathTemplate;
+
+/// Structural issue in a [`SnapshotHeader`] found by [`verify_snapshot_manifest()`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ManifestIssue {
+    #[error("header doesn't reference any storage logs chunks")]
+    NoStorageLogsChunks,
+    #[error("storage logs chunk {0} is referenced several times")]
+    DuplicateChunkId(u64),
+    #[error("storage logs chunk ID {chunk_id} is out of range for {chunk_count} chunks")]
+    ChunkIdOutOfRange { chunk_id: u64, chunk_count: usize },
+    #[error("storage logs chunk {0} has an empty filepath")]
+    EmptyChunkFilepath(u64),
+    #[error("storage logs path template is invalid: {0}")]
+    InvalidPathTemplate(String),
+    #[error("factory dependencies filepath is empty")]
+    EmptyFactoryDepsFilepath,
+    #[error("factory dependencies shard #{0} has an empty key")]
+    EmptyFactoryDepsShardKey(usize),
+    #[error(
+        "L1 batch number #{header} differs from the number #{last_l1_batch} of the included L1 batch header"
+    )]
+    L1BatchNumberMismatch {
+        header: L1BatchNumber,
+        last_l1_batch: L1BatchNumber,
+    },
+    #[error(
+        "miniblock #{miniblock_number} cannot be the last miniblock of L1 batch #{l1_batch_number} \
+         since each L1 batch contains at least one miniblock"
+    )]
+    MiniblockNumberTooLow {
+        miniblock_number: MiniblockNumber,
+        l1_batch_number: L1BatchNumber,
+    },
+    #[error("L1 batch header doesn't specify a protocol version")]
+    MissingProtocolVersion,
+    #[error("protocol version {version:?} is newer than the latest known version {latest:?}")]
+    UnknownProtocolVersion {
+        version: ProtocolVersionId,
+        latest: ProtocolVersionId,
+    },
+}
+
+/// Verifies structural invariants of a snapshot header without accessing the object store or any other
+/// external resources. Thus, verification is cheap and can be used as a gate before downloading the snapshot
+/// (e.g., from a CLI tool).
+///
+/// Returns all found issues; an empty list means that the header is structurally valid. Passing verification
+/// doesn't guarantee that the snapshot is applicable (e.g., the referenced objects may be missing).
+pub fn verify_snapshot_manifest(header: &SnapshotHeader) -> Vec<ManifestIssue> {
+    let mut issues = vec![];
+
+    // Chunk filepaths are ignored if the path template is specified.
+    let is_templated = if let Some(template) = &header.storage_logs_path_template {
+        if let Err(err) = PathTemplate::new(template) {
+            issues.push(ManifestIssue::InvalidPathTemplate(format!("{err:#}")));
+        }
+        true
+    } else {
+        false
+    };
+
+    let chunk_count = header.storage_logs_chunks.len();
+    if chunk_count == 0 {
+        issues.push(ManifestIssue::NoStorageLogsChunks);
+    }
+    let mut chunk_ids = HashSet::with_capacity(chunk_count);
+    for chunk in &header.storage_logs_chunks {
+        if !chunk_ids.insert(chunk.chunk_id) {
+            issues.push(ManifestIssue::DuplicateChunkId(chunk.chunk_id));
+        }
+        if chunk.chunk_id >= chunk_count as u64 {
+            issues.push(ManifestIssue::ChunkIdOutOfRange {
+                chunk_id: chunk.chunk_id,
+                chunk_count,
+            });
+        }
+        if !is_templated && chunk.filepath.is_empty() {
+            issues.push(ManifestIssue::EmptyChunkFilepath(chunk.chunk_id));
+        }
+    }
+
+    if header.factory_deps_filepath.is_empty() {
+        issues.push(ManifestIssue::EmptyFactoryDepsFilepath);
+    }
+    for (i, shard_key) in header.factory_deps_shards.iter().enumerate() {
+        if shard_key.is_empty() {
+            issues.push(ManifestIssue::EmptyFactoryDepsShardKey(i));
+        }
+    }
+
+    let last_l1_batch_header = &header.last_l1_batch_with_metadata.header;
+    if last_l1_batch_header.number != header.l1_batch_number {
+        issues.push(ManifestIssue::L1BatchNumberMismatch {
+            header: header.l1_batch_number,
+            last_l1_batch: last_l1_batch_header.number,
+        });
+    }
+    if header.miniblock_number.0 < header.l1_batch_number.0 {
+        issues.push(ManifestIssue::MiniblockNumberTooLow {
+            miniblock_number: header.miniblock_number,
+            l1_batch_number: header.l1_batch_number,
+        });
+    }
+
+    match last_l1_batch_header.protocol_version {
+        None => issues.push(ManifestIssue::MissingProtocolVersion),
+        Some(version) if version > ProtocolVersionId::latest() => {
+            issues.push(ManifestIssue::UnknownProtocolVersion {
+                version,
+                latest: ProtocolVersionId::latest(),
+            });
+        }
+        Some(_) => { /* version is plausible */ }
+    }
+    issues
+}
@@ -4,6 +4,10 @@ use std::time::Duration;
 
 use vise::{Buckets, EncodeLabelSet, EncodeLabelValue, Family, Gauge, Histogram, Metrics, Unit};
 
+const CHUNK_SIZE_BUCKETS: Buckets =
+    Buckets::exponential(1_024.0..=1_024.0 * 1_024.0 * 1_024.0, 4.0);
+const CHUNK_LOG_COUNT_BUCKETS: Buckets = Buckets::exponential(1.0..=1_048_576.0, 4.0);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EncodeLabelValue, EncodeLabelSet)]
 #[metrics(label = "stage", rename_all = "snake_case")]
 pub(crate) enum StorageLogsChunksStage {
@@ -38,6 +42,14 @@ pub(crate) struct SnapshotsApplierMetrics {
     /// Latency of storage log chunk processing split by stage.
     #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
     pub storage_logs_chunks_duration: Family<StorageLogsChunksStage, Histogram<Duration>>,
+
+    /// Size of downloaded storage log chunks.
+    #[metrics(buckets = CHUNK_SIZE_BUCKETS, unit = Unit::Bytes)]
+    pub storage_logs_chunk_size: Histogram<usize>,
+
+    /// Number of storage logs in downloaded storage log chunks.
+    #[metrics(buckets = CHUNK_LOG_COUNT_BUCKETS)]
+    pub storage_logs_chunk_log_count: Histogram<usize>,
 }
 
 #[vise::register]
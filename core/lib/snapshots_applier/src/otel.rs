@@ -0,0 +1,26 @@
+//! OpenTelemetry integration.
+
+use tracing::Subscriber;
+use tracing_opentelemetry::PreSampledTracer;
+use tracing_subscriber::{filter::filter_fn, registry::LookupSpan, Layer};
+
+/// Creates a `tracing` layer exporting spans and events emitted by the snapshot applier to OpenTelemetry
+/// using the provided `tracer`. Spans and events from other crates are ignored, so the layer can be added
+/// to the app-wide subscriber.
+///
+/// Exported spans cover the entire recovery (`snapshot_recovery`), each recovery attempt, recovery stages
+/// (factory deps, tokens and storage logs), and processing of each storage logs chunk. Log events emitted
+/// by the applier (e.g., chunk processing latency and the number of applied storage logs) are exported
+/// as span events. Metrics are still reported via `vise`; they can be ingested by an OpenTelemetry collector
+/// using its Prometheus receiver.
+pub fn opentelemetry_layer<S, T>(tracer: T) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    T: opentelemetry::trace::Tracer + PreSampledTracer + Send + Sync + 'static,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(filter_fn(|metadata| {
+            metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+        }))
+}
@@ -0,0 +1,127 @@
+//! Templates for object store keys of storage log chunks.
+
+use std::fmt;
+
+use anyhow::Context as _;
+use zksync_types::L1BatchNumber;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    L1BatchNumber { width: usize },
+    ChunkId { width: usize },
+}
+
+/// Template for object store keys of storage log chunks, such as `snapshots/{l1_batch}/chunk_{chunk_id:06}.bin`.
+/// Allows computing chunk keys from `(l1_batch_number, chunk_id)` instead of storing explicit filepaths
+/// in the snapshot header.
+///
+/// Supported placeholders are `{l1_batch}` (the snapshot L1 batch number) and `{chunk_id}`; the latter
+/// is mandatory. A placeholder may specify a minimum width, in which case the number is left-padded with zeros
+/// (e.g., `{chunk_id:06}`). Literal braces are escaped as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.raw)
+    }
+}
+
+impl PathTemplate {
+    /// Parses a template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template is malformed, contains unknown placeholders, or doesn't contain
+    /// the `{chunk_id}` placeholder.
+    pub fn new(template: &str) -> anyhow::Result<Self> {
+        Self::parse(template).with_context(|| format!("invalid path template `{template}`"))
+    }
+
+    fn parse(template: &str) -> anyhow::Result<Self> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').context("unclosed `{`")?;
+                    let placeholder = Self::parse_placeholder(&rest[..end])?;
+                    chars = rest[end + 1..].chars();
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(placeholder);
+                }
+                '}' => {
+                    anyhow::ensure!(chars.next() == Some('}'), "unmatched `}}`");
+                    literal.push('}');
+                }
+                _ => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let has_chunk_id = segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::ChunkId { .. }));
+        anyhow::ensure!(
+            has_chunk_id,
+            "template must contain `{{chunk_id}}` placeholder"
+        );
+        Ok(Self {
+            raw: template.to_owned(),
+            segments,
+        })
+    }
+
+    fn parse_placeholder(placeholder: &str) -> anyhow::Result<Segment> {
+        let (name, width) = match placeholder.split_once(':') {
+            Some((name, spec)) => {
+                let width = spec
+                    .strip_prefix('0')
+                    .and_then(|width| width.parse::<usize>().ok())
+                    .with_context(|| {
+                        format!(
+                            "unsupported format spec `{spec}` for `{name}`; only zero-padding (e.g., `06`) is supported"
+                        )
+                    })?;
+                (name, width)
+            }
+            None => (placeholder, 0),
+        };
+        Ok(match name {
+            "l1_batch" => Segment::L1BatchNumber { width },
+            "chunk_id" => Segment::ChunkId { width },
+            _ => anyhow::bail!(
+                "unknown placeholder `{name}`; supported placeholders are `l1_batch` and `chunk_id`"
+            ),
+        })
+    }
+
+    /// Computes the object store key for the specified storage logs chunk.
+    pub fn render(&self, l1_batch_number: L1BatchNumber, chunk_id: u64) -> String {
+        let mut key = String::with_capacity(self.raw.len());
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => key.push_str(literal),
+                Segment::L1BatchNumber { width } => {
+                    key.push_str(&format!("{:0width$}", l1_batch_number.0));
+                }
+                Segment::ChunkId { width } => key.push_str(&format!("{chunk_id:0width$}")),
+            }
+        }
+        key
+    }
+}
@@ -0,0 +1,183 @@
+//! Object store reading objects from a framed byte stream (e.g., piped from another process).
+
+use std::{collections::HashMap, io, sync::Arc};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::watch,
+    task::JoinHandle,
+};
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError, StoredObject};
+
+#[derive(Debug, Default)]
+struct PipeContents {
+    objects: HashMap<String, Vec<u8>>,
+    /// Set once the stream is fully read (`Ok(())`), or has failed to be read (`Err(_)` with the error message).
+    finished: Option<Result<(), String>>,
+}
+
+/// Read-only [`ObjectStore`] surfacing objects from a framed byte stream as they arrive. This allows bootstrapping
+/// a node from a snapshot piped from another process without intermediate storage.
+///
+/// Each frame in the stream has the following format:
+///
+/// - Big-endian `u32` length of the object key, followed by the key as UTF-8. The key has the `{bucket}/{key}`
+///   format, e.g. `storage_logs_snapshots/snapshot_l1_batch_1_factory_deps.proto.gzip`.
+/// - Big-endian `u64` length of the object, followed by the object bytes.
+///
+/// Frames can be written using [`Self::write_object()`]. Requesting an object that has not arrived yet waits
+/// until it arrives; if the stream ends without the object, [`ObjectStoreError::KeyNotFound`] is returned.
+/// Received objects are retained in memory for the lifetime of the store.
+#[derive(Debug)]
+pub struct PipeObjectStore {
+    contents: Arc<watch::Sender<PipeContents>>,
+    reader_task: JoinHandle<()>,
+}
+
+impl Drop for PipeObjectStore {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+impl PipeObjectStore {
+    /// Creates a store reading frames from the provided `reader` in a background task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside the Tokio runtime.
+    pub fn new(reader: impl AsyncRead + Unpin + Send + 'static) -> Self {
+        let (contents, _) = watch::channel(PipeContents::default());
+        let contents = Arc::new(contents);
+        let contents_sender = contents.clone();
+        let reader_task = tokio::spawn(async move {
+            let result = Self::read_frames(reader, &contents_sender).await;
+            if let Err(err) = &result {
+                tracing::warn!("Failed reading objects from pipe: {err:#}");
+            }
+            contents_sender.send_modify(|contents| {
+                contents.finished = Some(result.map_err(|err| format!("{err:#}")));
+            });
+        });
+        Self {
+            contents,
+            reader_task,
+        }
+    }
+
+    fn full_key(bucket: Bucket, key: &str) -> String {
+        format!("{bucket}/{key}")
+    }
+
+    async fn read_frames(
+        mut reader: impl AsyncRead + Unpin,
+        contents: &watch::Sender<PipeContents>,
+    ) -> anyhow::Result<()> {
+        loop {
+            let key_len = match reader.read_u32().await {
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => {
+                    return Err(anyhow::Error::from(err).context("failed reading key length"))
+                }
+            };
+            let mut key = vec![0_u8; key_len as usize];
+            reader
+                .read_exact(&mut key)
+                .await
+                .context("failed reading key")?;
+            let key = String::from_utf8(key).context("object key is not UTF-8")?;
+
+            let value_len = reader
+                .read_u64()
+                .await
+                .with_context(|| format!("failed reading length of object `{key}`"))?;
+            let mut value = Vec::new();
+            // Using `take()` rather than pre-allocating the buffer so that a bogus length doesn't lead to OOM.
+            (&mut reader)
+                .take(value_len)
+                .read_to_end(&mut value)
+                .await
+                .with_context(|| format!("failed reading object `{key}`"))?;
+            anyhow::ensure!(
+                value.len() as u64 == value_len,
+                "stream ended while reading object `{key}`: expected {value_len} bytes, got {}",
+                value.len()
+            );
+
+            tracing::debug!("Received object `{key}` ({value_len} bytes) from pipe");
+            contents.send_modify(|contents| {
+                contents.objects.insert(key, value);
+            });
+        }
+    }
+
+    /// Writes a frame with the specified object to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Propagates serialization and I/O errors.
+    pub async fn write_object<V: StoredObject>(
+        writer: &mut (impl AsyncWrite + Unpin),
+        key: V::Key<'_>,
+        value: &V,
+    ) -> anyhow::Result<()> {
+        let key = Self::full_key(V::BUCKET, &V::encode_key(key));
+        let value = value
+            .serialize()
+            .map_err(|err| anyhow::anyhow!("failed serializing object `{key}`: {err}"))?;
+
+        writer.write_u32(key.len().try_into()?).await?;
+        writer.write_all(key.as_bytes()).await?;
+        writer.write_u64(value.len() as u64).await?;
+        writer.write_all(&value).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ObjectStore for PipeObjectStore {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let key = Self::full_key(bucket, key);
+        let mut contents_receiver = self.contents.subscribe();
+        loop {
+            {
+                let contents = contents_receiver.borrow_and_update();
+                if let Some(value) = contents.objects.get(&key) {
+                    return Ok(value.clone());
+                }
+                match &contents.finished {
+                    Some(Ok(())) => {
+                        let err = format!("object `{key}` is not present in the piped stream");
+                        return Err(ObjectStoreError::KeyNotFound(err.into()));
+                    }
+                    Some(Err(err)) => return Err(ObjectStoreError::Other(err.clone().into())),
+                    None => { /* wait for the object to arrive */ }
+                }
+            }
+            // `unwrap()` is safe: the sender is owned by `self`
+            contents_receiver.changed().await.unwrap();
+        }
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot put `{bucket}/{key}`: pipe object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot remove `{bucket}/{key}`: pipe object store is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        format!("pipe/{bucket}")
+    }
+}
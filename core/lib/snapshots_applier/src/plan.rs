@@ -0,0 +1,228 @@
+//! Planning snapshot recovery without executing it.
+
+use std::fmt;
+
+use anyhow::Context as _;
+use zksync_dal::ConnectionPool;
+use zksync_object_store::{Bucket, ObjectStore, StoredObject};
+use zksync_types::{
+    snapshots::{
+        SnapshotFactoryDependencies, SnapshotHeader, SnapshotStorageLogsChunk,
+        SnapshotStorageLogsStorageKey,
+    },
+    L1BatchNumber, MiniblockNumber,
+};
+
+use crate::{
+    SnapshotsApplier, SnapshotsApplierConfig, SnapshotsApplierMainNodeClient,
+    SnapshotsApplierOutcome,
+};
+
+/// Object planned to be fetched from the object store during recovery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedObject {
+    pub bucket: Bucket,
+    pub key: String,
+}
+
+/// Storage logs chunk planned to be fetched and applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChunk {
+    pub chunk_id: u64,
+    pub object: PlannedObject,
+}
+
+/// Plan of snapshot recovery produced by [`SnapshotsApplierConfig::plan()`]. Lists objects that will be fetched
+/// (in the order of scheduling) and chunks that will be skipped because they are already applied.
+///
+/// Object sizes are not included since not all object stores can report them. Chunks are applied
+/// concurrently, so the actual fetch order may differ from the scheduling order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryPlan {
+    pub l1_batch_number: L1BatchNumber,
+    pub miniblock_number: MiniblockNumber,
+    /// `true` if recovery will be started from scratch, `false` if it will be resumed.
+    pub is_fresh: bool,
+    /// Factory dependency objects (several if factory deps are sharded); empty if factory deps are already applied
+    /// or are not applied per config.
+    pub factory_deps: Vec<PlannedObject>,
+    /// Storage log chunks to be fetched and applied.
+    pub chunks_to_apply: Vec<PlannedChunk>,
+    /// IDs of storage log chunks that are already applied and will be skipped.
+    pub skipped_chunk_ids: Vec<u64>,
+}
+
+impl fmt::Display for RecoveryPlan {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.is_fresh {
+            "Recover"
+        } else {
+            "Resume recovery"
+        };
+        writeln!(
+            formatter,
+            "{verb} from snapshot for L1 batch #{} (miniblock #{})",
+            self.l1_batch_number, self.miniblock_number
+        )?;
+        for object in &self.factory_deps {
+            writeln!(
+                formatter,
+                "Fetch factory deps: {}/{}",
+                object.bucket, object.key
+            )?;
+        }
+        for chunk in &self.chunks_to_apply {
+            writeln!(
+                formatter,
+                "Fetch storage logs chunk {}: {}/{}",
+                chunk.chunk_id, chunk.object.bucket, chunk.object.key
+            )?;
+        }
+        write!(
+            formatter,
+            "Skip {} already applied storage logs chunk(s)",
+            self.skipped_chunk_ids.len()
+        )
+    }
+}
+
+impl SnapshotsApplierConfig {
+    /// Plans snapshot recovery without executing it. Neither Postgres nor the object store are modified.
+    ///
+    /// Returns `Ok(None)` if there is nothing to recover, i.e., the node was initialized from genesis,
+    /// or the main node has no snapshots.
+    ///
+    /// # Errors
+    ///
+    /// Propagates DB and main node client errors.
+    pub async fn plan(
+        &self,
+        connection_pool: &ConnectionPool,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+    ) -> anyhow::Result<Option<RecoveryPlan>> {
+        let mut storage = connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let applied_status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .context("failed fetching applied snapshot status from DB")?;
+        if applied_status.is_none() {
+            let is_genesis_needed = storage
+                .blocks_dal()
+                .is_genesis_needed()
+                .await
+                .context("failed checking genesis L1 batch in DB")?;
+            if !is_genesis_needed {
+                return Ok(None);
+            }
+        }
+        drop(storage);
+
+        let header = main_node_client
+            .fetch_newest_snapshot()
+            .await
+            .context("failed fetching newest snapshot from main node")?;
+        let (l1_batch_number, miniblock_number, chunks_processed) = match (&applied_status, &header)
+        {
+            (Some(status), _) => (
+                status.l1_batch_number,
+                status.miniblock_number,
+                status.storage_logs_chunks_processed.clone(),
+            ),
+            (None, Some(header)) => (
+                header.l1_batch_number,
+                header.miniblock_number,
+                vec![false; header.storage_logs_chunks.len()],
+            ),
+            (None, None) => return Ok(None),
+        };
+        let header = header.filter(|header| header.l1_batch_number == l1_batch_number);
+        let (path_template, content_addressed_chunks) =
+            SnapshotsApplier::storage_logs_chunk_keys(header.as_ref())?;
+
+        let is_fresh = applied_status.is_none();
+        let factory_deps = match &header {
+            Some(header) if is_fresh && self.components.factory_deps => {
+                Self::plan_factory_deps(header)
+            }
+            _ => vec![],
+        };
+        let mut chunks_to_apply = vec![];
+        let mut skipped_chunk_ids = vec![];
+        for (chunk_id, &is_processed) in chunks_processed.iter().enumerate() {
+            let chunk_id = chunk_id as u64;
+            if is_processed {
+                skipped_chunk_ids.push(chunk_id);
+                continue;
+            }
+            let key = if let Some(chunk) = content_addressed_chunks.get(&chunk_id) {
+                chunk.key.clone()
+            } else if let Some(template) = &path_template {
+                template.render(l1_batch_number, chunk_id)
+            } else {
+                SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+                    l1_batch_number,
+                    chunk_id,
+                })
+            };
+            chunks_to_apply.push(PlannedChunk {
+                chunk_id,
+                object: PlannedObject {
+                    bucket: SnapshotStorageLogsChunk::BUCKET,
+                    key,
+                },
+            });
+        }
+
+        Ok(Some(RecoveryPlan {
+            l1_batch_number,
+            miniblock_number,
+            is_fresh,
+            factory_deps,
+            chunks_to_apply,
+            skipped_chunk_ids,
+        }))
+    }
+
+    fn plan_factory_deps(header: &SnapshotHeader) -> Vec<PlannedObject> {
+        if header.factory_deps_shards.is_empty() {
+            vec![PlannedObject {
+                bucket: SnapshotFactoryDependencies::BUCKET,
+                key: SnapshotFactoryDependencies::encode_key(header.l1_batch_number),
+            }]
+        } else {
+            header
+                .factory_deps_shards
+                .iter()
+                .map(|key| PlannedObject {
+                    bucket: SnapshotFactoryDependencies::BUCKET,
+                    key: key.clone(),
+                })
+                .collect()
+        }
+    }
+
+    /// Executes a previously produced recovery `plan`. Before executing, checks that the plan is still up to date,
+    /// i.e., it's equal to the plan for the current node state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plan is outdated, or if recovery fails.
+    pub async fn execute(
+        self,
+        plan: &RecoveryPlan,
+        connection_pool: &ConnectionPool,
+        main_node_client: &dyn SnapshotsApplierMainNodeClient,
+        blob_store: &dyn ObjectStore,
+    ) -> anyhow::Result<SnapshotsApplierOutcome> {
+        let current_plan = self.plan(connection_pool, main_node_client).await?;
+        anyhow::ensure!(
+            current_plan.as_ref() == Some(plan),
+            "recovery plan is outdated; the current plan is {current_plan:?}"
+        );
+        self.run(connection_pool, main_node_client, blob_store)
+            .await
+    }
+}
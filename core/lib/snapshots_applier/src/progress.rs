@@ -0,0 +1,74 @@
+//! HTTP endpoint exposing snapshot recovery progress.
+
+use std::net::SocketAddr;
+
+use anyhow::Context as _;
+use axum::{extract::State, routing::get, Json, Router};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::{
+    health::SnapshotsApplierHealthDetails, SnapshotsApplierDebugHandle, SnapshotsApplierDebugState,
+    SnapshotsApplierHealthCheck,
+};
+
+/// Response of the `/recovery/progress` endpoint.
+#[derive(Debug, Serialize)]
+struct RecoveryProgress {
+    #[serde(flatten)]
+    details: SnapshotsApplierHealthDetails,
+    debug: SnapshotsApplierDebugState,
+}
+
+type ProgressState = (SnapshotsApplierHealthCheck, SnapshotsApplierDebugHandle);
+
+async fn get_progress(
+    State((health_check, debug_handle)): State<ProgressState>,
+) -> Json<RecoveryProgress> {
+    Json(RecoveryProgress {
+        details: health_check.details(),
+        debug: debug_handle.debug_state(),
+    })
+}
+
+/// HTTP server serving recovery progress on `GET /recovery/progress`. The server is shut down once
+/// this guard is dropped.
+#[derive(Debug)]
+pub(crate) struct ProgressServerGuard {
+    _stop_sender: watch::Sender<()>,
+}
+
+impl ProgressServerGuard {
+    /// Binds the server to the specified address and spawns it as a Tokio task.
+    pub(crate) fn spawn(
+        bind_address: SocketAddr,
+        health_check: SnapshotsApplierHealthCheck,
+        debug_handle: SnapshotsApplierDebugHandle,
+    ) -> anyhow::Result<Self> {
+        let app = Router::new()
+            .route("/recovery/progress", get(get_progress))
+            .with_state((health_check, debug_handle));
+        let server = axum::Server::try_bind(&bind_address)
+            .with_context(|| format!("failed binding recovery progress server to {bind_address}"))?
+            .serve(app.into_make_service());
+        tracing::info!(
+            "Serving recovery progress on http://{}/recovery/progress",
+            server.local_addr()
+        );
+
+        let (stop_sender, mut stop_receiver) = watch::channel(());
+        let server = server.with_graceful_shutdown(async move {
+            // The sender is never used to send values, so this only completes once the guard is dropped.
+            stop_receiver.changed().await.ok();
+        });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                tracing::warn!("Recovery progress server failed: {err}");
+            }
+            tracing::info!("Recovery progress server shut down");
+        });
+        Ok(Self {
+            _stop_sender: stop_sender,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+//! Verification of Merkle proofs for storage logs included into snapshots.
+
+use anyhow::Context as _;
+use zksync_crypto::hasher::blake2::Blake2Hasher;
+use zksync_merkle_tree::{TreeEntry, TreeEntryWithProof};
+use zksync_types::{snapshots::SnapshotStorageLogsChunk, H256};
+
+/// Verifies all Merkle proofs included into a storage logs chunk against the specified trusted root hash.
+pub(crate) fn verify_storage_log_proofs(
+    chunk_id: u64,
+    chunk: &SnapshotStorageLogsChunk,
+    trusted_root_hash: H256,
+) -> anyhow::Result<()> {
+    for proof in &chunk.proofs {
+        let log_index = proof.storage_log_index;
+        let log = usize::try_from(log_index)
+            .ok()
+            .and_then(|idx| chunk.storage_logs.get(idx));
+        let Some(log) = log else {
+            anyhow::bail!(
+                "Merkle proof in storage logs chunk {chunk_id} references storage log #{log_index}, while the chunk \
+                 contains {} logs",
+                chunk.storage_logs.len()
+            );
+        };
+
+        let entry = TreeEntry::new(log.key.hashed_key_u256(), log.enumeration_index, log.value);
+        let entry = TreeEntryWithProof {
+            base: entry,
+            merkle_path: proof.merkle_path.clone(),
+        };
+        entry
+            .try_verify(&Blake2Hasher, trusted_root_hash)
+            .with_context(|| {
+                format!(
+                    "Merkle proof for storage log #{log_index} (key {:?}) in chunk {chunk_id} is invalid",
+                    log.key
+                )
+            })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Gradual ramp-up of storage log chunk processing concurrency.
+
+use std::sync::Mutex;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Concurrency limiter that starts with processing a single chunk at a time and linearly increases concurrency
+/// with each processed chunk, reaching the maximum concurrency after `ramp_chunks` processed chunks.
+/// This allows warming up caches and the connection pool before applying full load.
+#[derive(Debug)]
+pub(crate) struct ConcurrencyRamp {
+    semaphore: Semaphore,
+    max_concurrency: usize,
+    ramp_chunks: usize,
+    /// Number of processed chunks and the current concurrency limit.
+    state: Mutex<(usize, usize)>,
+}
+
+impl ConcurrencyRamp {
+    pub fn new(max_concurrency: usize, ramp_chunks: usize) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        let initial_limit = Self::limit(max_concurrency, ramp_chunks, 0);
+        Self {
+            semaphore: Semaphore::new(initial_limit),
+            max_concurrency,
+            ramp_chunks,
+            state: Mutex::new((0, initial_limit)),
+        }
+    }
+
+    /// Returns the concurrency limit after the specified number of processed chunks.
+    pub fn limit(max_concurrency: usize, ramp_chunks: usize, processed_chunks: usize) -> usize {
+        if ramp_chunks == 0 || processed_chunks >= ramp_chunks {
+            return max_concurrency;
+        }
+        1 + processed_chunks * (max_concurrency - 1) / ramp_chunks
+    }
+
+    pub fn current_limit(&self) -> usize {
+        self.state.lock().unwrap().1
+    }
+
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        // `unwrap()` is safe: the semaphore is never closed
+        self.semaphore.acquire().await.unwrap()
+    }
+
+    /// Records a processed chunk, increasing concurrency if necessary.
+    pub fn chunk_processed(&self) {
+        let mut state = self.state.lock().unwrap();
+        let (processed_chunks, current_limit) = &mut *state;
+        *processed_chunks += 1;
+        let new_limit = Self::limit(self.max_concurrency, self.ramp_chunks, *processed_chunks);
+        if new_limit > *current_limit {
+            self.semaphore.add_permits(new_limit - *current_limit);
+            tracing::debug!("Increased chunk processing concurrency to {new_limit}");
+            *current_limit = new_limit;
+        }
+    }
+}
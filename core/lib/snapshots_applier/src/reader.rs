@@ -0,0 +1,277 @@
+//! Reading snapshot data from an object store without applying it.
+
+use std::{collections::HashSet, fmt};
+
+use anyhow::Context as _;
+use zksync_dal::StorageProcessor;
+use zksync_object_store::{ObjectStore, ObjectStoreError};
+use zksync_types::{
+    snapshots::{
+        SnapshotFactoryDependencies, SnapshotHeader, SnapshotStorageLogsChunk,
+        SnapshotStorageLogsStorageKey,
+    },
+    Address, L1BatchNumber, MiniblockNumber, StorageValue, H256,
+};
+
+/// Reader of snapshot data described by a [`SnapshotHeader`] from an object store.
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+    blob_store: &'a dyn ObjectStore,
+    header: &'a SnapshotHeader,
+}
+
+impl<'a> SnapshotReader<'a> {
+    /// Creates a reader for the snapshot with the specified header.
+    pub fn new(blob_store: &'a dyn ObjectStore, header: &'a SnapshotHeader) -> Self {
+        Self { blob_store, header }
+    }
+
+    /// Returns the header of the read snapshot.
+    pub fn header(&self) -> &'a SnapshotHeader {
+        self.header
+    }
+
+    /// Fetches factory dependencies for the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Propagates object store errors.
+    pub async fn factory_deps(&self) -> Result<SnapshotFactoryDependencies, ObjectStoreError> {
+        self.blob_store.get(self.header.l1_batch_number).await
+    }
+
+    /// Fetches a storage logs chunk with the specified ID.
+    ///
+    /// # Errors
+    ///
+    /// Propagates object store errors.
+    pub async fn storage_logs_chunk(
+        &self,
+        chunk_id: u64,
+    ) -> Result<SnapshotStorageLogsChunk, ObjectStoreError> {
+        let storage_key = SnapshotStorageLogsStorageKey {
+            l1_batch_number: self.header.l1_batch_number,
+            chunk_id,
+        };
+        self.blob_store.get(storage_key).await
+    }
+
+    /// Summarizes snapshot contents. Storage log chunks are processed one by one, so that at most one chunk
+    /// is held in memory at a time.
+    ///
+    /// # Errors
+    ///
+    /// Propagates object store errors.
+    pub async fn summary(&self) -> anyhow::Result<SnapshotContentsSummary> {
+        let factory_deps = self
+            .factory_deps()
+            .await
+            .context("failed fetching factory deps")?;
+        let factory_deps_bytecode_size = factory_deps
+            .factory_deps
+            .iter()
+            .map(|dep| dep.bytecode.0.len() as u64)
+            .sum();
+        let factory_dep_count = factory_deps.factory_deps.len();
+        drop(factory_deps);
+
+        let mut storage_log_count = 0;
+        let mut accounts = HashSet::<Address>::new();
+        for chunk in &self.header.storage_logs_chunks {
+            let chunk_id = chunk.chunk_id;
+            let chunk = self
+                .storage_logs_chunk(chunk_id)
+                .await
+                .with_context(|| format!("failed fetching storage logs chunk {chunk_id}"))?;
+            storage_log_count += chunk.storage_logs.len() as u64;
+            accounts.extend(chunk.storage_logs.iter().map(|log| *log.key.address()));
+        }
+
+        Ok(SnapshotContentsSummary {
+            l1_batch_number: self.header.l1_batch_number,
+            miniblock_number: self.header.miniblock_number,
+            storage_logs_chunk_count: self.header.storage_logs_chunks.len(),
+            storage_log_count,
+            unique_account_count: accounts.len(),
+            factory_dep_count,
+            factory_deps_bytecode_size,
+        })
+    }
+
+    /// Compares storage logs in the snapshot with the current state of the node storage (i.e., the latest
+    /// values of storage keys in Postgres) and classifies their relationship. Storage log chunks are processed
+    /// one by one, so that at most one chunk is held in memory at a time. At most `max_reported_conflicts`
+    /// conflicting keys are reported in [`StateComparison::conflicts`]; all conflicts are counted regardless.
+    ///
+    /// # Errors
+    ///
+    /// Propagates object store and DB errors.
+    pub async fn compare_with_state(
+        &self,
+        storage: &mut StorageProcessor<'_>,
+        max_reported_conflicts: usize,
+    ) -> anyhow::Result<StateComparison> {
+        let mut matching_key_count = 0;
+        let mut snapshot_only_key_count = 0;
+        let mut conflicting_key_count = 0;
+        let mut conflicts = vec![];
+        for chunk in &self.header.storage_logs_chunks {
+            let chunk_id = chunk.chunk_id;
+            let chunk = self
+                .storage_logs_chunk(chunk_id)
+                .await
+                .with_context(|| format!("failed fetching storage logs chunk {chunk_id}"))?;
+            let hashed_keys: Vec<_> = chunk
+                .storage_logs
+                .iter()
+                .map(|log| log.key.hashed_key())
+                .collect();
+            let state_values = storage
+                .storage_logs_dal()
+                .get_storage_values(&hashed_keys, MiniblockNumber(u32::MAX))
+                .await
+                .with_context(|| {
+                    format!("failed fetching state values for storage logs chunk {chunk_id}")
+                })?;
+
+            for (log, hashed_key) in chunk.storage_logs.iter().zip(hashed_keys) {
+                match state_values.get(&hashed_key).copied().flatten() {
+                    None => snapshot_only_key_count += 1,
+                    Some(state_value) if state_value == log.value => matching_key_count += 1,
+                    Some(state_value) => {
+                        conflicting_key_count += 1;
+                        if conflicts.len() < max_reported_conflicts {
+                            conflicts.push(StateConflict {
+                                hashed_key,
+                                snapshot_value: log.value,
+                                state_value,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let state_key_count = storage
+            .storage_logs_dal()
+            .count_unique_storage_keys()
+            .await
+            .context("failed counting storage keys in the state")?;
+        let state_only_key_count =
+            state_key_count.saturating_sub(matching_key_count + conflicting_key_count);
+        let relation = StateRelation::new(
+            matching_key_count,
+            conflicting_key_count,
+            snapshot_only_key_count,
+            state_only_key_count,
+        );
+        Ok(StateComparison {
+            relation,
+            matching_key_count,
+            conflicting_key_count,
+            snapshot_only_key_count,
+            state_only_key_count,
+            conflicts,
+        })
+    }
+}
+
+/// Relationship between storage logs in a snapshot and the node state determined by
+/// [`SnapshotReader::compare_with_state()`]. Storage keys are compared together with their values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateRelation {
+    /// The snapshot and the state contain the same keys with the same values.
+    Equal,
+    /// The snapshot contains all keys from the state with the same values, and some additional keys.
+    Superset,
+    /// The state contains all keys from the snapshot with the same values, and some additional keys.
+    Subset,
+    /// The snapshot and the state have no keys in common.
+    Disjoint,
+    /// The snapshot and the state have common keys with the same values, and each of them has keys missing
+    /// from the other one.
+    Overlapping,
+    /// Some keys have different values in the snapshot and the state.
+    OverlappingWithConflicts,
+}
+
+impl StateRelation {
+    fn new(
+        matching_key_count: u64,
+        conflicting_key_count: u64,
+        snapshot_only_key_count: u64,
+        state_only_key_count: u64,
+    ) -> Self {
+        if conflicting_key_count > 0 {
+            Self::OverlappingWithConflicts
+        } else if snapshot_only_key_count == 0 && state_only_key_count == 0 {
+            Self::Equal
+        } else if snapshot_only_key_count == 0 {
+            Self::Subset
+        } else if state_only_key_count == 0 {
+            Self::Superset
+        } else if matching_key_count == 0 {
+            Self::Disjoint
+        } else {
+            Self::Overlapping
+        }
+    }
+}
+
+/// Storage key with different values in the snapshot and the node state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateConflict {
+    pub hashed_key: H256,
+    pub snapshot_value: StorageValue,
+    pub state_value: StorageValue,
+}
+
+/// Result of comparing a snapshot with the node state returned by [`SnapshotReader::compare_with_state()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateComparison {
+    pub relation: StateRelation,
+    /// Number of keys with the same values in the snapshot and the state.
+    pub matching_key_count: u64,
+    /// Number of keys with different values in the snapshot and the state.
+    pub conflicting_key_count: u64,
+    /// Number of snapshot keys missing from the state.
+    pub snapshot_only_key_count: u64,
+    /// Number of state keys missing from the snapshot.
+    pub state_only_key_count: u64,
+    /// Conflicting keys; may be truncated (see [`SnapshotReader::compare_with_state()`]).
+    pub conflicts: Vec<StateConflict>,
+}
+
+/// Summary of snapshot contents returned by [`SnapshotReader::summary()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotContentsSummary {
+    pub l1_batch_number: L1BatchNumber,
+    pub miniblock_number: MiniblockNumber,
+    pub storage_logs_chunk_count: usize,
+    pub storage_log_count: u64,
+    pub unique_account_count: usize,
+    pub factory_dep_count: usize,
+    /// Total size of factory dependency bytecodes in bytes.
+    pub factory_deps_bytecode_size: u64,
+}
+
+impl fmt::Display for SnapshotContentsSummary {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            formatter,
+            "Snapshot for L1 batch #{} (miniblock #{})",
+            self.l1_batch_number, self.miniblock_number
+        )?;
+        writeln!(
+            formatter,
+            "Storage logs: {} in {} chunk(s)",
+            self.storage_log_count, self.storage_logs_chunk_count
+        )?;
+        writeln!(formatter, "Unique accounts: {}", self.unique_account_count)?;
+        write!(
+            formatter,
+            "Factory deps: {} with total bytecode size {} bytes",
+            self.factory_dep_count, self.factory_deps_bytecode_size
+        )
+    }
+}
@@ -0,0 +1,458 @@
+//! Recording and deterministic replay of object store and main node responses received during recovery.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError};
+use zksync_types::{
+    api::en::SyncBlock, snapshots::SnapshotHeader, tokens::TokenInfo, Bytes, L1BatchNumber,
+    MiniblockNumber, StorageKey, StorageValue, H256,
+};
+use zksync_web3_decl::jsonrpsee::core::ClientError as RpcError;
+
+use crate::SnapshotsApplierMainNodeClient;
+
+/// Kind of an [`ObjectStoreError`] recorded in a [`RecoveryTrace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracedObjectStoreErrorKind {
+    KeyNotFound,
+    Serialization,
+    Other,
+    Unsupported,
+}
+
+/// Object store response recorded in a [`RecoveryTrace`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracedObjectStoreResponse {
+    /// Object contents returned by [`ObjectStore::get_raw()`].
+    Object(Bytes),
+    /// Object size returned by [`ObjectStore::get_size_raw()`].
+    Size(u64),
+    Error {
+        kind: TracedObjectStoreErrorKind,
+        message: String,
+    },
+}
+
+impl TracedObjectStoreResponse {
+    fn error(err: &ObjectStoreError) -> Self {
+        let (kind, message) = match err {
+            ObjectStoreError::KeyNotFound(err) => (TracedObjectStoreErrorKind::KeyNotFound, err),
+            ObjectStoreError::Serialization(err) => {
+                (TracedObjectStoreErrorKind::Serialization, err)
+            }
+            ObjectStoreError::Other(err) => (TracedObjectStoreErrorKind::Other, err),
+            ObjectStoreError::Unsupported(err) => (TracedObjectStoreErrorKind::Unsupported, err),
+        };
+        Self::Error {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    fn to_error(kind: TracedObjectStoreErrorKind, message: &str) -> ObjectStoreError {
+        let err = message.to_owned().into();
+        match kind {
+            TracedObjectStoreErrorKind::KeyNotFound => ObjectStoreError::KeyNotFound(err),
+            TracedObjectStoreErrorKind::Serialization => ObjectStoreError::Serialization(err),
+            TracedObjectStoreErrorKind::Other => ObjectStoreError::Other(err),
+            TracedObjectStoreErrorKind::Unsupported => ObjectStoreError::Unsupported(err),
+        }
+    }
+}
+
+/// Main node response recorded in a [`RecoveryTrace`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracedRpcResponse {
+    Ok(serde_json::Value),
+    Error {
+        message: String,
+        /// Whether the error is transient (i.e., recovery is retried after it).
+        is_transient: bool,
+    },
+}
+
+/// Trace of responses received by the applier from the object store and the main node, allowing to replay
+/// recovery deterministically without network access (e.g., to debug a recovery failure in production).
+///
+/// Responses are keyed by the request (e.g., `get storage_logs_snapshots/<key>` or `fetch_newest_snapshot()`).
+/// Responses for the same request are recorded in the order they were received, so that retries are replayed
+/// faithfully. A trace is recorded using [`RecoveryTraceRecorder`] and replayed using [`RecoveryTraceReplayer`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryTrace {
+    pub object_store: BTreeMap<String, Vec<TracedObjectStoreResponse>>,
+    /// Object store prefixes keyed by the bucket.
+    pub storage_prefixes: BTreeMap<String, String>,
+    pub main_node: BTreeMap<String, Vec<TracedRpcResponse>>,
+}
+
+impl RecoveryTrace {
+    /// Reads a trace from the specified JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O and deserialization errors.
+    pub fn read_from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read(path)
+            .with_context(|| format!("failed reading recovery trace from `{}`", path.display()))?;
+        serde_json::from_slice(&contents).with_context(|| {
+            format!(
+                "failed deserializing recovery trace from `{}`",
+                path.display()
+            )
+        })
+    }
+
+    /// Writes the trace to the specified file as JSON, overwriting the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O errors.
+    pub fn write_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_vec(self).context("failed serializing recovery trace")?;
+        fs::write(path, contents)
+            .with_context(|| format!("failed writing recovery trace to `{}`", path.display()))
+    }
+}
+
+fn object_request(method: &str, bucket: Bucket, key: &str) -> String {
+    format!("{method} {bucket}/{key}")
+}
+
+fn storage_value_request(key: &StorageKey, miniblock_number: MiniblockNumber) -> String {
+    format!(
+        "fetch_storage_value_at({:?}, {miniblock_number})",
+        key.hashed_key()
+    )
+}
+
+/// Wrapper around an object store and a main node client recording all their responses into a [`RecoveryTrace`].
+/// Should be passed to the applier both as the object store and the main node client.
+pub struct RecoveryTraceRecorder<C> {
+    client: C,
+    object_store: Arc<dyn ObjectStore>,
+    trace: Mutex<RecoveryTrace>,
+}
+
+impl<C: fmt::Debug> fmt::Debug for RecoveryTraceRecorder<C> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("RecoveryTraceRecorder")
+            .field("client", &self.client)
+            .field("object_store", &self.object_store)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: SnapshotsApplierMainNodeClient> RecoveryTraceRecorder<C> {
+    /// Creates a recorder wrapping the specified main node client and object store.
+    pub fn new(client: C, object_store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            client,
+            object_store,
+            trace: Mutex::default(),
+        }
+    }
+
+    /// Returns the trace recorded so far.
+    pub fn trace(&self) -> RecoveryTrace {
+        self.trace.lock().unwrap().clone()
+    }
+
+    fn record_object_response(&self, request: String, response: TracedObjectStoreResponse) {
+        let mut trace = self.trace.lock().unwrap();
+        trace
+            .object_store
+            .entry(request)
+            .or_default()
+            .push(response);
+    }
+
+    fn record_rpc_response<T: Serialize>(&self, request: String, response: &Result<T, RpcError>) {
+        let response = match response {
+            Ok(value) => match serde_json::to_value(value) {
+                Ok(value) => TracedRpcResponse::Ok(value),
+                Err(err) => {
+                    tracing::warn!("Failed serializing response to `{request}` for trace: {err}");
+                    return;
+                }
+            },
+            Err(err) => TracedRpcResponse::Error {
+                message: err.to_string(),
+                is_transient: matches!(
+                    err,
+                    RpcError::Transport(_) | RpcError::RequestTimeout | RpcError::RestartNeeded(_)
+                ),
+            },
+        };
+        let mut trace = self.trace.lock().unwrap();
+        trace.main_node.entry(request).or_default().push(response);
+    }
+}
+
+#[async_trait]
+impl<C: SnapshotsApplierMainNodeClient> SnapshotsApplierMainNodeClient
+    for RecoveryTraceRecorder<C>
+{
+    async fn fetch_l2_block(&self, number: MiniblockNumber) -> Result<Option<SyncBlock>, RpcError> {
+        let response = self.client.fetch_l2_block(number).await;
+        self.record_rpc_response(format!("fetch_l2_block({number})"), &response);
+        response
+    }
+
+    async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError> {
+        let response = self.client.fetch_newest_snapshot().await;
+        self.record_rpc_response("fetch_newest_snapshot()".to_owned(), &response);
+        response
+    }
+
+    async fn fetch_tokens(
+        &self,
+        at_miniblock: MiniblockNumber,
+    ) -> Result<Vec<TokenInfo>, RpcError> {
+        let response = self.client.fetch_tokens(at_miniblock).await;
+        self.record_rpc_response(format!("fetch_tokens({at_miniblock})"), &response);
+        response
+    }
+
+    async fn fetch_storage_value_at(
+        &self,
+        key: &StorageKey,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<StorageValue, RpcError> {
+        let response = self
+            .client
+            .fetch_storage_value_at(key, miniblock_number)
+            .await;
+        self.record_rpc_response(storage_value_request(key, miniblock_number), &response);
+        response
+    }
+
+    async fn fetch_l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> Result<Option<H256>, RpcError> {
+        let response = self.client.fetch_l1_batch_root_hash(number).await;
+        self.record_rpc_response(format!("fetch_l1_batch_root_hash({number})"), &response);
+        response
+    }
+}
+
+#[async_trait]
+impl<C: SnapshotsApplierMainNodeClient + 'static> ObjectStore for RecoveryTraceRecorder<C> {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let response = self.object_store.get_raw(bucket, key).await;
+        let traced_response = match &response {
+            Ok(bytes) => TracedObjectStoreResponse::Object(Bytes(bytes.clone())),
+            Err(err) => TracedObjectStoreResponse::error(err),
+        };
+        self.record_object_response(object_request("get", bucket, key), traced_response);
+        response
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        self.object_store.put_raw(bucket, key, value).await
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        self.object_store.remove_raw(bucket, key).await
+    }
+
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let response = self.object_store.get_size_raw(bucket, key).await;
+        let traced_response = match &response {
+            Ok(size) => TracedObjectStoreResponse::Size(*size),
+            Err(err) => TracedObjectStoreResponse::error(err),
+        };
+        self.record_object_response(object_request("size", bucket, key), traced_response);
+        response
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        let prefix = self.object_store.storage_prefix_raw(bucket);
+        let mut trace = self.trace.lock().unwrap();
+        trace
+            .storage_prefixes
+            .insert(bucket.to_string(), prefix.clone());
+        prefix
+    }
+}
+
+/// Object store and main node client replaying responses from a [`RecoveryTrace`]. Should be passed
+/// to the applier both as the object store and the main node client.
+///
+/// If a request is not present in the trace (i.e., replayed recovery diverges from the recorded one), a fatal error
+/// is returned. If a request is made more times than it was recorded, the last recorded response is returned.
+/// The replayer is read-only; writes to the object store are not supported.
+pub struct RecoveryTraceReplayer {
+    trace: RecoveryTrace,
+    /// Number of replayed responses keyed by the request.
+    cursors: Mutex<HashMap<String, usize>>,
+}
+
+impl fmt::Debug for RecoveryTraceReplayer {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("RecoveryTraceReplayer")
+            .field("object_requests", &self.trace.object_store.len())
+            .field("main_node_requests", &self.trace.main_node.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecoveryTraceReplayer {
+    /// Creates a replayer for the specified trace.
+    pub fn new(trace: RecoveryTrace) -> Self {
+        Self {
+            trace,
+            cursors: Mutex::default(),
+        }
+    }
+
+    fn next_response<'a, R>(
+        &'a self,
+        responses: &'a BTreeMap<String, Vec<R>>,
+        request: &str,
+    ) -> Option<&'a R> {
+        let responses = responses.get(request)?;
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(request.to_owned()).or_default();
+        let response = responses.get(*cursor).or_else(|| responses.last());
+        *cursor += 1;
+        response
+    }
+
+    fn replay_object_response(
+        &self,
+        request: &str,
+    ) -> Result<&TracedObjectStoreResponse, ObjectStoreError> {
+        self.next_response(&self.trace.object_store, request)
+            .ok_or_else(|| {
+                let err = format!("request `{request}` is not present in the recovery trace");
+                ObjectStoreError::Unsupported(err.into())
+            })
+    }
+
+    fn replay_rpc_response<T: DeserializeOwned>(&self, request: &str) -> Result<T, RpcError> {
+        let response = self
+            .next_response(&self.trace.main_node, request)
+            .ok_or_else(|| {
+                RpcError::Custom(format!(
+                    "request `{request}` is not present in the recovery trace"
+                ))
+            })?;
+        match response {
+            TracedRpcResponse::Ok(value) => T::deserialize(value).map_err(|err| {
+                RpcError::Custom(format!(
+                    "failed deserializing traced response to `{request}`: {err}"
+                ))
+            }),
+            TracedRpcResponse::Error {
+                is_transient: true, ..
+            } => Err(RpcError::RequestTimeout),
+            TracedRpcResponse::Error { message, .. } => Err(RpcError::Custom(message.clone())),
+        }
+    }
+}
+
+#[async_trait]
+impl SnapshotsApplierMainNodeClient for RecoveryTraceReplayer {
+    async fn fetch_l2_block(&self, number: MiniblockNumber) -> Result<Option<SyncBlock>, RpcError> {
+        self.replay_rpc_response(&format!("fetch_l2_block({number})"))
+    }
+
+    async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError> {
+        self.replay_rpc_response("fetch_newest_snapshot()")
+    }
+
+    async fn fetch_tokens(
+        &self,
+        at_miniblock: MiniblockNumber,
+    ) -> Result<Vec<TokenInfo>, RpcError> {
+        self.replay_rpc_response(&format!("fetch_tokens({at_miniblock})"))
+    }
+
+    async fn fetch_storage_value_at(
+        &self,
+        key: &StorageKey,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<StorageValue, RpcError> {
+        self.replay_rpc_response(&storage_value_request(key, miniblock_number))
+    }
+
+    async fn fetch_l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> Result<Option<H256>, RpcError> {
+        self.replay_rpc_response(&format!("fetch_l1_batch_root_hash({number})"))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RecoveryTraceReplayer {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        match self.replay_object_response(&object_request("get", bucket, key))? {
+            TracedObjectStoreResponse::Object(bytes) => Ok(bytes.0.clone()),
+            TracedObjectStoreResponse::Error { kind, message } => {
+                Err(TracedObjectStoreResponse::to_error(*kind, message))
+            }
+            TracedObjectStoreResponse::Size(_) => {
+                let err = format!("unexpected traced response for `{bucket}/{key}`");
+                Err(ObjectStoreError::Unsupported(err.into()))
+            }
+        }
+    }
+
+    async fn put_raw(
+        &self,
+        bucket: Bucket,
+        key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot write `{bucket}/{key}`: recovery trace replayer is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn remove_raw(&self, bucket: Bucket, key: &str) -> Result<(), ObjectStoreError> {
+        let err = format!("cannot remove `{bucket}/{key}`: recovery trace replayer is read-only");
+        Err(ObjectStoreError::Unsupported(err.into()))
+    }
+
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        match self.replay_object_response(&object_request("size", bucket, key))? {
+            TracedObjectStoreResponse::Size(size) => Ok(*size),
+            TracedObjectStoreResponse::Error { kind, message } => {
+                Err(TracedObjectStoreResponse::to_error(*kind, message))
+            }
+            TracedObjectStoreResponse::Object(_) => {
+                let err = format!("unexpected traced response for `{bucket}/{key}`");
+                Err(ObjectStoreError::Unsupported(err.into()))
+            }
+        }
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        let bucket = bucket.to_string();
+        self.trace
+            .storage_prefixes
+            .get(&bucket)
+            .cloned()
+            .unwrap_or(bucket)
+    }
+}
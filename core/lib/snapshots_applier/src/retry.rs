@@ -0,0 +1,205 @@
+//! Retries for individual object store requests, and the retry budget shared across all retries.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError, StoredObject};
+
+#[cfg(feature = "chaos")]
+use crate::FailureInjection;
+use crate::{throttle, BackoffStrategy, SnapshotsApplierConfig, SnapshotsApplierDebugHandle};
+
+/// Budget limiting the aggregate number of retries (both for individual object store requests and for the entire
+/// recovery) during a single [`SnapshotsApplierConfig::run()`]. The budget is a token bucket: each retry takes
+/// a token, and once the bucket is empty, transient errors are not retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudget {
+    /// Maximum number of tokens in the bucket. The bucket is initially full.
+    pub capacity: usize,
+    /// Interval after which a token is added to the bucket (up to [`Self::capacity`]). If not set,
+    /// the bucket is never refilled, i.e., the capacity is the total number of retries.
+    pub refill_interval: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct TokenBucketState {
+    tokens: usize,
+    last_refill: Instant,
+}
+
+/// Runtime state of a [`RetryBudget`].
+#[derive(Debug)]
+pub(crate) struct RetryTokenBucket {
+    budget: RetryBudget,
+    state: Mutex<TokenBucketState>,
+}
+
+impl RetryTokenBucket {
+    pub fn new(budget: RetryBudget) -> Self {
+        Self {
+            budget,
+            state: Mutex::new(TokenBucketState {
+                tokens: budget.capacity,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token for a retry. Returns `false` if the budget is exhausted.
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if let Some(refill_interval) = self.budget.refill_interval {
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.last_refill);
+            let refilled_tokens = elapsed.as_nanos() / refill_interval.as_nanos().max(1);
+            if refilled_tokens > 0 {
+                let refilled_tokens = usize::try_from(refilled_tokens).unwrap_or(usize::MAX);
+                state.tokens = state
+                    .tokens
+                    .saturating_add(refilled_tokens)
+                    .min(self.budget.capacity);
+                state.last_refill = now;
+            }
+        }
+
+        if state.tokens == 0 {
+            return false;
+        }
+        state.tokens -= 1;
+        true
+    }
+}
+
+/// Wrapper around an [`ObjectStore`] retrying requests failing with transient errors.
+///
+/// If the recovery deadline is set, the store doesn't start a retry that would overrun it, returning
+/// the last encountered error instead. Thus, per-request retries never consume more than the time remaining
+/// for the entire recovery. Likewise, if the retry budget is set, the store doesn't retry requests
+/// once the budget is exhausted.
+///
+/// Requests throttled by the backend are retried after the delay requested by the backend, unless
+/// [`SnapshotsApplierConfig::respect_retry_after`] is disabled.
+///
+/// The store also limits the number of concurrent requests to buckets with a configured concurrency cap.
+/// A permit is held only while a request is in flight, i.e., not during backoff between retries.
+#[derive(Debug)]
+pub(crate) struct RetryingObjectStore<'a> {
+    inner: &'a dyn ObjectStore,
+    retry_count: usize,
+    backoff: Box<dyn BackoffStrategy + 'a>,
+    respect_retry_after: bool,
+    deadline: Option<Instant>,
+    retry_budget: Option<&'a RetryTokenBucket>,
+    bucket_semaphores: HashMap<Bucket, Semaphore>,
+    debug_handle: SnapshotsApplierDebugHandle,
+    #[cfg(feature = "chaos")]
+    failure_injection: FailureInjection,
+}
+
+impl<'a> RetryingObjectStore<'a> {
+    pub fn new(
+        inner: &'a dyn ObjectStore,
+        config: &'a SnapshotsApplierConfig,
+        deadline: Option<Instant>,
+    ) -> Self {
+        Self {
+            inner,
+            retry_count: config.object_store_retry_count,
+            backoff: config.object_store_retry_backoff(),
+            respect_retry_after: config.respect_retry_after,
+            deadline,
+            retry_budget: None,
+            bucket_semaphores: config
+                .throttling
+                .object_store_bucket_concurrency
+                .iter()
+                .map(|(&bucket, &limit)| (bucket, Semaphore::new(limit.max(1))))
+                .collect(),
+            debug_handle: config.debug_handle.clone(),
+            #[cfg(feature = "chaos")]
+            failure_injection: config.failure_injection,
+        }
+    }
+
+    pub fn with_retry_budget(mut self, retry_budget: Option<&'a RetryTokenBucket>) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+
+    pub async fn get<V: StoredObject>(&self, key: V::Key<'_>) -> Result<V, ObjectStoreError> {
+        let bytes = self.get_raw(V::BUCKET, &V::encode_key(key)).await?;
+        V::deserialize(bytes).map_err(ObjectStoreError::Serialization)
+    }
+
+    pub async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let mut retry_id = 0;
+        loop {
+            let err = match self.get_raw_once(bucket, key).await {
+                Ok(bytes) => {
+                    self.debug_handle.bytes_downloaded(bytes.len());
+                    return Ok(bytes);
+                }
+                Err(err) => err,
+            };
+            let is_transient = matches!(err, ObjectStoreError::Other(_));
+            if !is_transient || retry_id >= self.retry_count {
+                return Err(err);
+            }
+            let retry_after = self
+                .respect_retry_after
+                .then(|| throttle::retry_after(&err))
+                .flatten();
+            let backoff = retry_after.unwrap_or_else(|| self.backoff.retry_delay(retry_id + 1));
+            if let Some(deadline) = self.deadline {
+                if Instant::now() + backoff >= deadline {
+                    tracing::info!(
+                        "Not retrying object store request since it would overrun the recovery deadline; \
+                         last error: {err}"
+                    );
+                    return Err(err);
+                }
+            }
+            if self
+                .retry_budget
+                .is_some_and(|budget| !budget.try_acquire())
+            {
+                tracing::info!(
+                    "Not retrying object store request since the retry budget is exhausted; last error: {err}"
+                );
+                return Err(err);
+            }
+
+            retry_id += 1;
+            tracing::warn!(
+                "Transient error fetching object `{key}` from store: {err}; retry {retry_id} / {} in {backoff:?}",
+                self.retry_count
+            );
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    pub async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let _permit = self.acquire_permit(bucket).await;
+        self.inner.get_size_raw(bucket, key).await
+    }
+
+    pub fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        self.inner.storage_prefix_raw(bucket)
+    }
+
+    async fn get_raw_once(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        #[cfg(feature = "chaos")]
+        self.failure_injection.object_store_read(bucket, key)?;
+        let _permit = self.acquire_permit(bucket).await;
+        self.inner.get_raw(bucket, key).await
+    }
+
+    async fn acquire_permit(&self, bucket: Bucket) -> Option<SemaphorePermit<'_>> {
+        let semaphore = self.bucket_semaphores.get(&bucket)?;
+        // `unwrap()` is safe: the semaphore is never closed
+        Some(semaphore.acquire().await.unwrap())
+    }
+}
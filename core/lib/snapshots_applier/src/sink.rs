@@ -0,0 +1,285 @@
+//! Additional destinations for storage logs applied from a snapshot.
+
+#[cfg(feature = "rocksdb")]
+use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+#[cfg(feature = "rocksdb")]
+use tokio::sync::Mutex;
+use zksync_dal::ConnectionPool;
+#[cfg(feature = "rocksdb")]
+use zksync_state::{RocksbStorageBuilder, RocksdbStorage};
+use zksync_types::{
+    snapshots::{SnapshotRecoveryStatus, SnapshotStorageLog},
+    AccountTreeId, H256,
+};
+
+/// Additional destination for storage logs applied from a snapshot (in addition to Postgres).
+///
+/// # Delivery guarantees
+///
+/// Chunks are delivered to sinks **at least once**. A sink is written to inside the DB transaction
+/// for the chunk, before the chunk is marked as processed and the transaction is committed; this ensures that
+/// the sink contains all logs from processed chunks. As a consequence, the same chunk can be delivered
+/// several times, e.g. if:
+///
+/// - committing the DB transaction fails after the sink was written to, and the chunk is retried;
+/// - the applier is interrupted or restarted (including by another process) before the commit;
+/// - another sink configured after this one fails for the chunk.
+///
+/// Delivered chunks are not ordered by `chunk_id`, and several chunks can be delivered concurrently.
+/// Implementations must thus treat `(status.l1_batch_number, chunk_id)` as an idempotency key: writing
+/// the same chunk again must not change the sink state. Chunk contents for a given key are always the same.
+#[async_trait]
+pub trait StorageLogsSink: fmt::Debug + Send + Sync {
+    /// Writes storage logs from the specified chunk of the snapshot being recovered (as described by `status`).
+    /// May be called several times for the same chunk; see [delivery guarantees](Self#delivery-guarantees).
+    async fn write_storage_logs_chunk(
+        &self,
+        status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()>;
+}
+
+/// [`StorageLogsSink`] writing logs to the RocksDB state keeper cache. This allows a node to bootstrap
+/// the cache from the snapshot rather than from Postgres after recovery.
+#[cfg(feature = "rocksdb")]
+#[derive(Debug)]
+pub struct RocksdbStorageLogsSink {
+    storage: Mutex<RocksbStorageBuilder>,
+}
+
+#[cfg(feature = "rocksdb")]
+impl RocksdbStorageLogsSink {
+    /// Opens the RocksDB cache at the specified path.
+    ///
+    /// # Errors
+    ///
+    /// Propagates RocksDB I/O errors.
+    pub async fn new(path: &Path) -> anyhow::Result<Self> {
+        let storage = RocksdbStorage::builder(path)
+            .await
+            .context("failed opening RocksDB state keeper cache")?;
+        Ok(Self::from(storage))
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl From<RocksbStorageBuilder> for RocksdbStorageLogsSink {
+    fn from(storage: RocksbStorageBuilder) -> Self {
+        Self {
+            storage: Mutex::new(storage),
+        }
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+#[async_trait]
+impl StorageLogsSink for RocksdbStorageLogsSink {
+    async fn write_storage_logs_chunk(
+        &self,
+        _status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let mut storage = self.storage.lock().await;
+        storage
+            .insert_snapshot_storage_logs(storage_logs)
+            .await
+            .with_context(|| format!("failed writing storage logs chunk {chunk_id} to RocksDB"))
+    }
+}
+
+/// Map of Postgres shards for [`ShardedPostgresStorageLogsSink`]. Each shard holds storage logs and initial writes
+/// for a contiguous range of hashed keys determined by the first byte of the key.
+#[derive(Debug)]
+pub struct StorageLogsShardMap {
+    /// First hashed key byte for each shard, together with the shard pool. Ordered by the byte.
+    shards: Vec<(u8, ConnectionPool)>,
+}
+
+impl StorageLogsShardMap {
+    /// Creates a shard map. Each shard is specified by the first hashed key byte in the shard and the connection pool
+    /// for the shard; the shard covers all bytes up to the first byte of the next shard (exclusive).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if shards are empty, not ordered by their first byte, or don't start from byte 0.
+    pub fn new(shards: Vec<(u8, ConnectionPool)>) -> anyhow::Result<Self> {
+        let first_byte = shards.first().context("shard map is empty")?.0;
+        anyhow::ensure!(
+            first_byte == 0,
+            "first shard must start from byte 0, but starts from {first_byte:#04x}"
+        );
+        for window in shards.windows(2) {
+            let (prev_byte, next_byte) = (window[0].0, window[1].0);
+            anyhow::ensure!(
+                prev_byte < next_byte,
+                "shards are not ordered by first hashed key byte: {prev_byte:#04x} is followed by {next_byte:#04x}"
+            );
+        }
+        Ok(Self { shards })
+    }
+
+    fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the index of the shard holding the specified hashed key.
+    pub fn shard_index(&self, hashed_key: H256) -> usize {
+        let first_byte = hashed_key.as_bytes()[0];
+        // `partition_point()` is positive since the first shard starts from byte 0.
+        self.shards
+            .partition_point(|&(shard_byte, _)| shard_byte <= first_byte)
+            - 1
+    }
+}
+
+/// [`StorageLogsSink`] routing storage logs and initial writes to Postgres shards based on hashed keys. The sink
+/// should be used together with disabled [`SnapshotsApplierComponents::storage_logs`], so that storage logs
+/// are not duplicated in the main database (which still holds the recovery status).
+///
+/// Logs for each shard are written in a separate transaction. If all logs for a shard are already persisted
+/// (e.g., because the chunk is re-applied after an applier restart), the shard is skipped.
+///
+/// [`SnapshotsApplierComponents::storage_logs`]: crate::SnapshotsApplierComponents::storage_logs
+#[derive(Debug)]
+pub struct ShardedPostgresStorageLogsSink {
+    shard_map: StorageLogsShardMap,
+}
+
+impl ShardedPostgresStorageLogsSink {
+    pub fn new(shard_map: StorageLogsShardMap) -> Self {
+        Self { shard_map }
+    }
+
+    async fn write_to_shard(
+        pool: &ConnectionPool,
+        status: &SnapshotRecoveryStatus,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let mut storage = pool.access_storage_tagged("snapshots_applier").await?;
+        let mut transaction = storage
+            .start_transaction()
+            .await
+            .context("failed starting DB transaction")?;
+        let hashed_keys: Vec<_> = storage_logs
+            .iter()
+            .map(|log| log.key.hashed_key())
+            .collect();
+        let persisted_count = transaction
+            .storage_logs_dal()
+            .count_storage_logs_for_keys(&hashed_keys, status.miniblock_number)
+            .await
+            .context("failed counting persisted storage logs")?;
+        if persisted_count == hashed_keys.len() as u64 {
+            return Ok(());
+        }
+        // Logs are written atomically, so a partially written chunk indicates external modification of the shard.
+        anyhow::ensure!(
+            persisted_count == 0,
+            "only {persisted_count} out of {} storage logs are persisted",
+            hashed_keys.len()
+        );
+
+        transaction
+            .storage_logs_dal()
+            .insert_storage_logs_from_snapshot(status.miniblock_number, storage_logs)
+            .await
+            .context("failed persisting storage logs")?;
+        transaction
+            .storage_logs_dedup_dal()
+            .insert_initial_writes_from_snapshot(storage_logs)
+            .await
+            .context("failed persisting initial writes")?;
+        transaction
+            .commit()
+            .await
+            .context("failed committing DB transaction")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageLogsSink for ShardedPostgresStorageLogsSink {
+    async fn write_storage_logs_chunk(
+        &self,
+        status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let mut logs_by_shard = vec![vec![]; self.shard_map.len()];
+        for log in storage_logs {
+            let shard_index = self.shard_map.shard_index(log.key.hashed_key());
+            logs_by_shard[shard_index].push(log.clone());
+        }
+
+        let shard_pools = self.shard_map.shards.iter().map(|(_, pool)| pool);
+        for (shard_index, (pool, shard_logs)) in shard_pools.zip(&logs_by_shard).enumerate() {
+            if shard_logs.is_empty() {
+                continue;
+            }
+            Self::write_to_shard(pool, status, shard_logs)
+                .await
+                .with_context(|| {
+                    format!("failed writing storage logs chunk {chunk_id} to shard #{shard_index}")
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct AccountStats {
+    chunk_ids: HashSet<u64>,
+    log_counts: HashMap<AccountTreeId, u64>,
+}
+
+/// [`StorageLogsSink`] accumulating the number of storage logs per account as chunks are applied, which allows
+/// bootstrapping analytics without a second pass over the recovered storage. The sink is cheaply cloneable;
+/// clones share accumulated stats, so a clone can be retained to read stats after recovery.
+///
+/// Stats only cover chunks applied by the current process; chunks processed before an applier restart
+/// are not accounted for. Chunks re-applied by the same process (e.g., after a retry) are counted once.
+#[derive(Debug, Clone, Default)]
+pub struct AccountStatsSink {
+    stats: Arc<std::sync::Mutex<AccountStats>>,
+}
+
+impl AccountStatsSink {
+    /// Returns the number of storage logs per account accumulated so far.
+    pub fn log_counts(&self) -> HashMap<AccountTreeId, u64> {
+        self.stats.lock().unwrap().log_counts.clone()
+    }
+
+    /// Returns the number of chunks accounted in [`Self::log_counts()`].
+    pub fn chunk_count(&self) -> usize {
+        self.stats.lock().unwrap().chunk_ids.len()
+    }
+}
+
+#[async_trait]
+impl StorageLogsSink for AccountStatsSink {
+    async fn write_storage_logs_chunk(
+        &self,
+        _status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let mut stats = self.stats.lock().unwrap();
+        if !stats.chunk_ids.insert(chunk_id) {
+            return Ok(());
+        }
+        for log in storage_logs {
+            *stats.log_counts.entry(*log.key.account()).or_default() += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Forwarding applied storage log chunks to a standby node.
+//!
+//! The standby node listens on a TCP address and receives a frame for each applied chunk:
+//!
+//! - L1 batch number of the snapshot (`u32`, big-endian)
+//! - Chunk ID (`u64`, big-endian)
+//! - Payload length in bytes (`u32`, big-endian)
+//! - Payload: chunk serialized in the same format as in the object store (i.e., [`SnapshotStorageLogsChunk`]
+//!   serialized as a [`StoredObject`])
+//!
+//! After processing a frame, the standby node responds with a single [`STANDBY_ACK_BYTE`]. The next frame is only sent
+//! after the acknowledgement is received, so the standby node is bootstrapped in lockstep with the applier.
+//!
+//! Chunks are forwarded before they are committed by the applier, so a chunk may be sent several times
+//! (e.g., if the applier is restarted, or the chunk transaction fails). The standby must ignore chunks
+//! it has already received, e.g. using [`StandbyChunkReceiver`].
+
+use std::{collections::HashSet, io, net::SocketAddr};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+};
+use zksync_object_store::StoredObject;
+use zksync_types::{
+    snapshots::{SnapshotRecoveryStatus, SnapshotStorageLog, SnapshotStorageLogsChunk},
+    L1BatchNumber,
+};
+
+use crate::StorageLogsSink;
+
+/// Byte sent by the standby node to acknowledge a received chunk.
+pub const STANDBY_ACK_BYTE: u8 = 1;
+
+/// [`StorageLogsSink`] forwarding applied chunks to a standby node using the protocol described
+/// in the [module docs](self). The connection is established on the first write and is re-established
+/// after an I/O error.
+#[derive(Debug)]
+pub struct StandbyForwardingSink {
+    address: SocketAddr,
+    connection: Mutex<Option<TcpStream>>,
+}
+
+impl StandbyForwardingSink {
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            connection: Mutex::new(None),
+        }
+    }
+
+    async fn send_frame(
+        stream: &mut TcpStream,
+        l1_batch_number: L1BatchNumber,
+        chunk_id: u64,
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        let payload_len =
+            u32::try_from(payload.len()).context("chunk payload doesn't fit into a frame")?;
+        stream.write_all(&l1_batch_number.0.to_be_bytes()).await?;
+        stream.write_all(&chunk_id.to_be_bytes()).await?;
+        stream.write_all(&payload_len.to_be_bytes()).await?;
+        stream.write_all(payload).await?;
+        stream.flush().await?;
+
+        let ack = stream
+            .read_u8()
+            .await
+            .context("failed reading acknowledgement")?;
+        anyhow::ensure!(
+            ack == STANDBY_ACK_BYTE,
+            "standby node responded with unexpected byte {ack:#04x}"
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageLogsSink for StandbyForwardingSink {
+    async fn write_storage_logs_chunk(
+        &self,
+        status: &SnapshotRecoveryStatus,
+        chunk_id: u64,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        let chunk = SnapshotStorageLogsChunk {
+            storage_logs: storage_logs.to_vec(),
+            proofs: vec![],
+        };
+        let payload = chunk
+            .serialize()
+            .map_err(|err| anyhow::anyhow!(err))
+            .context("failed serializing chunk")?;
+
+        let mut connection = self.connection.lock().await;
+        if connection.is_none() {
+            let stream = TcpStream::connect(self.address).await.with_context(|| {
+                format!("failed connecting to standby node at {}", self.address)
+            })?;
+            tracing::info!("Connected to standby node at {}", self.address);
+            *connection = Some(stream);
+        }
+        // `unwrap()` is safe: the connection is established above
+        let stream = connection.as_mut().unwrap();
+        let result = Self::send_frame(stream, status.l1_batch_number, chunk_id, &payload).await;
+        if result.is_err() {
+            // The connection state is unknown; reconnect on the next write.
+            *connection = None;
+        }
+        result.with_context(|| {
+            format!(
+                "failed forwarding chunk to standby node at {}",
+                self.address
+            )
+        })
+    }
+}
+
+/// Chunk received by a standby node.
+#[derive(Debug)]
+pub struct StandbyChunk {
+    /// L1 batch number of the snapshot the chunk belongs to.
+    pub l1_batch_number: L1BatchNumber,
+    /// ID of the chunk in the snapshot.
+    pub chunk_id: u64,
+    pub chunk: SnapshotStorageLogsChunk,
+}
+
+/// Standby-side counterpart of [`StandbyForwardingSink`]. Reads frames and acknowledges chunks that were
+/// already received (as identified by the L1 batch number and chunk ID), so that re-sent chunks are ignored.
+///
+/// Received chunks are tracked in memory; the receiver should be reused across connections from the applier,
+/// since the sink reconnects after I/O errors.
+#[derive(Debug, Default)]
+pub struct StandbyChunkReceiver {
+    received_chunks: HashSet<(L1BatchNumber, u64)>,
+}
+
+impl StandbyChunkReceiver {
+    /// Returns the number of distinct chunks acknowledged by this receiver.
+    pub fn received_chunk_count(&self) -> usize {
+        self.received_chunks.len()
+    }
+
+    /// Receives the next chunk that wasn't received before, skipping (and acknowledging) duplicates.
+    /// Returns `None` if the connection is closed by the applier. The returned chunk must be acknowledged
+    /// with [`Self::acknowledge()`] once it's processed.
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O errors and returns an error if the chunk cannot be deserialized.
+    pub async fn receive_chunk<S>(&mut self, stream: &mut S) -> anyhow::Result<Option<StandbyChunk>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        loop {
+            let l1_batch_number = match stream.read_u32().await {
+                Ok(number) => L1BatchNumber(number),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(anyhow::Error::new(err).context("failed reading frame")),
+            };
+            let chunk_id = stream.read_u64().await.context("failed reading chunk ID")?;
+            let payload_len = stream
+                .read_u32()
+                .await
+                .context("failed reading payload length")?;
+            let mut payload = vec![0; payload_len as usize];
+            stream
+                .read_exact(&mut payload)
+                .await
+                .context("failed reading payload")?;
+
+            if self.received_chunks.contains(&(l1_batch_number, chunk_id)) {
+                tracing::debug!(
+                    "Ignoring chunk {chunk_id} for L1 batch #{l1_batch_number} since it was already received"
+                );
+                stream.write_u8(STANDBY_ACK_BYTE).await?;
+                stream.flush().await?;
+                continue;
+            }
+            let chunk = SnapshotStorageLogsChunk::deserialize(payload)
+                .map_err(|err| anyhow::anyhow!(err))
+                .with_context(|| format!("failed deserializing chunk {chunk_id}"))?;
+            return Ok(Some(StandbyChunk {
+                l1_batch_number,
+                chunk_id,
+                chunk,
+            }));
+        }
+    }
+
+    /// Acknowledges a processed chunk returned by [`Self::receive_chunk()`].
+    ///
+    /// # Errors
+    ///
+    /// Propagates I/O errors.
+    pub async fn acknowledge<S>(
+        &mut self,
+        stream: &mut S,
+        chunk: &StandbyChunk,
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncWrite + Unpin,
+    {
+        self.received_chunks
+            .insert((chunk.l1_batch_number, chunk.chunk_id));
+        stream
+            .write_u8(STANDBY_ACK_BYTE)
+            .await
+            .context("failed sending acknowledgement")?;
+        stream.flush().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! Summaries of recovered node state allowing to compare recovery results across nodes.
+
+use std::fmt;
+
+use anyhow::Context as _;
+use zksync_dal::ConnectionPool;
+use zksync_types::{L1BatchNumber, MiniblockNumber, H256};
+
+use crate::checksum;
+
+/// Summary of the node state recovered from a snapshot. Summaries for different nodes can be compared
+/// using [`Self::is_consistent_with()`] to confirm that all nodes recovered identically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoverySummary {
+    pub l1_batch_number: L1BatchNumber,
+    pub miniblock_number: MiniblockNumber,
+    pub storage_logs_chunk_count: usize,
+    /// Number of storage logs persisted for the snapshot miniblock.
+    pub storage_log_count: u64,
+    /// Checksum of the recovered state; see [`state_checksum()`](crate::state_checksum()).
+    pub state_checksum: H256,
+}
+
+impl RecoverySummary {
+    /// Loads the summary for the node storage. Returns `Ok(None)` if the storage wasn't recovered
+    /// from a snapshot, or if recovery is not complete yet.
+    ///
+    /// # Errors
+    ///
+    /// Propagates DB errors.
+    pub async fn load(connection_pool: &ConnectionPool) -> anyhow::Result<Option<Self>> {
+        let mut storage = connection_pool
+            .access_storage_tagged("snapshots_applier")
+            .await?;
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .context("failed fetching applied snapshot status from DB")?;
+        let Some(status) = status else {
+            return Ok(None);
+        };
+        if status.storage_logs_chunks_left_to_process() > 0 {
+            return Ok(None);
+        }
+
+        let storage_log_count = storage
+            .storage_logs_dal()
+            .count_miniblock_storage_logs(status.miniblock_number)
+            .await
+            .context("failed counting storage logs")?;
+        let state_checksum =
+            checksum::recovered_state_checksum(&mut storage, status.miniblock_number, 1)
+                .await
+                .context("failed computing recovered state checksum")?;
+        Ok(Some(Self {
+            l1_batch_number: status.l1_batch_number,
+            miniblock_number: status.miniblock_number,
+            storage_logs_chunk_count: status.storage_logs_chunks_processed.len(),
+            storage_log_count,
+            state_checksum,
+        }))
+    }
+
+    /// Checks whether this summary matches the `other` one.
+    ///
+    /// # Errors
+    ///
+    /// Returns all mismatching fields if the summaries differ.
+    pub fn is_consistent_with(&self, other: &Self) -> Result<(), RecoverySummaryMismatch> {
+        let mut diffs = vec![];
+        let mut compare = |field, this: String, other: String| {
+            if this != other {
+                diffs.push(RecoverySummaryFieldDiff { field, this, other });
+            }
+        };
+        compare(
+            "l1_batch_number",
+            self.l1_batch_number.to_string(),
+            other.l1_batch_number.to_string(),
+        );
+        compare(
+            "miniblock_number",
+            self.miniblock_number.to_string(),
+            other.miniblock_number.to_string(),
+        );
+        compare(
+            "storage_logs_chunk_count",
+            self.storage_logs_chunk_count.to_string(),
+            other.storage_logs_chunk_count.to_string(),
+        );
+        compare(
+            "storage_log_count",
+            self.storage_log_count.to_string(),
+            other.storage_log_count.to_string(),
+        );
+        compare(
+            "state_checksum",
+            format!("{:?}", self.state_checksum),
+            format!("{:?}", other.state_checksum),
+        );
+
+        if diffs.is_empty() {
+            Ok(())
+        } else {
+            Err(RecoverySummaryMismatch { diffs })
+        }
+    }
+}
+
+/// Difference in a single field of compared [`RecoverySummary`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoverySummaryFieldDiff {
+    /// Name of the field.
+    pub field: &'static str,
+    /// Field value in the summary on which [`RecoverySummary::is_consistent_with()`] was called.
+    pub this: String,
+    /// Field value in the other summary.
+    pub other: String,
+}
+
+/// Error returned by [`RecoverySummary::is_consistent_with()`] if summaries differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoverySummaryMismatch {
+    /// Mismatching fields in the order of their declaration in [`RecoverySummary`]. Never empty.
+    pub diffs: Vec<RecoverySummaryFieldDiff>,
+}
+
+impl fmt::Display for RecoverySummaryMismatch {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("recovery summaries differ: ")?;
+        for (i, diff) in self.diffs.iter().enumerate() {
+            if i > 0 {
+                formatter.write_str(", ")?;
+            }
+            write!(
+                formatter,
+                "{} ({} vs {})",
+                diff.field, diff.this, diff.other
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RecoverySummaryMismatch {}
@@ -1,19 +1,46 @@
 //! Snapshot applier tests.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    collections::HashSet,
+    error::Error as _,
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use assert_matches::assert_matches;
+use flate2::{write::GzEncoder, Compression};
+use rand::{rngs::StdRng, SeedableRng};
+use tempfile::TempDir;
 use test_casing::test_casing;
-use zksync_object_store::ObjectStoreFactory;
+use zksync_health_check::{CheckHealth, HealthStatus};
+use zksync_merkle_tree::{MerkleTree, PatchSet, TreeEntry};
+use zksync_object_store::{Bucket, ObjectStoreFactory};
+#[cfg(feature = "rocksdb")]
+use zksync_state::RocksdbStorage;
 use zksync_types::{
     block::{L1BatchHeader, MiniblockHeader},
-    Address, L1BatchNumber, ProtocolVersion, ProtocolVersionId,
+    snapshots::{
+        SnapshotFactoryDependencies, SnapshotFactoryDependency, SnapshotStorageLogProof,
+        SnapshotStorageLogsChunk, SnapshotStorageLogsChunkMetadata,
+    },
+    tokens::{TokenInfo, TokenMetadata},
+    web3::futures::FutureExt as _,
+    AccountTreeId, Address, L1BatchNumber, ProtocolVersion, ProtocolVersionId,
 };
 
+#[cfg(feature = "ipfs")]
+use self::utils::MockIpfsGateway;
 use self::utils::{
-    mock_recovery_status, prepare_clients, MockMainNodeClient, ObjectStoreWithErrors,
+    expected_state_checksum, mock_account, mock_recovery_status, prepare_clients,
+    prepare_clients_with_chunk_sizes, random_storage_logs, ConcurrencyTrackingStore,
+    FixedDiskStats, MockL1Client, MockMainNodeClient, MockStreamingSource, ObjectStoreWithDelays,
+    ObjectStoreWithErrors, RecordingBackoff,
 };
 use super::*;
+use crate::crc32c::Crc32c;
 
 mod utils;
 
@@ -86,7 +113,8 @@ async fn snapshots_creator_can_successfully_recover_db(
     }
 
     // Try recovering again; it should return early.
-    let err = SnapshotsApplier::load_snapshot(&pool, &client, object_store)
+    let config = SnapshotsApplierConfig::for_tests();
+    let err = SnapshotsApplier::load_snapshot(&config, None, None, &pool, &client, object_store)
         .await
         .unwrap_err();
     assert_matches!(
@@ -166,6 +194,36 @@ async fn applier_returns_early_without_snapshots() {
     assert_matches!(outcome, SnapshotsApplierOutcome::NoSnapshotsOnMainNode);
 }
 
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn applier_waits_for_main_node_to_advertise_snapshot(with_timeout: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    client
+        .snapshot_polls_before_response
+        .store(1, Ordering::Relaxed);
+
+    let config = SnapshotsApplierConfig {
+        snapshot_wait_timeout: with_timeout.then_some(Duration::from_secs(60)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    if with_timeout {
+        assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+        let status = applied_snapshot_status(&pool).await.unwrap();
+        assert_eq!(status, Some(expected_status));
+    } else {
+        assert_matches!(outcome, SnapshotsApplierOutcome::NoSnapshotsOnMainNode);
+    }
+    assert_eq!(
+        client
+            .snapshot_polls_before_response
+            .load(Ordering::Relaxed),
+        0
+    );
+}
+
 #[tokio::test]
 async fn applier_returns_error_on_fatal_object_store_error() {
     let pool = ConnectionPool::test_pool().await;
@@ -185,6 +243,32 @@ async fn applier_returns_error_on_fatal_object_store_error() {
             Some(ObjectStoreError::KeyNotFound(_))
         )
     }));
+    let recovery_err = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<RecoveryError>())
+        .unwrap();
+    assert_matches!(recovery_err, RecoveryError::ObjectNotFound(_));
+    assert!(format!("{err:#}").contains(recovery_err.hint()), "{err:#}");
+}
+
+#[test]
+fn recovery_error_messages_contain_hints() {
+    let errors = [
+        RecoveryError::from(ObjectStoreError::KeyNotFound("not found".into())),
+        RecoveryError::from(ObjectStoreError::Serialization("invalid".into())),
+        RecoveryError::from(ObjectStoreError::Other("access denied".into())),
+        RecoveryError::from(ObjectStoreError::Unsupported("read-only".into())),
+        RecoveryError::from(SqlxError::RowNotFound),
+        RecoveryError::from(RpcError::RequestTimeout),
+    ];
+    let mut hints = HashSet::new();
+    for err in &errors {
+        let hint = err.hint();
+        assert!(err.to_string().contains(hint), "{err}");
+        assert!(hints.insert(hint), "duplicate hint: {hint}");
+        assert!(err.source().is_some(), "{err:?}");
+    }
+    assert!(errors[2].hint().contains("IAM role"));
 }
 
 #[tokio::test]
@@ -207,3 +291,5062 @@ async fn applier_returns_error_after_too_many_object_store_retries() {
         )
     }));
 }
+
+#[tokio::test]
+async fn estimating_remaining_chunks() {
+    let mut status = mock_recovery_status();
+    let (_, client, _) = prepare_clients(&status).await;
+    let header = client.fetch_newest_snapshot_response.unwrap();
+
+    let remaining = estimate_chunks_remaining(&status, &header);
+    assert_eq!(remaining, 0);
+
+    status.storage_logs_chunks_processed = vec![true, false];
+    let remaining = estimate_chunks_remaining(&status, &header);
+    assert_eq!(remaining, 1);
+
+    status.storage_logs_chunks_processed = vec![false, false];
+    let remaining = estimate_chunks_remaining(&status, &header);
+    assert_eq!(remaining, 2);
+}
+
+#[tokio::test]
+async fn summarizing_snapshot_contents() {
+    let status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&status).await;
+    let header = client.fetch_newest_snapshot_response.unwrap();
+
+    let summary = SnapshotReader::new(object_store.as_ref(), &header)
+        .summary()
+        .await
+        .unwrap();
+    let unique_accounts: HashSet<_> = all_snapshot_storage_logs
+        .values()
+        .map(|log| *log.key.address())
+        .collect();
+    assert_eq!(
+        summary,
+        SnapshotContentsSummary {
+            l1_batch_number: status.l1_batch_number,
+            miniblock_number: status.miniblock_number,
+            storage_logs_chunk_count: 2,
+            storage_log_count: all_snapshot_storage_logs.len() as u64,
+            unique_account_count: unique_accounts.len(),
+            factory_dep_count: 1,
+            factory_deps_bytecode_size: 32,
+        }
+    );
+    assert_eq!(summary.storage_log_count, 20);
+}
+
+#[tokio::test]
+async fn object_store_concurrency_is_capped_per_bucket() {
+    let tracking_store = ConcurrencyTrackingStore::default();
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            object_store_bucket_concurrency: HashMap::from([
+                (Bucket::StorageSnapshot, 2),
+                (Bucket::ProverJobs, 3),
+            ]),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let object_store = RetryingObjectStore::new(&tracking_store, &config, None);
+
+    let buckets = [
+        Bucket::StorageSnapshot,
+        Bucket::ProverJobs,
+        Bucket::WitnessInput,
+    ];
+    let requests = buckets.into_iter().flat_map(|bucket| {
+        let object_store = &object_store;
+        (0..10)
+            .map(move |i| async move { object_store.get_raw(bucket, &format!("object{i}")).await })
+    });
+    for result in futures::future::join_all(requests).await {
+        result.unwrap();
+    }
+
+    assert_eq!(tracking_store.max_concurrency(Bucket::StorageSnapshot), 2);
+    assert_eq!(tracking_store.max_concurrency(Bucket::ProverJobs), 3);
+    // The bucket without a cap is not throttled.
+    assert_eq!(tracking_store.max_concurrency(Bucket::WitnessInput), 10);
+}
+
+#[cfg(feature = "opentelemetry")]
+#[tokio::test]
+async fn recovery_spans_are_exported_to_opentelemetry() {
+    use std::{future::Future, pin::Pin};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::{
+        export::trace::{ExportResult, SpanData, SpanExporter},
+        trace::TracerProvider,
+    };
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    #[derive(Debug, Clone, Default)]
+    struct RecordingExporter(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for RecordingExporter {
+        fn export(
+            &mut self,
+            batch: Vec<SpanData>,
+        ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+            self.0.lock().unwrap().extend(batch);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    let exporter = RecordingExporter::default();
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber =
+        tracing_subscriber::registry().with(opentelemetry_layer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    for result in provider.force_flush() {
+        result.unwrap();
+    }
+
+    let spans = exporter.0.lock().unwrap();
+    let span_names: HashSet<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+    for expected_name in [
+        "snapshot_recovery",
+        "load_snapshot",
+        "recover_factory_deps",
+        "recover_storage_logs",
+        "recover_storage_logs_single_chunk",
+    ] {
+        assert!(span_names.contains(expected_name), "{span_names:?}");
+    }
+    let chunk_span_count = spans
+        .iter()
+        .filter(|span| span.name == "recover_storage_logs_single_chunk")
+        .count();
+    assert_eq!(chunk_span_count, 2);
+}
+
+#[tokio::test]
+async fn comparing_recovery_summaries() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let summary = RecoverySummary::load(&pool).await.unwrap();
+    assert!(summary.is_none());
+    SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+
+    let summary = RecoverySummary::load(&pool).await.unwrap().unwrap();
+    let expected_summary = RecoverySummary {
+        l1_batch_number: expected_status.l1_batch_number,
+        miniblock_number: expected_status.miniblock_number,
+        storage_logs_chunk_count: 2,
+        storage_log_count: all_snapshot_storage_logs.len() as u64,
+        state_checksum: expected_state_checksum(&all_snapshot_storage_logs),
+    };
+    assert_eq!(summary, expected_summary);
+    summary.is_consistent_with(&expected_summary).unwrap();
+
+    let other_summary = RecoverySummary {
+        miniblock_number: summary.miniblock_number + 1,
+        storage_log_count: 19,
+        ..summary.clone()
+    };
+    let mismatch = summary.is_consistent_with(&other_summary).unwrap_err();
+    assert_eq!(
+        mismatch.diffs,
+        [
+            RecoverySummaryFieldDiff {
+                field: "miniblock_number",
+                this: "321".to_owned(),
+                other: "322".to_owned(),
+            },
+            RecoverySummaryFieldDiff {
+                field: "storage_log_count",
+                this: "20".to_owned(),
+                other: "19".to_owned(),
+            },
+        ]
+    );
+    assert_eq!(
+        mismatch.to_string(),
+        "recovery summaries differ: miniblock_number (321 vs 322), storage_log_count (20 vs 19)"
+    );
+}
+
+#[tokio::test]
+async fn recovery_summaries_differ_for_different_storage_logs() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    let summary = RecoverySummary::load(&pool).await.unwrap().unwrap();
+
+    // Recover another node from a snapshot differing in a single storage value.
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 0,
+    };
+    let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    chunk.storage_logs[0].value = H256::repeat_byte(0xff);
+    object_store.put(chunk_key, &chunk).await.unwrap();
+    let other_pool = ConnectionPool::test_pool().await;
+    SnapshotsApplierConfig::for_tests()
+        .run(&other_pool, &client, &object_store)
+        .await
+        .unwrap();
+
+    let other_summary = RecoverySummary::load(&other_pool).await.unwrap().unwrap();
+    assert_ne!(other_summary.state_checksum, summary.state_checksum);
+    let mismatch = summary.is_consistent_with(&other_summary).unwrap_err();
+    assert_eq!(mismatch.diffs.len(), 1);
+    assert_eq!(mismatch.diffs[0].field, "state_checksum");
+}
+
+#[test]
+fn start_delay_is_within_configured_bound() {
+    let mut rng = StdRng::seed_from_u64(123);
+    let config = SnapshotsApplierConfig::for_tests();
+    assert_eq!(config.start_delay(&mut rng), Duration::ZERO);
+
+    let max_start_jitter = Duration::from_secs(10);
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            max_start_jitter,
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let delays: HashSet<_> = (0..100).map(|_| config.start_delay(&mut rng)).collect();
+    assert!(delays.iter().all(|&delay| delay <= max_start_jitter));
+    assert!(delays.len() > 1, "{delays:?}");
+}
+
+#[tokio::test]
+async fn recovering_from_piped_stream() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let (mut writer, reader) = tokio::io::duplex(1_024);
+    let writer_task = tokio::spawn(async move {
+        let l1_batch_number = expected_status.l1_batch_number;
+        let factory_deps: SnapshotFactoryDependencies =
+            object_store.get(l1_batch_number).await.unwrap();
+        PipeObjectStore::write_object(&mut writer, l1_batch_number, &factory_deps).await?;
+        // Write chunks in the reverse order to check that the order of objects in the stream doesn't matter.
+        for chunk_id in (0..2).rev() {
+            let key = SnapshotStorageLogsStorageKey {
+                l1_batch_number,
+                chunk_id,
+            };
+            let chunk: SnapshotStorageLogsChunk = object_store.get(key).await.unwrap();
+            PipeObjectStore::write_object(&mut writer, key, &chunk).await?;
+        }
+        anyhow::Ok(())
+    });
+
+    let pipe_store = PipeObjectStore::new(reader);
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &pipe_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    writer_task.await.unwrap().unwrap();
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+    }
+
+    // The stream is closed once the writer is dropped, so missing objects should be reported as such.
+    let err = (&pipe_store as &dyn ObjectStore)
+        .get::<SnapshotFactoryDependencies>(L1BatchNumber(1))
+        .await
+        .unwrap_err();
+    assert_matches!(err, ObjectStoreError::KeyNotFound(_));
+}
+
+#[test]
+fn concurrency_ramp_limits() {
+    let limits: Vec<_> = (0..6)
+        .map(|processed_chunks| ConcurrencyRamp::limit(9, 4, processed_chunks))
+        .collect();
+    assert_eq!(limits, [1, 3, 5, 7, 9, 9]);
+
+    // Without a ramp, the maximum concurrency is used from the start.
+    assert_eq!(ConcurrencyRamp::limit(9, 0, 0), 9);
+}
+
+#[tokio::test]
+async fn concurrency_increases_stepwise_during_ramp() {
+    let ramp = ConcurrencyRamp::new(4, 3);
+    let mut observed_limits = vec![ramp.current_limit()];
+    for _ in 0..4 {
+        let permit = ramp.acquire().await;
+        ramp.chunk_processed();
+        drop(permit);
+        observed_limits.push(ramp.current_limit());
+    }
+    assert_eq!(observed_limits, [1, 2, 3, 4, 4]);
+
+    // Check that the permits are actually available.
+    let permits: Vec<_> = futures::future::join_all((0..4).map(|_| ramp.acquire())).await;
+    assert_eq!(permits.len(), 4);
+    assert!(ramp.acquire().now_or_never().is_none());
+}
+
+#[tokio::test]
+async fn concurrency_is_clamped_to_pool_size() {
+    let pool = ConnectionPool::constrained_test_pool(2).await;
+    let pool_size = pool.max_size();
+    assert_eq!(pool_size, 2);
+
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(10),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    assert_eq!(config.effective_concurrency(pool_size), 2);
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    assert_eq!(config.effective_concurrency(pool_size), 1);
+    let config = SnapshotsApplierConfig::for_tests();
+    assert_eq!(config.effective_concurrency(pool_size), 2);
+
+    // Check that recovery with the clamped concurrency completes successfully.
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(10),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+}
+
+#[tokio::test]
+async fn getting_applied_status() {
+    let pool = ConnectionPool::test_pool().await;
+    let status = applied_snapshot_status(&pool).await.unwrap();
+    assert_eq!(status, None);
+
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let status = applied_snapshot_status(&pool).await.unwrap();
+    assert_eq!(status, Some(expected_status));
+}
+
+#[test]
+fn computing_crc32c() {
+    let mut crc32c = Crc32c::new();
+    crc32c.update(b"123456789");
+    assert_eq!(crc32c.finalize(), 0xe306_9283);
+
+    // Incremental computation must produce the same checksum.
+    let mut crc32c = Crc32c::new();
+    for chunk in b"123456789".chunks(2) {
+        crc32c.update(chunk);
+    }
+    assert_eq!(crc32c.finalize(), 0xe306_9283);
+    assert_eq!(Crc32c::new().finalize(), 0);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn recovering_with_crc32c_verification(corrupt_chunk: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let l1_batch_number = expected_status.l1_batch_number;
+    let mut keys = vec![SnapshotFactoryDependencies::encode_key(l1_batch_number)];
+    keys.extend((0..2).map(|chunk_id| {
+        SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id,
+        })
+    }));
+    let mut source = MockStreamingSource::default();
+    for (i, key) in keys.iter().enumerate() {
+        let bytes = object_store
+            .get_raw(Bucket::StorageSnapshot, key)
+            .await
+            .unwrap();
+        let mut crc32c = Crc32c::new();
+        crc32c.update(&bytes);
+        let mut crc32c = crc32c.finalize();
+        if corrupt_chunk && i == 2 {
+            crc32c ^= 1;
+        }
+        let object_key = format!("{}/{key}", Bucket::StorageSnapshot);
+        source.objects.insert(object_key, (bytes, Some(crc32c)));
+    }
+    let object_store = Crc32cVerifyingStore::new(source);
+    let chunk_size = object_store
+        .get_size_raw(Bucket::StorageSnapshot, &keys[1])
+        .await
+        .unwrap();
+    assert!(chunk_size > 0);
+
+    let config = SnapshotsApplierConfig {
+        retry_count: 2,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if corrupt_chunk {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("CRC32C mismatch"), "{err}");
+        assert!(err.contains(&keys[2]), "{err}");
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+#[test_casing(3, [None, Some(100), Some(7)])]
+#[tokio::test]
+async fn configuring_crc32c_read_buffer_size(read_buffer_size: Option<usize>) {
+    let bytes: Vec<u8> = (0..=255).cycle().take(1_000).collect();
+    let mut crc32c = Crc32c::new();
+    crc32c.update(&bytes);
+    let mut source = MockStreamingSource::default();
+    let object_key = format!("{}/object", Bucket::StorageSnapshot);
+    source
+        .objects
+        .insert(object_key, (bytes.clone(), Some(crc32c.finalize())));
+    let read_count = source.read_count.clone();
+
+    let mut object_store = Crc32cVerifyingStore::new(source);
+    if let Some(size) = read_buffer_size {
+        object_store = object_store.with_read_buffer_size(size);
+    }
+    let fetched_bytes = object_store
+        .get_raw(Bucket::StorageSnapshot, "object")
+        .await
+        .unwrap();
+    assert_eq!(fetched_bytes, bytes);
+
+    let buffer_size = read_buffer_size.unwrap_or(DEFAULT_READ_BUFFER_SIZE);
+    let expected_read_count = (bytes.len() + buffer_size - 1) / buffer_size;
+    assert_eq!(read_count.load(Ordering::Relaxed), expected_read_count);
+}
+
+#[tokio::test]
+async fn crc32c_verifying_reader_fails_on_mismatch_while_streaming() {
+    use tokio::io::AsyncReadExt;
+
+    let bytes = b"123456789".to_vec();
+    let mut reader = Crc32cVerifyingReader::new(
+        "test/object".to_owned(),
+        Some(0xe306_9283),
+        Some(9),
+        bytes.as_slice(),
+    );
+    let mut buffer = vec![];
+    reader.read_to_end(&mut buffer).await.unwrap();
+    assert_eq!(buffer, bytes);
+
+    let mut reader = Crc32cVerifyingReader::new(
+        "test/object".to_owned(),
+        Some(0xe306_9283 ^ 1),
+        None,
+        bytes.as_slice(),
+    );
+    let err = reader.read_to_end(&mut vec![]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("CRC32C mismatch"), "{err}");
+
+    // An object exceeding the reported size must be rejected before it's read in full.
+    let mut reader =
+        Crc32cVerifyingReader::new("test/object".to_owned(), None, Some(2), bytes.as_slice());
+    let mut buffer = [0_u8; 4];
+    let err = reader.read(&mut buffer).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("got at least 4"), "{err}");
+}
+
+#[cfg(feature = "ipfs")]
+#[tokio::test]
+async fn recovering_from_ipfs() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let mut gateway = MockIpfsGateway::default();
+    let mut cids = HashMap::new();
+    let l1_batch_number = expected_status.l1_batch_number;
+    let mut keys = vec![SnapshotFactoryDependencies::encode_key(l1_batch_number)];
+    keys.extend((0..2).map(|chunk_id| {
+        SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id,
+        })
+    }));
+    for (i, key) in keys.iter().enumerate() {
+        let bytes = object_store
+            .get_raw(Bucket::StorageSnapshot, key)
+            .await
+            .unwrap();
+        let cid = format!("bafy{i}");
+        gateway.contents.insert(cid.clone(), bytes);
+        cids.insert(
+            IpfsObjectStore::object_key(Bucket::StorageSnapshot, key),
+            cid,
+        );
+    }
+    let ipfs_store = IpfsObjectStore::new(gateway, cids);
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &ipfs_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+
+    let err = ipfs_store
+        .put_raw(Bucket::StorageSnapshot, &keys[0], vec![])
+        .await
+        .unwrap_err();
+    assert_matches!(err, ObjectStoreError::Unsupported(_));
+}
+
+/// Codec "unpacking" values stored XORed with a fixed mask.
+#[derive(Debug)]
+struct XorValueCodec(u8);
+
+impl ValueCodec for XorValueCodec {
+    fn decode(&self, _key: &StorageKey, value: StorageValue) -> anyhow::Result<StorageValue> {
+        let mut bytes = value.0;
+        for byte in &mut bytes {
+            *byte ^= self.0;
+        }
+        Ok(StorageValue::from(bytes))
+    }
+}
+
+#[tokio::test]
+async fn applier_decodes_values_using_codec() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig {
+        value_codec: Box::new(XorValueCodec(0xa5)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        let expected_value = XorValueCodec(0xa5)
+            .decode(&expected_log.key, expected_log.value)
+            .unwrap();
+        assert_eq!(db_log.value, expected_value);
+        assert_ne!(db_log.value, expected_log.value);
+    }
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn applier_errors_on_incomplete_snapshot_miniblock(wrong_l1_batch: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    let miniblock = client
+        .fetch_l2_block_responses
+        .get_mut(&expected_status.miniblock_number)
+        .unwrap();
+    if wrong_l1_batch {
+        miniblock.l1_batch_number += 1;
+    } else {
+        miniblock.hash = None;
+    }
+
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    if wrong_l1_batch {
+        assert!(err.contains("belongs to L1 batch #124"), "{err}");
+    } else {
+        assert!(err.contains("doesn't have hash set"), "{err}");
+    }
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, None);
+}
+
+/// Main node client only implementing required methods.
+#[derive(Debug)]
+struct MinimalMainNodeClient(MockMainNodeClient);
+
+#[async_trait]
+impl SnapshotsApplierMainNodeClient for MinimalMainNodeClient {
+    async fn fetch_l2_block(&self, number: MiniblockNumber) -> Result<Option<SyncBlock>, RpcError> {
+        self.0.fetch_l2_block(number).await
+    }
+
+    async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError> {
+        self.0.fetch_newest_snapshot().await
+    }
+}
+
+#[tokio::test]
+async fn recovering_with_minimal_main_node_client() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let client = MinimalMainNodeClient(client);
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Unsupported methods must result in a fatal error rather than a panic.
+    let err = client
+        .fetch_l1_batch_root_hash(expected_status.l1_batch_number)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("`fetch_l1_batch_root_hash` is not supported"),
+        "{err}"
+    );
+    assert_matches!(
+        SnapshotsApplierError::from(err),
+        SnapshotsApplierError::Fatal(_)
+    );
+}
+
+#[tokio::test]
+async fn applier_errors_on_inconsistent_timestamps() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.l1_batch_timestamp = expected_status.miniblock_timestamp + 1;
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.contains("has timestamp 105 which is earlier than timestamp 106"),
+        "{err}"
+    );
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, None);
+}
+
+#[tokio::test]
+async fn applier_errors_on_inconsistent_timestamps_on_resume() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+
+    // Emulate an interrupted recovery; the main node reports a different timestamp for the snapshot miniblock.
+    let partial_status = SnapshotRecoveryStatus {
+        l1_batch_root_hash: expected_status.l1_batch_root_hash,
+        miniblock_hash: expected_status.miniblock_hash,
+        storage_logs_chunks_processed: vec![false, false],
+        ..mock_recovery_status()
+    };
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&partial_status)
+        .await
+        .unwrap();
+    drop(storage);
+    let miniblock = client
+        .fetch_l2_block_responses
+        .get_mut(&expected_status.miniblock_number)
+        .unwrap();
+    miniblock.timestamp = expected_status.miniblock_timestamp + 10;
+
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.contains("has timestamp 115, while recovery status has timestamp 105"),
+        "{err}"
+    );
+
+    // Recovery is resumed once timestamps are consistent.
+    let miniblock = client
+        .fetch_l2_block_responses
+        .get_mut(&expected_status.miniblock_number)
+        .unwrap();
+    miniblock.timestamp = expected_status.miniblock_timestamp;
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn applier_handles_newer_protocol_version(allow_newer_protocol_version: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.protocol_version = ProtocolVersionId::next();
+    assert!(expected_status.protocol_version > ProtocolVersionId::latest());
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .last_l1_batch_with_metadata
+        .header
+        .protocol_version = Some(expected_status.protocol_version);
+
+    let config = SnapshotsApplierConfig {
+        allow_newer_protocol_version,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if allow_newer_protocol_version {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        let mut storage = pool.access_storage().await.unwrap();
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .unwrap();
+        assert_eq!(status.unwrap(), expected_status);
+    } else {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("please upgrade the node"), "{err}");
+    }
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn applier_verifies_l1_batch_commitment(matching_commitment: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let snapshot_commitment = client
+        .fetch_newest_snapshot_response
+        .as_ref()
+        .unwrap()
+        .last_l1_batch_with_metadata
+        .metadata
+        .commitment;
+    let l1_commitment = if matching_commitment {
+        snapshot_commitment
+    } else {
+        H256::repeat_byte(0xfe)
+    };
+    let l1_client = MockL1Client {
+        l1_batch_commitments: HashMap::from([(expected_status.l1_batch_number, l1_commitment)]),
+        ..MockL1Client::default()
+    };
+
+    let config = SnapshotsApplierConfig {
+        l1_client: Some(Box::new(l1_client)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    if matching_commitment {
+        assert_matches!(result, Ok(SnapshotsApplierOutcome::Ok));
+        assert_eq!(status, Some(expected_status));
+    } else {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("commitment"), "{err}");
+        assert_eq!(status, None);
+    }
+}
+
+#[tokio::test]
+async fn applier_writes_only_selected_components() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig {
+        components: SnapshotsApplierComponents {
+            storage_logs: true,
+            factory_deps: false,
+            tokens: false,
+            recovery_status: false,
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+
+    let factory_dep_hash = hash_bytecode(&(0..32).collect::<Vec<u8>>());
+    let factory_dep = storage
+        .factory_deps_dal()
+        .get_factory_dep(factory_dep_hash)
+        .await;
+    assert_eq!(factory_dep, None);
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, None);
+}
+
+#[tokio::test]
+async fn applier_errors_early_on_missing_db_tables() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .system_dal()
+        .drop_table_for_tests("storage_logs")
+        .await;
+    drop(storage);
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            check_db_schema: true,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("missing tables"), "{err}");
+    assert!(err.contains("storage_logs"), "{err}");
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, None);
+}
+
+#[tokio::test]
+async fn recovering_sharded_factory_deps() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+
+    let bytecode = |byte: u8| vec![byte; 32];
+    let shards = [vec![1, 2], vec![2, 3], vec![3, 4, 1], vec![4]];
+    let mut shard_keys = vec![];
+    for (i, shard) in shards.iter().enumerate() {
+        let factory_deps = SnapshotFactoryDependencies {
+            factory_deps: shard
+                .iter()
+                .map(|&byte| SnapshotFactoryDependency {
+                    bytecode: bytecode(byte).into(),
+                })
+                .collect(),
+        };
+        let key = format!("factory_deps_shard_{i}.proto.gzip");
+        object_store
+            .put_raw(
+                SnapshotFactoryDependencies::BUCKET,
+                &key,
+                factory_deps.serialize().unwrap(),
+            )
+            .await
+            .unwrap();
+        shard_keys.push(key);
+    }
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .factory_deps_shards = shard_keys;
+    // Shuffle completion order of shards.
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.starts_with("factory_deps_shard_0") || key.starts_with("factory_deps_shard_2") {
+            Duration::from_millis(50)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            factory_deps_concurrency: 3,
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    for byte in 1..=4 {
+        let bytecode = bytecode(byte);
+        let factory_dep = storage
+            .factory_deps_dal()
+            .get_factory_dep(hash_bytecode(&bytecode))
+            .await;
+        assert_eq!(factory_dep, Some(bytecode));
+    }
+    // The unsharded factory deps object must not be applied.
+    let unsharded_dep_hash = hash_bytecode(&(0..32).collect::<Vec<u8>>());
+    let factory_dep = storage
+        .factory_deps_dal()
+        .get_factory_dep(unsharded_dep_hash)
+        .await;
+    assert_eq!(factory_dep, None);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn reconciling_storage_logs_with_main_node(diverging_main_node: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+    client.storage_values = all_snapshot_storage_logs
+        .values()
+        .map(|log| {
+            let value = if diverging_main_node {
+                H256::repeat_byte(0xff)
+            } else {
+                log.value
+            };
+            ((log.key, expected_status.miniblock_number), value)
+        })
+        .collect();
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            main_node_reconciliation_sample_size: 5,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if diverging_main_node {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("main node returns"), "{err}");
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+async fn measure_recovery_makespan(chunk_order: StorageLogsChunkOrder) -> Duration {
+    // Chunk processing concurrency is equal to the pool size.
+    let pool = ConnectionPool::constrained_test_pool(2).await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.storage_logs_chunks_processed = vec![true; 4];
+    let (object_store, client, _) =
+        prepare_clients_with_chunk_sizes(&expected_status, &[10, 10, 10, 60]).await;
+    let large_chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 3,
+    });
+    // Emulate download time proportional to the chunk size.
+    let object_store = ObjectStoreWithDelays::new(object_store, move |key| {
+        if key == large_chunk_key {
+            Duration::from_millis(600)
+        } else if key.contains("storage_logs") {
+            Duration::from_millis(200)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        chunk_order,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let started_at = Instant::now();
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    started_at.elapsed()
+}
+
+#[tokio::test]
+async fn largest_first_chunk_order_reduces_makespan() {
+    // With sequential order, the large chunk starts after two small chunks are processed, giving makespan ~800ms.
+    // With largest-first order, small chunks are processed while the large chunk is downloaded (~600ms).
+    let sequential_makespan = measure_recovery_makespan(StorageLogsChunkOrder::Sequential).await;
+    let largest_first_makespan =
+        measure_recovery_makespan(StorageLogsChunkOrder::LargestFirst).await;
+    assert!(
+        largest_first_makespan < sequential_makespan,
+        "largest-first: {largest_first_makespan:?}, sequential: {sequential_makespan:?}"
+    );
+}
+
+#[tokio::test]
+async fn applier_recovers_tokens() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    let tokens: Vec<_> = (1_u64..=2)
+        .map(|i| TokenInfo {
+            l1_address: Address::from_low_u64_be(i),
+            l2_address: Address::from_low_u64_be(i + 1_000),
+            metadata: TokenMetadata {
+                name: format!("Token {i}"),
+                symbol: format!("TK{i}"),
+                decimals: 18,
+            },
+        })
+        .collect();
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .includes_tokens = true;
+    client
+        .fetch_tokens_responses
+        .insert(expected_status.miniblock_number, tokens.clone());
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let mut l2_addresses = storage
+        .tokens_dal()
+        .get_all_l2_token_addresses()
+        .await
+        .unwrap();
+    l2_addresses.sort_unstable();
+    let expected_l2_addresses: Vec<_> = tokens.iter().map(|token| token.l2_address).collect();
+    assert_eq!(l2_addresses, expected_l2_addresses);
+}
+
+#[tokio::test]
+async fn repairing_processed_chunk_without_persisted_data() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    // Emulate chunk 0 marked as processed without its data actually persisted.
+    let mut inconsistent_status = mock_recovery_status();
+    inconsistent_status.storage_logs_chunks_processed = vec![true, false];
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&inconsistent_status)
+        .await
+        .unwrap();
+
+    let config = SnapshotsApplierConfig {
+        repair_processed_chunks: true,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let mut storage_transaction = storage.start_transaction().await.unwrap();
+    let mut recovery = SnapshotsApplier {
+        config: &config,
+        connection_pool: &pool,
+        blob_store: RetryingObjectStore::new(&object_store, &config, None),
+        applied_snapshot_status: inconsistent_status,
+        content_addressed_chunks: HashMap::new(),
+        path_template: None,
+        checkpoint_state: Default::default(),
+        applied_chunks: Default::default(),
+        format_version: 0,
+        compression_dictionary: None,
+    };
+    recovery
+        .repair_processed_chunks(&mut storage_transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        recovery
+            .applied_snapshot_status
+            .storage_logs_chunks_processed,
+        [false, false]
+    );
+    storage_transaction.commit().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [false, false]);
+    drop(storage);
+
+    // Check that the repaired chunk is re-applied.
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, Some(expected_status));
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+#[tokio::test]
+async fn reverifying_partially_persisted_last_chunk() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Emulate the last chunk partially persisted despite being marked as processed.
+    let last_chunk_id = expected_status.storage_logs_chunks_processed.len() as u64 - 1;
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: last_chunk_id,
+    };
+    let chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    let removed_keys: Vec<_> = chunk.storage_logs[..3]
+        .iter()
+        .map(|log| log.key.hashed_key())
+        .collect();
+    let mut storage = pool.access_storage().await.unwrap();
+    let removed_count = storage
+        .storage_logs_dal()
+        .delete_storage_logs_for_keys(&removed_keys, expected_status.miniblock_number)
+        .await
+        .unwrap();
+    assert_eq!(removed_count, 3);
+    drop(storage);
+
+    let config = SnapshotsApplierConfig {
+        reverify_last_processed_chunk: true,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, Some(expected_status));
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    let all_initial_writes = storage
+        .storage_logs_dedup_dal()
+        .dump_all_initial_writes_for_tests()
+        .await;
+    assert_eq!(all_initial_writes.len(), all_snapshot_storage_logs.len());
+}
+
+const CORRUPTED_PROCESSED_FLAGS: [&[bool]; 3] = [&[false], &[false, false], &[true, false, true]];
+
+#[test_casing(3, CORRUPTED_PROCESSED_FLAGS)]
+#[tokio::test]
+async fn reconciling_corrupted_recovery_progress(corrupted_flags: &[bool]) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .set_storage_logs_chunks_processed(corrupted_flags)
+        .await
+        .unwrap();
+    drop(storage);
+
+    let config = SnapshotsApplierConfig {
+        reconcile_progress: true,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status, Some(expected_status));
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn recovering_with_content_addressed_chunk_keys(corrupt_chunk: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+
+    // Move chunks to content-addressed keys.
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    for chunk in &mut header.storage_logs_chunks {
+        let standard_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id: chunk.chunk_id,
+        });
+        let mut bytes = object_store
+            .get_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+        let hash = H256(keccak256(&bytes));
+        if corrupt_chunk && chunk.chunk_id == 1 {
+            bytes.push(0);
+        }
+        chunk.filepath = format!("{hash:?}");
+        object_store
+            .put_raw(Bucket::StorageSnapshot, &chunk.filepath, bytes)
+            .await
+            .unwrap();
+        object_store
+            .remove_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+    }
+
+    let result = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await;
+    if corrupt_chunk {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("storage logs chunk 1 is corrupted"), "{err}");
+        return;
+    }
+
+    assert_matches!(result, Ok(SnapshotsApplierOutcome::Ok));
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+    }
+}
+
+/// Runs recovery that fails fatally after the first storage logs chunk is processed.
+async fn interrupt_recovery_after_first_chunk(
+    pool: &ConnectionPool,
+    client: &MockMainNodeClient,
+    object_store: Arc<dyn ObjectStore>,
+    l1_batch_number: L1BatchNumber,
+) {
+    let failing_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number,
+        chunk_id: 1,
+    });
+    let object_store = ObjectStoreWithErrors::new(object_store, move |key| {
+        if key == failing_key {
+            Err(ObjectStoreError::KeyNotFound("not found".into()))
+        } else {
+            Ok(())
+        }
+    });
+    let config = SnapshotsApplierConfig {
+        throttling: ThrottlingConfig {
+            // Process chunks one by one, so that the first chunk is guaranteed to be processed.
+            concurrency_ramp_chunks: 10,
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    config.run(pool, client, &object_store).await.unwrap_err();
+
+    let status = applied_snapshot_status(pool).await.unwrap().unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [true, false]);
+}
+
+#[tokio::test]
+async fn resuming_recovery_after_chunk_filepaths_change() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+    let l1_batch_number = expected_status.l1_batch_number;
+    interrupt_recovery_after_first_chunk(&pool, &client, object_store.clone(), l1_batch_number)
+        .await;
+
+    // Re-upload chunks under new filepaths. The processed chunk is removed from the object store,
+    // so the applier would fail if it tried to fetch it again.
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    for chunk in &mut header.storage_logs_chunks {
+        let standard_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id: chunk.chunk_id,
+        });
+        let bytes = object_store
+            .get_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+        object_store
+            .remove_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+        chunk.filepath = format!("{:?}", H256(keccak256(&bytes)));
+        if chunk.chunk_id != 0 {
+            object_store
+                .put_raw(Bucket::StorageSnapshot, &chunk.filepath, bytes)
+                .await
+                .unwrap();
+        }
+    }
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let status = applied_snapshot_status(&pool).await.unwrap().unwrap();
+    assert_eq!(status, expected_status);
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+    }
+}
+
+#[tokio::test]
+async fn resuming_recovery_errors_if_chunk_ids_change() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    interrupt_recovery_after_first_chunk(
+        &pool,
+        &client,
+        object_store.clone(),
+        expected_status.l1_batch_number,
+    )
+    .await;
+
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    header
+        .storage_logs_chunks
+        .push(SnapshotStorageLogsChunkMetadata::new(2, "file2"));
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("don't match 2 chunks"), "{err}");
+}
+
+#[tokio::test]
+async fn chunk_decode_timeout_aborts_pathological_chunk() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    // Replace a chunk with a small object that decompresses into 64 MiB of zeros.
+    let mut encoder = GzEncoder::new(vec![], Compression::fast());
+    let zeros = vec![0_u8; 1 << 20];
+    for _ in 0..64 {
+        encoder.write_all(&zeros).unwrap();
+    }
+    let malformed_chunk = encoder.finish().unwrap();
+    let key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    });
+    object_store
+        .put_raw(Bucket::StorageSnapshot, &key, malformed_chunk)
+        .await
+        .unwrap();
+
+    let config = SnapshotsApplierConfig {
+        chunk_decode_timeout: Some(Duration::from_millis(10)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.contains("decoding storage logs chunk 1 didn't finish"),
+        "{err}"
+    );
+    let status = applied_snapshot_status(&pool).await.unwrap().unwrap();
+    assert!(!status.storage_logs_chunks_processed[1]);
+}
+
+#[tokio::test]
+async fn verification_checkpoint_is_persisted_and_used_on_resume() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.storage_logs_chunks_processed = vec![true; 4];
+    let (object_store, client, all_snapshot_storage_logs) =
+        prepare_clients_with_chunk_sizes(&expected_status, &[10; 4]).await;
+    let chunk_key = |chunk_id| {
+        SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        })
+    };
+
+    // Interrupt recovery after 3 chunks are processed.
+    let failing_key = chunk_key(3);
+    let failing_object_store = ObjectStoreWithErrors::new(object_store.clone(), move |key| {
+        if key == failing_key {
+            Err(ObjectStoreError::KeyNotFound("not found".into()))
+        } else {
+            Ok(())
+        }
+    });
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            verification_checkpoint_interval: 2,
+            ..VerificationConfig::default()
+        },
+        throttling: ThrottlingConfig {
+            // Process chunks one by one, so that chunks are processed in the order of their IDs.
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    config
+        .run(&pool, &client, &failing_object_store)
+        .await
+        .unwrap_err();
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let checkpoint = storage
+        .snapshot_recovery_dal()
+        .get_verification_checkpoint()
+        .await
+        .unwrap()
+        .expect("no checkpoint");
+    assert_eq!(
+        checkpoint,
+        SnapshotRecoveryCheckpoint {
+            verified_chunks: vec![true, true, false, false],
+            storage_logs_count: 20,
+        }
+    );
+    drop(storage);
+
+    // Remove chunks covered by the checkpoint; the applier should not try to re-verify them on resume.
+    for chunk_id in [0, 1] {
+        object_store
+            .remove_raw(Bucket::StorageSnapshot, &chunk_key(chunk_id))
+            .await
+            .unwrap();
+    }
+    let config = SnapshotsApplierConfig {
+        repair_processed_chunks: true,
+        verification: VerificationConfig {
+            verification_checkpoint_interval: 2,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+#[tokio::test]
+async fn recovery_progress_can_be_seeded_from_external_status() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    // Remove the chunk marked as processed, so that the applier would fail if it tried to fetch it.
+    let key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 0,
+    });
+    object_store
+        .remove_raw(Bucket::StorageSnapshot, &key)
+        .await
+        .unwrap();
+
+    let config = SnapshotsApplierConfig {
+        initial_status: Some(SnapshotRecoveryStatus {
+            storage_logs_chunks_processed: vec![true, false],
+            ..expected_status
+        }),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let status = applied_snapshot_status(&pool).await.unwrap().unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [true, true]);
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    // Only logs from the second chunk should be applied.
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len() / 2);
+}
+
+#[tokio::test]
+async fn seeded_recovery_status_must_match_snapshot() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig {
+        initial_status: Some(SnapshotRecoveryStatus {
+            l1_batch_number: expected_status.l1_batch_number + 1,
+            storage_logs_chunks_processed: vec![true, false],
+            ..expected_status
+        }),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.contains("doesn't match the recovered snapshot"),
+        "{err}"
+    );
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert!(status.is_none());
+}
+
+#[test]
+fn rendering_path_templates() {
+    let template = PathTemplate::new("snapshots/{l1_batch}/chunk_{chunk_id:06}.bin").unwrap();
+    assert_eq!(
+        template.render(L1BatchNumber(123), 5),
+        "snapshots/123/chunk_000005.bin"
+    );
+    assert_eq!(
+        template.render(L1BatchNumber(123), 1_234_567),
+        "snapshots/123/chunk_1234567.bin"
+    );
+    assert_eq!(
+        template.to_string(),
+        "snapshots/{l1_batch}/chunk_{chunk_id:06}.bin"
+    );
+
+    let template = PathTemplate::new("{{escaped}}/{l1_batch:08}-{chunk_id}").unwrap();
+    assert_eq!(
+        template.render(L1BatchNumber(42), 3),
+        "{escaped}/00000042-3"
+    );
+
+    let invalid_templates = [
+        ("snapshots/{l1_batch}.bin", "must contain `{chunk_id}`"),
+        ("snapshots/{chunk_id", "unclosed"),
+        ("snapshots/chunk_id}", "unmatched"),
+        ("snapshots/{chunk}", "unknown placeholder `chunk`"),
+        ("snapshots/{chunk_id:6}", "unsupported format spec"),
+    ];
+    for (template, expected_err) in invalid_templates {
+        let err = format!("{:#}", PathTemplate::new(template).unwrap_err());
+        assert!(err.contains(expected_err), "{template}: {err}");
+    }
+}
+
+#[tokio::test]
+async fn recovering_with_templated_chunk_keys() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+
+    // Move chunks to templated keys and remove explicit filepaths from the header.
+    let template = "snapshots/{l1_batch}/chunk_{chunk_id:06}.bin";
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    header.storage_logs_path_template = Some(template.to_owned());
+    for chunk in &mut header.storage_logs_chunks {
+        chunk.filepath.clear();
+        let standard_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id: chunk.chunk_id,
+        });
+        let bytes = object_store
+            .get_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+        object_store
+            .remove_raw(Bucket::StorageSnapshot, &standard_key)
+            .await
+            .unwrap();
+        let templated_key = format!("snapshots/123/chunk_{:06}.bin", chunk.chunk_id);
+        object_store
+            .put_raw(Bucket::StorageSnapshot, &templated_key, bytes)
+            .await
+            .unwrap();
+    }
+    assert!(verify_snapshot_manifest(header).is_empty());
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+    }
+}
+
+#[tokio::test]
+async fn watchdog_aborts_stalled_recovery() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let stalled_chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    });
+    let object_store = ObjectStoreWithDelays::new(object_store, move |key| {
+        if key == stalled_chunk_key {
+            Duration::from_secs(3_600)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let stall_timeout = Duration::from_millis(100);
+    let config = SnapshotsApplierConfig {
+        retry_count: 1,
+        stall_timeout: Some(stall_timeout),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let stalled = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<SnapshotRecoveryStalled>());
+    assert_eq!(stalled.unwrap().stall_timeout, stall_timeout, "{err:#}");
+    assert_eq!(
+        CancellationReason::from_error(&err),
+        Some(CancellationReason::Stalled { stall_timeout })
+    );
+
+    // Recovery should be resumable.
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [true, false]);
+}
+
+#[tokio::test]
+async fn debug_state_reflects_recovery_progress() {
+    // Chunks are processed sequentially since the pool has a single connection.
+    let pool = ConnectionPool::constrained_test_pool(1).await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let l1_batch_number = expected_status.l1_batch_number;
+    let chunk_key = |chunk_id| {
+        SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id,
+        })
+    };
+    let factory_deps_key = SnapshotFactoryDependencies::encode_key(l1_batch_number);
+    let mut expected_bytes_downloaded = 0;
+    for key in [factory_deps_key, chunk_key(0)] {
+        let bytes = object_store.get_raw(Bucket::StorageSnapshot, &key).await;
+        expected_bytes_downloaded += bytes.unwrap().len() as u64;
+    }
+    let stalled_chunk_key = chunk_key(1);
+    let object_store = ObjectStoreWithDelays::new(object_store, move |key| {
+        if key == stalled_chunk_key {
+            Duration::from_secs(3_600)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig::for_tests();
+    let debug_handle = config.debug_handle.clone();
+    assert_eq!(
+        debug_handle.debug_state(),
+        SnapshotsApplierDebugState::default()
+    );
+    let recovery = config.run(&pool, &client, &object_store);
+    let wait_for_stalled_chunk = async {
+        loop {
+            let state = debug_handle.debug_state();
+            if state.current_chunk_id == Some(1) && state.in_flight_downloads == 1 {
+                break state;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let state = tokio::select! {
+        _ = recovery => panic!("recovery unexpectedly finished"),
+        state = wait_for_stalled_chunk => state,
+    };
+
+    assert_eq!(
+        state,
+        SnapshotsApplierDebugState {
+            in_flight_downloads: 1,
+            decoded_chunks_pending_insert: 0,
+            bytes_downloaded: expected_bytes_downloaded,
+            current_chunk_id: Some(1),
+            prewarmed_blocks: 0,
+        }
+    );
+}
+
+#[tokio::test]
+async fn watchdog_does_not_abort_slow_recovery_with_progress() {
+    // Chunks are processed sequentially since the pool has a single connection.
+    let pool = ConnectionPool::constrained_test_pool(1).await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.contains("storage_logs") {
+            Duration::from_millis(150)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        retry_count: 1,
+        stall_timeout: Some(Duration::from_millis(250)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+}
+
+#[tokio::test]
+async fn recovering_storage_logs_with_initial_writes_from_multiple_l1_batches() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, mut all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+
+    // Reassign initial write L1 batches so that they vary both within and across chunks.
+    let l1_batch_numbers = [
+        L1BatchNumber(1),
+        L1BatchNumber(50),
+        expected_status.l1_batch_number,
+    ];
+    all_snapshot_storage_logs.clear();
+    for chunk_id in 0..2 {
+        let key = SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        };
+        let mut chunk: SnapshotStorageLogsChunk = object_store.get(key).await.unwrap();
+        for (i, log) in chunk.storage_logs.iter_mut().enumerate() {
+            log.l1_batch_number_of_initial_write =
+                l1_batch_numbers[(i + chunk_id as usize) % l1_batch_numbers.len()];
+        }
+        object_store.put(key, &chunk).await.unwrap();
+        all_snapshot_storage_logs.extend(
+            chunk
+                .storage_logs
+                .into_iter()
+                .map(|log| (log.key.hashed_key(), log)),
+        );
+    }
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_initial_writes = storage
+        .storage_logs_dedup_dal()
+        .dump_all_initial_writes_for_tests()
+        .await;
+    assert_eq!(all_initial_writes.len(), all_snapshot_storage_logs.len());
+    let mut seen_l1_batch_numbers = HashSet::new();
+    for initial_write in all_initial_writes {
+        let log = &all_snapshot_storage_logs[&initial_write.hashed_key];
+        assert_eq!(
+            initial_write.l1_batch_number,
+            log.l1_batch_number_of_initial_write
+        );
+        assert_eq!(initial_write.index, log.enumeration_index);
+        seen_l1_batch_numbers.insert(initial_write.l1_batch_number);
+    }
+    assert_eq!(seen_l1_batch_numbers, HashSet::from(l1_batch_numbers));
+
+    // Integrity checks performed on restart must accept logs from different L1 batches as well.
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            restart_verification_fraction: 1.0,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = SnapshotsApplier::load_snapshot(&config, None, None, &pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        SnapshotsApplierError::EarlyReturn(SnapshotsApplierOutcome::Ok)
+    );
+}
+
+#[tokio::test]
+async fn short_storage_logs_insert_is_detected_before_marking_chunk_processed() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, _, _) = prepare_clients(&expected_status).await;
+    let mut status = mock_recovery_status();
+    status.storage_logs_chunks_processed = vec![false, false];
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&status)
+        .await
+        .unwrap();
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            verify_chunks_before_marking_processed: true,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let recovery = SnapshotsApplier {
+        config: &config,
+        connection_pool: &pool,
+        blob_store: RetryingObjectStore::new(&object_store, &config, None),
+        applied_snapshot_status: status,
+        content_addressed_chunks: HashMap::new(),
+        path_template: None,
+        checkpoint_state: Default::default(),
+        applied_chunks: Default::default(),
+        format_version: 0,
+        compression_dictionary: None,
+    };
+    let chunk = recovery.fetch_storage_logs_chunk(0).await.unwrap();
+    let (inserted_logs, _) = chunk.storage_logs.split_at(chunk.storage_logs.len() / 2);
+
+    // Emulate a short insert.
+    let mut transaction = storage.start_transaction().await.unwrap();
+    recovery
+        .insert_storage_logs_chunk(0, inserted_logs, &mut transaction)
+        .await
+        .unwrap();
+    let err = recovery
+        .check_persisted_storage_logs_count(0, &chunk.storage_logs, &mut transaction)
+        .await
+        .unwrap_err();
+    assert_matches!(err, SnapshotsApplierError::Retryable(_));
+    let err = format!("{err:#}");
+    assert!(err.contains("only 5 out of 10 storage logs"), "{err}");
+    drop(transaction);
+
+    // Fully applied chunk should pass the check.
+    let mut transaction = storage.start_transaction().await.unwrap();
+    recovery
+        .insert_storage_logs_chunk(0, &chunk.storage_logs, &mut transaction)
+        .await
+        .unwrap();
+    recovery
+        .check_persisted_storage_logs_count(0, &chunk.storage_logs, &mut transaction)
+        .await
+        .unwrap();
+    drop(transaction);
+
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [false, false]);
+}
+
+#[tokio::test]
+async fn planning_recovery() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let config = SnapshotsApplierConfig::for_tests();
+
+    let plan = config.plan(&pool, &client).await.unwrap().unwrap();
+    assert!(plan.is_fresh);
+    assert_eq!(plan.l1_batch_number, expected_status.l1_batch_number);
+    assert_eq!(plan.factory_deps.len(), 1);
+    assert_eq!(plan.chunks_to_apply.len(), 2);
+    assert!(plan.skipped_chunk_ids.is_empty());
+
+    // Emulate partially completed recovery.
+    let mut partial_status = mock_recovery_status();
+    partial_status.storage_logs_chunks_processed = vec![true, false];
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&partial_status)
+        .await
+        .unwrap();
+    drop(storage);
+
+    let plan = config.plan(&pool, &client).await.unwrap().unwrap();
+    let expected_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    });
+    assert_eq!(
+        plan,
+        RecoveryPlan {
+            l1_batch_number: expected_status.l1_batch_number,
+            miniblock_number: expected_status.miniblock_number,
+            is_fresh: false,
+            factory_deps: vec![],
+            chunks_to_apply: vec![PlannedChunk {
+                chunk_id: 1,
+                object: PlannedObject {
+                    bucket: Bucket::StorageSnapshot,
+                    key: expected_key,
+                },
+            }],
+            skipped_chunk_ids: vec![0],
+        }
+    );
+
+    let outcome = config
+        .execute(&plan, &pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // The plan is outdated after execution.
+    let config = SnapshotsApplierConfig::for_tests();
+    let err = config
+        .execute(&plan, &pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("outdated"), "{err:#}");
+}
+
+#[test]
+fn computing_backoff_delays() {
+    let backoff = ExponentialBackoff::new(Duration::from_millis(100), 2.0);
+    let delays: Vec<_> = (1..=4).map(|i| backoff.retry_delay(i)).collect();
+    assert_eq!(delays, [100, 200, 400, 800].map(Duration::from_millis));
+
+    let backoff = backoff.with_jitter(0.5);
+    for i in 1..=4 {
+        let max_delay = Duration::from_millis(100 << (i - 1));
+        let delay = backoff.retry_delay(i);
+        assert!(delay <= max_delay && delay >= max_delay / 2, "{delay:?}");
+    }
+
+    let backoff = ConstantBackoff(Duration::from_millis(100));
+    for i in 1..=4 {
+        assert_eq!(backoff.retry_delay(i), Duration::from_millis(100));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn object_store_retries_use_custom_backoff_strategy() {
+    let expected_status = mock_recovery_status();
+    let (object_store, _, _) = prepare_clients(&expected_status).await;
+    let object_store = ObjectStoreWithErrors::new(object_store, |_| {
+        Err(ObjectStoreError::Other("service not available".into()))
+    });
+
+    let backoff = RecordingBackoff::default();
+    let config = SnapshotsApplierConfig {
+        object_store_retry_count: 3,
+        object_store_retry_backoff_strategy: Some(Box::new(backoff.clone())),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let store = RetryingObjectStore::new(&object_store, &config, None);
+    let started_at = Instant::now();
+    let err = store
+        .get::<SnapshotFactoryDependencies>(expected_status.l1_batch_number)
+        .await
+        .unwrap_err();
+
+    assert_matches!(err, ObjectStoreError::Other(_));
+    let expected_delays = [10, 20, 30].map(Duration::from_millis);
+    assert_eq!(backoff.delays(), expected_delays);
+    assert_eq!(started_at.elapsed(), expected_delays.iter().sum());
+}
+
+#[tokio::test]
+async fn recovery_retries_use_custom_backoff_strategy() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let object_store = ObjectStoreWithErrors::new(object_store, |_| {
+        Err(ObjectStoreError::Other("service not available".into()))
+    });
+
+    let backoff = RecordingBackoff::default();
+    let config = SnapshotsApplierConfig {
+        retry_count: 3,
+        object_store_retry_count: 0,
+        retry_backoff_strategy: Some(Box::new(backoff.clone())),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    config.run(&pool, &client, &object_store).await.unwrap_err();
+    assert_eq!(backoff.delays(), [10, 20, 30].map(Duration::from_millis));
+}
+
+#[cfg(feature = "chaos")]
+#[tokio::test]
+async fn recovery_completes_with_injected_failures() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig {
+        retry_count: 100,
+        retry_backoff_multiplier: 1.0,
+        object_store_retry_count: 10,
+        failure_injection: FailureInjection {
+            object_store_read_failure_probability: 0.5,
+            rpc_failure_probability: 0.5,
+            db_insert_failure_probability: 0.5,
+            db_commit_failure_probability: 0.5,
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status.unwrap(), expected_status);
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+#[tokio::test]
+async fn health_check_reflects_recovery_progress() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let config = SnapshotsApplierConfig::for_tests();
+    let health_check = config.health_check.clone();
+    assert_eq!(health_check.name(), "snapshot_recovery");
+    assert_matches!(
+        health_check.check_health().await.status(),
+        HealthStatus::NotReady
+    );
+
+    let observed_statuses = Arc::new(Mutex::new(vec![]));
+    let object_store = ObjectStoreWithErrors::new(object_store, {
+        let health_check = health_check.clone();
+        let observed_statuses = observed_statuses.clone();
+        move |_| {
+            let health = health_check.check_health().now_or_never().unwrap();
+            observed_statuses.lock().unwrap().push(health.status());
+            Ok(())
+        }
+    });
+
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let observed_statuses = observed_statuses.lock().unwrap();
+    assert!(!observed_statuses.is_empty());
+    assert!(
+        observed_statuses
+            .iter()
+            .all(|&status| status == HealthStatus::NotReady),
+        "{observed_statuses:?}"
+    );
+    assert_matches!(
+        health_check.check_health().await.status(),
+        HealthStatus::Ready
+    );
+}
+
+#[tokio::test]
+async fn verifying_snapshot_manifest() {
+    let expected_status = mock_recovery_status();
+    let (_, mut client, _) = prepare_clients(&expected_status).await;
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    assert!(verify_snapshot_manifest(header).is_empty());
+
+    header.storage_logs_chunks = vec![
+        SnapshotStorageLogsChunkMetadata::new(0, "file0"),
+        SnapshotStorageLogsChunkMetadata::new(0, String::new()),
+    ];
+    header.miniblock_number = MiniblockNumber(1);
+    header.last_l1_batch_with_metadata.header.number = L1BatchNumber(122);
+    header.last_l1_batch_with_metadata.header.protocol_version = Some(ProtocolVersionId::next());
+
+    let issues = verify_snapshot_manifest(header);
+    assert_eq!(
+        issues,
+        [
+            ManifestIssue::DuplicateChunkId(0),
+            ManifestIssue::EmptyChunkFilepath(0),
+            ManifestIssue::L1BatchNumberMismatch {
+                header: L1BatchNumber(123),
+                last_l1_batch: L1BatchNumber(122),
+            },
+            ManifestIssue::MiniblockNumberTooLow {
+                miniblock_number: MiniblockNumber(1),
+                l1_batch_number: L1BatchNumber(123),
+            },
+            ManifestIssue::UnknownProtocolVersion {
+                version: ProtocolVersionId::next(),
+                latest: ProtocolVersionId::latest(),
+            },
+        ]
+    );
+
+    header.storage_logs_chunks.clear();
+    header.factory_deps_filepath.clear();
+    header.last_l1_batch_with_metadata.header.protocol_version = None;
+    let issues = verify_snapshot_manifest(header);
+    assert!(
+        issues.contains(&ManifestIssue::NoStorageLogsChunks),
+        "{issues:?}"
+    );
+    assert!(
+        issues.contains(&ManifestIssue::EmptyFactoryDepsFilepath),
+        "{issues:?}"
+    );
+    assert!(
+        issues.contains(&ManifestIssue::MissingProtocolVersion),
+        "{issues:?}"
+    );
+}
+
+#[tokio::test]
+async fn applier_refuses_header_with_duplicate_chunk_ids() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    header.storage_logs_chunks = vec![
+        SnapshotStorageLogsChunkMetadata::new(0, "file0"),
+        SnapshotStorageLogsChunkMetadata::new(0, "other_file0"),
+    ];
+
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("ambiguous"), "{err}");
+    assert!(err.contains("other_file0"), "{err}");
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert!(all_storage_logs.is_empty());
+}
+
+#[tokio::test]
+async fn restart_verification_detects_corrupted_chunk() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            restart_verification_fraction: 1.0,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Emulate corruption by changing a value in the snapshot chunk.
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    };
+    let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    chunk.storage_logs[3].value = H256::repeat_byte(0xff);
+    object_store.put(chunk_key, &chunk).await.unwrap();
+
+    // Without verification, the corruption is not detected.
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            restart_verification_fraction: 1.0,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("chunk 1 is corrupted"), "{err}");
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn checking_disk_space_before_recovery(is_sufficient: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let mut chunks_size = 0;
+    for chunk_id in 0..2 {
+        let key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        });
+        chunks_size += object_store
+            .get_size_raw(SnapshotStorageLogsChunk::BUCKET, &key)
+            .await
+            .unwrap();
+    }
+    let mut disk_space_check = DiskSpaceCheck::new("/var/lib/postgresql");
+    disk_space_check.reserve = 1_000;
+    let required_space = disk_space_check.required_space(chunks_size);
+    assert!(required_space > chunks_size + 1_000);
+    let available_space = if is_sufficient {
+        required_space
+    } else {
+        required_space - 1
+    };
+    disk_space_check.stats_provider = Box::new(FixedDiskStats(available_space));
+
+    let config = SnapshotsApplierConfig {
+        disk_space_check: Some(disk_space_check),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    if is_sufficient {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        assert_eq!(status.unwrap(), expected_status);
+    } else {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("insufficient disk space"), "{err}");
+        // Recovery must not be started.
+        assert_eq!(status, None);
+    }
+}
+
+#[tokio::test]
+async fn inline_chunk_verification_aborts_recovery_on_corrupted_chunk() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.storage_logs_chunks_processed = vec![true; 4];
+    let (object_store, client, all_snapshot_storage_logs) =
+        prepare_clients_with_chunk_sizes(&expected_status, &[10; 4]).await;
+
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 2,
+    };
+    let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    chunk.storage_logs[5].key = chunk.storage_logs[3].key;
+    object_store.put(chunk_key, &chunk).await.unwrap();
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            verify_chunks_inline: true,
+            ..VerificationConfig::default()
+        },
+        throttling: ThrottlingConfig {
+            // Process chunks one by one, so that chunks are processed in the order of their IDs.
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("chunk 2 failed integrity check"), "{err}");
+    assert!(err.contains("is repeated in chunk 2"), "{err}");
+
+    // Chunks before the corrupted one must be applied, and the following chunks must not be processed.
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        status.storage_logs_chunks_processed,
+        [true, true, false, false]
+    );
+    let storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(storage_logs.len(), 20);
+    for db_log in &storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert!(expected_log.enumeration_index <= 20, "{expected_log:?}");
+        assert_eq!(db_log.value, expected_log.value);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ProofTampering {
+    None,
+    ChangedHash,
+    TooLongPath,
+    TruncatedPath,
+}
+
+/// Prepares clients with the snapshot root hash matching storage logs, and Merkle proofs included into chunk 1.
+async fn prepare_clients_with_proofs(
+    expected_status: &mut SnapshotRecoveryStatus,
+    tampering: ProofTampering,
+) -> (Arc<dyn ObjectStore>, MockMainNodeClient) {
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(expected_status).await;
+
+    let mut tree = MerkleTree::new(PatchSet::default());
+    let tree_entries = all_snapshot_storage_logs
+        .values()
+        .map(|log| TreeEntry::new(log.key.hashed_key_u256(), log.enumeration_index, log.value))
+        .collect();
+    let root_hash = tree.extend(tree_entries).root_hash;
+    expected_status.l1_batch_root_hash = root_hash;
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .last_l1_batch_with_metadata
+        .metadata
+        .root_hash = root_hash;
+
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    };
+    let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    let proven_indices = [0_usize, 7];
+    let tree_keys: Vec<_> = proven_indices
+        .iter()
+        .map(|&idx| chunk.storage_logs[idx].key.hashed_key_u256())
+        .collect();
+    let entries_with_proofs = tree.entries_with_proofs(0, &tree_keys).unwrap();
+    chunk.proofs = proven_indices
+        .iter()
+        .zip(entries_with_proofs)
+        .map(|(&idx, entry)| SnapshotStorageLogProof {
+            storage_log_index: idx as u64,
+            merkle_path: entry.merkle_path,
+        })
+        .collect();
+    let merkle_path = &mut chunk.proofs[1].merkle_path;
+    match tampering {
+        ProofTampering::None => { /* do nothing */ }
+        ProofTampering::ChangedHash => merkle_path[0] = H256::repeat_byte(0xff),
+        // The tree depth is 256, so a longer path is malformed.
+        ProofTampering::TooLongPath => merkle_path.resize(257, H256::zero()),
+        ProofTampering::TruncatedPath => {
+            merkle_path.pop();
+        }
+    }
+    object_store.put(chunk_key, &chunk).await.unwrap();
+    (object_store, client)
+}
+
+#[test_casing(4, [
+    ProofTampering::None,
+    ProofTampering::ChangedHash,
+    ProofTampering::TooLongPath,
+    ProofTampering::TruncatedPath,
+])]
+#[tokio::test]
+async fn verifying_storage_log_proofs(tampering: ProofTampering) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    let (object_store, client) = prepare_clients_with_proofs(&mut expected_status, tampering).await;
+
+    let result = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await;
+    if matches!(tampering, ProofTampering::None) {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        let mut storage = pool.access_storage().await.unwrap();
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .unwrap();
+        assert_eq!(status.unwrap(), expected_status);
+    } else {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(
+            err.contains("Merkle proof for storage log #7")
+                && err.contains("in chunk 1 is invalid"),
+            "{err}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn storage_log_proofs_are_not_verified_if_disabled() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    let (object_store, client) =
+        prepare_clients_with_proofs(&mut expected_status, ProofTampering::TooLongPath).await;
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            verify_storage_log_proofs: false,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+}
+
+#[cfg(feature = "rocksdb")]
+#[tokio::test]
+async fn applier_writes_storage_logs_to_rocksdb_sink() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let dir = TempDir::new().expect("cannot create temporary dir for RocksDB");
+    let sink = RocksdbStorageLogsSink::new(dir.path()).await.unwrap();
+    let config = SnapshotsApplierConfig {
+        sinks: SinksConfig {
+            storage_logs_sinks: vec![Box::new(sink)],
+            ..SinksConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    // The sink is dropped together with the config, so RocksDB can be reopened.
+
+    let storage = RocksdbStorage::builder(dir.path()).await.unwrap();
+    for (hashed_key, log) in &all_snapshot_storage_logs {
+        let entry = storage.read_state_entry(*hashed_key).await;
+        assert_eq!(entry, Some((log.value, Some(log.enumeration_index))));
+    }
+}
+
+#[tokio::test]
+async fn applier_routes_storage_logs_to_postgres_shards() {
+    let pool = ConnectionPool::test_pool().await;
+    let shard_pools = [
+        ConnectionPool::test_pool().await,
+        ConnectionPool::test_pool().await,
+    ];
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let shard_map = StorageLogsShardMap::new(vec![
+        (0x00, shard_pools[0].clone()),
+        (0x80, shard_pools[1].clone()),
+    ])
+    .unwrap();
+    assert_eq!(shard_map.shard_index(H256::zero()), 0);
+    assert_eq!(shard_map.shard_index(H256::repeat_byte(0x7f)), 0);
+    assert_eq!(shard_map.shard_index(H256::repeat_byte(0x80)), 1);
+    assert_eq!(shard_map.shard_index(H256::repeat_byte(0xff)), 1);
+
+    let config = SnapshotsApplierConfig {
+        components: SnapshotsApplierComponents {
+            storage_logs: false,
+            ..SnapshotsApplierComponents::default()
+        },
+        sinks: SinksConfig {
+            storage_logs_sinks: vec![Box::new(ShardedPostgresStorageLogsSink::new(shard_map))],
+            ..SinksConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let main_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert!(main_storage_logs.is_empty(), "{main_storage_logs:?}");
+
+    let mut recovered_log_count = 0;
+    for (shard_index, shard_pool) in shard_pools.iter().enumerate() {
+        let mut storage = shard_pool.access_storage().await.unwrap();
+        let shard_logs = storage
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await;
+        for log in &shard_logs {
+            let is_in_upper_half = log.hashed_key.as_bytes()[0] >= 0x80;
+            assert_eq!(usize::from(is_in_upper_half), shard_index, "{log:?}");
+            let expected_log = &all_snapshot_storage_logs[&log.hashed_key];
+            assert_eq!(log.value, expected_log.value);
+            assert_eq!(log.operation_number, expected_log.enumeration_index);
+            assert_eq!(log.miniblock_number, expected_status.miniblock_number);
+        }
+        recovered_log_count += shard_logs.len();
+    }
+    assert_eq!(recovered_log_count, all_snapshot_storage_logs.len());
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn applier_errors_on_enumeration_index_off_by_one(shift_up: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    for chunk_id in 0..expected_status.storage_logs_chunks_processed.len() as u64 {
+        let chunk_key = SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        };
+        let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+        for log in &mut chunk.storage_logs {
+            if shift_up {
+                log.enumeration_index += 1;
+            } else {
+                log.enumeration_index -= 1;
+            }
+        }
+        object_store.put(chunk_key, &chunk).await.unwrap();
+    }
+
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("must start from 1"), "{err}");
+    if shift_up {
+        // All chunks are applied, but the applier must not consider recovery successful on restart.
+        let err = SnapshotsApplierConfig::for_tests()
+            .run(&pool, &client, &object_store)
+            .await
+            .unwrap_err();
+        let err = format!("{err:#}");
+        assert!(err.contains("must start from 1"), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn retry_budget_is_shared_across_retries() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let request_count = Arc::new(AtomicUsize::new(0));
+    let object_store = ObjectStoreWithErrors::new(object_store, {
+        let request_count = request_count.clone();
+        move |_| {
+            request_count.fetch_add(1, Ordering::SeqCst);
+            Err(ObjectStoreError::Other("transient error".into()))
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        object_store_retry_count: 10,
+        retry_budget: Some(RetryBudget {
+            capacity: 3,
+            refill_interval: None,
+        }),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    assert_eq!(
+        CancellationReason::from_error(&err),
+        Some(CancellationReason::RetryBudgetExhausted)
+    );
+    let err = format!("{err:#}");
+    assert!(err.contains("retry budget is exhausted"), "{err}");
+    // The initial request and 3 retries; after that, neither the request nor the entire recovery are retried.
+    assert_eq!(request_count.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_budget_is_refilled_over_time() {
+    let budget = RetryTokenBucket::new(RetryBudget {
+        capacity: 2,
+        refill_interval: Some(Duration::from_secs(1)),
+    });
+    assert!(budget.try_acquire());
+    assert!(budget.try_acquire());
+    assert!(!budget.try_acquire());
+
+    tokio::time::advance(Duration::from_millis(1_500)).await;
+    assert!(budget.try_acquire());
+    assert!(!budget.try_acquire());
+    // The bucket is never refilled beyond its capacity.
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert!(budget.try_acquire());
+    assert!(budget.try_acquire());
+    assert!(!budget.try_acquire());
+}
+
+#[tokio::test(start_paused = true)]
+async fn object_store_retries_do_not_overrun_deadline() {
+    let expected_status = mock_recovery_status();
+    let (object_store, _, _) = prepare_clients(&expected_status).await;
+    let attempt_count = Arc::new(AtomicUsize::new(0));
+    let object_store = ObjectStoreWithErrors::new(object_store, {
+        let attempt_count = attempt_count.clone();
+        move |_| {
+            attempt_count.fetch_add(1, Ordering::SeqCst);
+            Err(ObjectStoreError::Other("service not available".into()))
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        object_store_retry_count: 100,
+        object_store_initial_retry_backoff: Duration::from_millis(30),
+        retry_backoff_multiplier: 2.0,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let deadline = Instant::now() + Duration::from_millis(100);
+    let store = RetryingObjectStore::new(&object_store, &config, Some(deadline));
+    let err = store
+        .get::<SnapshotFactoryDependencies>(expected_status.l1_batch_number)
+        .await
+        .unwrap_err();
+
+    assert_matches!(err, ObjectStoreError::Other(_));
+    assert!(Instant::now() < deadline);
+    // Attempts are made at 0ms, 30ms and 90ms; the next attempt at 210ms would overrun the deadline.
+    assert_eq!(attempt_count.load(Ordering::SeqCst), 3);
+}
+
+#[test_casing(2, [(0..2, 2..4), (0..3, 1..4)])]
+#[tokio::test]
+async fn recovery_can_be_sharded_across_appliers(
+    first_chunk_ids: std::ops::Range<u64>,
+    second_chunk_ids: std::ops::Range<u64>,
+) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.storage_logs_chunks_processed = vec![true; 4];
+    let (object_store, client, all_snapshot_storage_logs) =
+        prepare_clients_with_chunk_sizes(&expected_status, &[10; 4]).await;
+
+    let first_config = SnapshotsApplierConfig {
+        chunk_id_range: Some(first_chunk_ids),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let second_config = SnapshotsApplierConfig {
+        chunk_id_range: Some(second_chunk_ids),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let (first_outcome, second_outcome) = tokio::join!(
+        first_config.run(&pool, &client, &object_store),
+        second_config.run(&pool, &client, &object_store)
+    );
+    assert_matches!(first_outcome.unwrap(), SnapshotsApplierOutcome::Ok);
+    assert_matches!(second_outcome.unwrap(), SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status.unwrap(), expected_status);
+
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+    }
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn verifying_factory_deps_completeness(missing_dep: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let chunk_key = SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 1,
+    };
+    let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+    let account_code_storage = zksync_types::AccountTreeId::new(ACCOUNT_CODE_STORAGE_ADDRESS);
+    let code_hash = if missing_dep {
+        H256::from_low_u64_be(0x42)
+    } else {
+        hash_bytecode(&(0..32).collect::<Vec<u8>>())
+    };
+    chunk.storage_logs[0].key = StorageKey::new(account_code_storage, H256::repeat_byte(1));
+    chunk.storage_logs[0].value = code_hash;
+    // Storage logs for contracts being constructed have a marker that must be ignored by the check.
+    let mut constructing_code_hash = hash_bytecode(&(0..32).collect::<Vec<u8>>());
+    constructing_code_hash.0[1] = 1;
+    chunk.storage_logs[1].key = StorageKey::new(account_code_storage, H256::repeat_byte(2));
+    chunk.storage_logs[1].value = constructing_code_hash;
+    object_store.put(chunk_key, &chunk).await.unwrap();
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            verify_factory_deps_completeness: true,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if missing_dep {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(
+            err.contains("references 1 contract bytecode(s) missing from factory deps"),
+            "{err}"
+        );
+        assert!(err.contains(&format!("{:?}", code_hash)), "{err}");
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!status.storage_logs_chunks_processed[1]);
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn writing_applied_data_manifest(recovered_before: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    if recovered_before {
+        SnapshotsApplierConfig::for_tests()
+            .run(&pool, &client, &object_store)
+            .await
+            .unwrap();
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let manifest_path = temp_dir.path().join("manifest.json");
+    let config = SnapshotsApplierConfig {
+        applied_manifest_path: Some(manifest_path.clone()),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut manifest = AppliedManifest::read_from_file(&manifest_path).unwrap();
+    manifest.verify().unwrap();
+    assert_eq!(manifest.l1_batch_number, expected_status.l1_batch_number);
+    assert_eq!(manifest.miniblock_number, expected_status.miniblock_number);
+    assert_eq!(
+        manifest.state_checksum,
+        expected_state_checksum(&all_snapshot_storage_logs)
+    );
+    assert_eq!(manifest.chunks.len(), 2);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let recovered_log_count = storage
+        .storage_logs_dal()
+        .count_miniblock_storage_logs(expected_status.miniblock_number)
+        .await
+        .unwrap();
+    let manifest_log_count: u64 = manifest
+        .chunks
+        .iter()
+        .map(|chunk| chunk.storage_log_count)
+        .sum();
+    assert_eq!(manifest_log_count, recovered_log_count);
+    for (chunk_id, chunk) in (0..).zip(&manifest.chunks) {
+        let chunk_key = SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        };
+        let snapshot_chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+        assert_eq!(
+            *chunk,
+            AppliedChunk::new(chunk_id, &snapshot_chunk.storage_logs, 1)
+        );
+    }
+
+    manifest.chunks[1].storage_log_count += 1;
+    let err = manifest.verify().unwrap_err().to_string();
+    assert!(err.contains("doesn't match its contents"), "{err}");
+}
+
+#[cfg(feature = "progress-server")]
+#[tokio::test]
+async fn recovery_progress_is_served_over_http() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    // Slow down chunk downloads, so that progress can be queried while the chunks are processed.
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.contains("storage_logs") {
+            Duration::from_millis(500)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    // Reserve a free port for the server.
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let config = SnapshotsApplierConfig {
+        progress_server_address: Some(address),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let recovery_future = config.run(&pool, &client, &object_store);
+
+    let progress_future = async {
+        let url = format!("http://{address}/recovery/progress");
+        loop {
+            if let Ok(response) = reqwest::get(&url).await {
+                let progress: serde_json::Value =
+                    serde_json::from_str(&response.text().await.unwrap()).unwrap();
+                if progress["stage"] == "recovery" {
+                    return progress;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+
+    let (outcome, progress) = tokio::join!(recovery_future, progress_future);
+    assert_matches!(outcome.unwrap(), SnapshotsApplierOutcome::Ok);
+    assert_eq!(
+        progress["l1_batch_number"],
+        expected_status.l1_batch_number.0
+    );
+    assert_eq!(progress["storage_logs_chunk_count"], 2);
+    assert_eq!(progress["storage_logs_chunks_left_to_process"], 2);
+    assert!(
+        progress["debug"]["in_flight_downloads"].is_u64(),
+        "{progress}"
+    );
+
+    // The server must be shut down after recovery.
+    let url = format!("http://{address}/recovery/progress");
+    for _ in 0..50 {
+        if reqwest::get(&url).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("recovery progress server is not shut down after recovery");
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn aborting_recovery_if_main_node_root_hash_diverges(reorg: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let root_hashes = client.l1_batch_root_hashes.clone();
+    root_hashes.lock().unwrap().insert(
+        expected_status.l1_batch_number,
+        expected_status.l1_batch_root_hash,
+    );
+
+    let l1_batch_number = expected_status.l1_batch_number;
+    let object_store = ObjectStoreWithDelays::new(object_store, move |key| {
+        if !key.contains("storage_logs") {
+            return Duration::ZERO;
+        }
+        if reorg {
+            // Emulate a reorg at the snapshot boundary once storage logs recovery has started.
+            let mut root_hashes = root_hashes.lock().unwrap();
+            root_hashes.insert(l1_batch_number, H256::repeat_byte(0xfe));
+        }
+        Duration::from_millis(200)
+    });
+
+    let config = SnapshotsApplierConfig {
+        main_node_head_check_interval: Some(Duration::from_millis(20)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if reorg {
+        let err = result.unwrap_err();
+        assert_eq!(
+            CancellationReason::from_error(&err),
+            Some(CancellationReason::MainNodeDiverged {
+                l1_batch_number,
+                expected_root_hash: expected_status.l1_batch_root_hash,
+                root_hash: H256::repeat_byte(0xfe),
+            })
+        );
+        let err = format!("{err:#}");
+        assert!(err.contains("the main node has probably reorged"), "{err}");
+
+        let mut storage = pool.access_storage().await.unwrap();
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.storage_logs_chunks_processed, [false, false]);
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+#[tokio::test]
+async fn main_node_root_hash_check_tolerates_transient_errors() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    client.l1_batch_root_hashes.lock().unwrap().insert(
+        expected_status.l1_batch_number,
+        expected_status.l1_batch_root_hash,
+    );
+    client.root_hash_failures = AtomicUsize::new(3);
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.contains("storage_logs") {
+            Duration::from_millis(200)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        main_node_head_check_interval: Some(Duration::from_millis(20)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    assert_eq!(client.root_hash_failures.load(Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn aborting_recovery_if_main_node_misses_snapshot_l1_batch() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    // The main node doesn't report a root hash for the snapshot L1 batch.
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.contains("storage_logs") {
+            Duration::from_millis(500)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        main_node_head_check_interval: Some(Duration::from_millis(20)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    assert_eq!(
+        CancellationReason::from_error(&err),
+        Some(CancellationReason::MainNodeMissingL1Batch {
+            l1_batch_number: expected_status.l1_batch_number,
+        })
+    );
+}
+
+#[test]
+fn storage_logs_checksum_does_not_depend_on_parallelism() {
+    assert_eq!(storage_logs_checksum(&[], 4), H256::zero());
+
+    // Use a log count such that the last leaf is incomplete, and the number of leaves is odd.
+    let storage_logs = random_storage_logs(L1BatchNumber(1), 1, 4_096 * 5 + 100);
+    let serial_checksum = storage_logs_checksum(&storage_logs, 1);
+    for parallelism in [2, 3, 4, 8, 100] {
+        let parallel_checksum = storage_logs_checksum(&storage_logs, parallelism);
+        assert_eq!(
+            parallel_checksum, serial_checksum,
+            "parallelism={parallelism}"
+        );
+    }
+
+    // The checksum must depend on the order of logs.
+    let mut reordered_logs = storage_logs.clone();
+    reordered_logs.swap(0, storage_logs.len() - 1);
+    assert_ne!(storage_logs_checksum(&reordered_logs, 4), serial_checksum);
+    assert_ne!(
+        storage_logs_checksum(&storage_logs[1..], 1),
+        serial_checksum
+    );
+}
+
+#[test]
+fn state_checksum_does_not_depend_on_parallelism() {
+    assert_eq!(state_checksum(&[], 4), H256::zero());
+
+    // Use a log count such that the state consists of 3 segments, the last of which is incomplete.
+    let segment_len = checksum::STATE_CHECKSUM_SEGMENT_LEN;
+    let mut storage_logs = random_storage_logs(L1BatchNumber(1), 1, segment_len as u64 * 2 + 100);
+    storage_logs.sort_unstable_by_key(|log| log.key.hashed_key());
+    let serial_checksum = state_checksum(&storage_logs, 1);
+    for parallelism in [2, 3, 16, 100] {
+        assert_eq!(
+            state_checksum(&storage_logs, parallelism),
+            serial_checksum,
+            "parallelism={parallelism}"
+        );
+    }
+
+    // The state checksum must be a fold of ordered segment checksums.
+    let segment_checksums: Vec<_> = storage_logs
+        .chunks(segment_len)
+        .map(|segment| storage_logs_checksum(segment, 4))
+        .collect();
+    assert_eq!(segment_checksums.len(), 3);
+    assert_eq!(combine_checksums(&segment_checksums), serial_checksum);
+    let mut reordered_checksums = segment_checksums.clone();
+    reordered_checksums.swap(0, 1);
+    assert_ne!(combine_checksums(&reordered_checksums), serial_checksum);
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn running_acceptance_queries(failing_query: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    let known_log = all_snapshot_storage_logs.values().next().unwrap();
+
+    let mut acceptance_queries = vec![
+        AcceptanceQuery::StorageValue {
+            key: known_log.key,
+            expected_value: known_log.value,
+        },
+        AcceptanceQuery::StorageLogCount { range: 1..=100 },
+    ];
+    if failing_query {
+        acceptance_queries.push(AcceptanceQuery::StorageLogCount { range: 21..=100 });
+    }
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            acceptance_queries,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if failing_query {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("acceptance query #2"), "{err}");
+        assert!(
+            err.contains("20 storage logs are recovered, while the expected number is in 21..=100"),
+            "{err}"
+        );
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+#[tokio::test]
+async fn post_recovery_checks_are_skipped_after_passing() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let failing_config = || SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            acceptance_queries: vec![AcceptanceQuery::StorageLogCount { range: 21..=100 }],
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+
+    let err = failing_config()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("acceptance query #0"), "{err}");
+    let mut storage = pool.access_storage().await.unwrap();
+    let checks_passed = storage
+        .snapshot_recovery_dal()
+        .are_post_recovery_checks_passed()
+        .await
+        .unwrap();
+    assert!(!checks_passed);
+    drop(storage);
+
+    // Failed checks are re-run on restart.
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let mut storage = pool.access_storage().await.unwrap();
+    let checks_passed = storage
+        .snapshot_recovery_dal()
+        .are_post_recovery_checks_passed()
+        .await
+        .unwrap();
+    assert!(checks_passed);
+    drop(storage);
+
+    // Once checks have passed, they are not re-run on restart.
+    let outcome = failing_config()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+}
+
+#[test_casing(2, [CompressionFormat::Gzip, CompressionFormat::Zstd])]
+#[tokio::test]
+async fn compressing_object_store_roundtrip_with_different_levels(format: CompressionFormat) {
+    let inner_store = ObjectStoreFactory::mock().create_store().await;
+    let levels = match format {
+        CompressionFormat::Gzip => [(1, 0), (9, 0)],
+        CompressionFormat::Zstd => [(0, 1), (0, 19)],
+    };
+    let value = b"snapshot data ".repeat(1_000);
+
+    for (i, (gzip_level, zstd_level)) in levels.into_iter().enumerate() {
+        let config = CompressionConfig {
+            format,
+            gzip_level,
+            zstd_level,
+        };
+        let store = CompressingObjectStore::new(inner_store.clone(), config).unwrap();
+        let key = format!("object{i}");
+        store
+            .put_raw(Bucket::StorageSnapshot, &key, value.clone())
+            .await
+            .unwrap();
+
+        let compressed_value = inner_store
+            .get_raw(Bucket::StorageSnapshot, &key)
+            .await
+            .unwrap();
+        assert!(compressed_value.len() < value.len());
+        assert_eq!(
+            store.get_raw(Bucket::StorageSnapshot, &key).await.unwrap(),
+            value
+        );
+    }
+
+    // The format is detected on read regardless of the configuration of the reading store.
+    let reading_store =
+        CompressingObjectStore::new(inner_store.clone(), CompressionConfig::default()).unwrap();
+    for i in 0..levels.len() {
+        let key = format!("object{i}");
+        let read_value = reading_store
+            .get_raw(Bucket::StorageSnapshot, &key)
+            .await
+            .unwrap();
+        assert_eq!(read_value, value);
+    }
+
+    let invalid_config = CompressionConfig {
+        format,
+        gzip_level: 10,
+        ..CompressionConfig::default()
+    };
+    CompressingObjectStore::new(inner_store, invalid_config).unwrap_err();
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn checking_expected_state_checksum(mismatch: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let checksum = if mismatch {
+        H256::repeat_byte(0xee)
+    } else {
+        expected_state_checksum(&all_snapshot_storage_logs)
+    };
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            expected_state_checksum: Some(checksum),
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if mismatch {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("differs from the expected"), "{err}");
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    }
+}
+
+#[tokio::test]
+async fn state_checksum_check_detects_corrupted_storage_log() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            expected_state_checksum: Some(expected_state_checksum(&all_snapshot_storage_logs)),
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Emulate corruption by re-inserting applied storage logs with one of the values changed.
+    let mut corrupted_logs: Vec<_> = all_snapshot_storage_logs.values().cloned().collect();
+    corrupted_logs[0].value = H256::repeat_byte(0xff);
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .storage_logs_dal()
+        .rollback_storage_logs(expected_status.miniblock_number - 1)
+        .await
+        .unwrap();
+    storage
+        .storage_logs_dal()
+        .insert_storage_logs_from_snapshot(expected_status.miniblock_number, &corrupted_logs)
+        .await
+        .unwrap();
+    // Post-recovery checks are not re-run once they have passed.
+    storage
+        .snapshot_recovery_dal()
+        .reset_post_recovery_checks_for_tests()
+        .await;
+    drop(storage);
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            expected_state_checksum: Some(expected_state_checksum(&all_snapshot_storage_logs)),
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("differs from the expected"), "{err}");
+}
+
+#[tokio::test]
+async fn refusing_recovery_with_ambiguous_recovery_status() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let other_status = SnapshotRecoveryStatus {
+        l1_batch_number: expected_status.l1_batch_number + 1,
+        ..mock_recovery_status()
+    };
+    for status in [&expected_status, &other_status] {
+        storage
+            .snapshot_recovery_dal()
+            .insert_initial_recovery_status(status)
+            .await
+            .unwrap();
+    }
+    drop(storage);
+
+    let object_store = ObjectStoreWithErrors::new(object_store, |_| unreachable!());
+    let err = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.contains("snapshot recovery status is ambiguous"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn prewarming_relations_after_applying_chunks() {
+    let pool = ConnectionPool::test_pool().await;
+    // `pg_prewarm` is a contrib extension and may be unavailable in the test Postgres; prewarming must not
+    // affect recovery in this case.
+    let has_prewarm = pool
+        .access_storage()
+        .await
+        .unwrap()
+        .system_dal()
+        .create_extension_for_tests("pg_prewarm")
+        .await
+        .is_ok();
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let debug_handle = SnapshotsApplierDebugHandle::default();
+    let config = SnapshotsApplierConfig {
+        prewarm_relations: vec!["storage_logs".to_owned()],
+        debug_handle: debug_handle.clone(),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let prewarmed_blocks = debug_handle.debug_state().prewarmed_blocks;
+    if has_prewarm {
+        assert!(prewarmed_blocks > 0);
+    } else {
+        assert_eq!(prewarmed_blocks, 0);
+    }
+}
+
+#[tokio::test]
+async fn recovery_deadline_is_reported_as_cancellation_reason() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let object_store = ObjectStoreWithDelays::new(object_store, |key| {
+        if key.contains("storage_logs") {
+            Duration::from_secs(3_600)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let max_recovery_duration = Duration::from_millis(100);
+    let config = SnapshotsApplierConfig {
+        max_recovery_duration: Some(max_recovery_duration),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    assert_eq!(
+        CancellationReason::from_error(&err),
+        Some(CancellationReason::Deadline {
+            max_recovery_duration
+        })
+    );
+    let err = format!("{err:#}");
+    assert!(err.contains("snapshot recovery cancelled"), "{err}");
+
+    // Errors not caused by cancellation must not have a reason.
+    let err = anyhow::anyhow!("snapshot is corrupted");
+    assert_eq!(CancellationReason::from_error(&err), None);
+}
+
+/// Returns the sum of values for all samples of the specified metric in the encoded registry.
+fn encoded_metric_value(encoded_registry: &str, sample_name: &str) -> f64 {
+    encoded_registry
+        .lines()
+        .filter(|line| line.starts_with(sample_name))
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .sum()
+}
+
+#[tokio::test]
+async fn chunk_size_metrics_are_populated() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let chunk_count = expected_status.storage_logs_chunks_processed.len();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let mut chunks_byte_size = 0;
+    for chunk_id in 0..chunk_count as u64 {
+        let key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        });
+        let bytes = object_store
+            .get_raw(SnapshotStorageLogsChunk::BUCKET, &key)
+            .await
+            .unwrap();
+        chunks_byte_size += bytes.len();
+    }
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Metrics are global and may be updated by other tests running concurrently, so we only check lower bounds.
+    let registry = vise::MetricsCollection::default().collect();
+    let mut encoded_registry = String::new();
+    registry
+        .encode(&mut encoded_registry, vise::Format::OpenMetrics)
+        .unwrap();
+
+    let observed_chunk_count = encoded_metric_value(
+        &encoded_registry,
+        "snapshots_applier_storage_logs_chunk_log_count_count",
+    );
+    assert!(
+        observed_chunk_count >= chunk_count as f64,
+        "{encoded_registry}"
+    );
+    let observed_log_count = encoded_metric_value(
+        &encoded_registry,
+        "snapshots_applier_storage_logs_chunk_log_count_sum",
+    );
+    assert!(
+        observed_log_count >= all_snapshot_storage_logs.len() as f64,
+        "{encoded_registry}"
+    );
+    // All chunks created by `prepare_clients()` have 10 storage logs.
+    let small_chunk_count = encoded_metric_value(
+        &encoded_registry,
+        "snapshots_applier_storage_logs_chunk_log_count_bucket{le=\"16.0\"}",
+    );
+    assert!(
+        small_chunk_count >= chunk_count as f64,
+        "{encoded_registry}"
+    );
+
+    let observed_byte_size = encoded_metric_value(
+        &encoded_registry,
+        "snapshots_applier_storage_logs_chunk_size_bytes_sum",
+    );
+    assert!(
+        observed_byte_size >= chunks_byte_size as f64,
+        "{encoded_registry}"
+    );
+}
+
+const CUSTOM_FORMAT_MAGIC: &[u8] = b"ZKS1";
+const CUSTOM_FORMAT_VERSION: u32 = 42;
+
+fn decode_custom_format<T: StoredObject>(
+    bytes: Vec<u8>,
+    _limits: &DecodingLimits,
+) -> anyhow::Result<T> {
+    let payload = bytes
+        .strip_prefix(CUSTOM_FORMAT_MAGIC)
+        .context("missing magic bytes")?;
+    T::deserialize(payload.to_vec()).map_err(|err| anyhow::anyhow!(err))
+}
+
+async fn convert_to_custom_format(object_store: &dyn ObjectStore, bucket: Bucket, key: &str) {
+    let bytes = object_store.get_raw(bucket, key).await.unwrap();
+    let converted_bytes = [CUSTOM_FORMAT_MAGIC, &bytes].concat();
+    object_store
+        .put_raw(bucket, key, converted_bytes)
+        .await
+        .unwrap();
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn decoding_snapshot_objects_via_registry(register_format: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+
+    let l1_batch_number = expected_status.l1_batch_number;
+    let factory_deps_key = SnapshotFactoryDependencies::encode_key(l1_batch_number);
+    convert_to_custom_format(
+        &*object_store,
+        SnapshotFactoryDependencies::BUCKET,
+        &factory_deps_key,
+    )
+    .await;
+    for chunk_id in 0..expected_status.storage_logs_chunks_processed.len() as u64 {
+        let chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id,
+        });
+        convert_to_custom_format(&*object_store, SnapshotStorageLogsChunk::BUCKET, &chunk_key)
+            .await;
+    }
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .format_version = CUSTOM_FORMAT_VERSION;
+
+    let mut decoder_registry = SnapshotDecoderRegistry::default();
+    assert!(!decoder_registry.supports(CUSTOM_FORMAT_VERSION, SnapshotObjectType::StorageLogsChunk));
+    if register_format {
+        decoder_registry
+            .register_storage_logs_chunk_decoder(CUSTOM_FORMAT_VERSION, decode_custom_format)
+            .register_factory_deps_decoder(CUSTOM_FORMAT_VERSION, decode_custom_format);
+        assert!(
+            decoder_registry.supports(CUSTOM_FORMAT_VERSION, SnapshotObjectType::StorageLogsChunk)
+        );
+        assert!(decoder_registry.supports(
+            CUSTOM_FORMAT_VERSION,
+            SnapshotObjectType::FactoryDependencies
+        ));
+
+        let chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id: 0,
+        });
+        let bytes = object_store
+            .get_raw(SnapshotStorageLogsChunk::BUCKET, &chunk_key)
+            .await
+            .unwrap();
+        let decoder = decoder_registry
+            .storage_logs_chunk_decoder(CUSTOM_FORMAT_VERSION)
+            .unwrap();
+        let chunk = decoder(bytes, &DecodingLimits::default()).unwrap();
+        for log in &chunk.storage_logs {
+            assert_eq!(all_snapshot_storage_logs[&log.key.hashed_key()], *log);
+        }
+    }
+
+    let config = SnapshotsApplierConfig {
+        decoder_registry,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    if register_format {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        let mut storage = pool.access_storage().await.unwrap();
+        let all_storage_logs = storage
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await;
+        assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    } else {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(
+            err.contains("data format version 42, which is not supported"),
+            "{err}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn recovering_chunks_with_delta_encoded_indices() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+    for chunk_id in 0..expected_status.storage_logs_chunks_processed.len() as u64 {
+        let chunk_key = SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        };
+        let mut chunk: SnapshotStorageLogsChunk = object_store.get(chunk_key).await.unwrap();
+        let mut prev_index = 0;
+        for log in &mut chunk.storage_logs {
+            let absolute_index = log.enumeration_index;
+            log.enumeration_index = absolute_index.wrapping_sub(prev_index);
+            prev_index = absolute_index;
+        }
+        object_store.put(chunk_key, &chunk).await.unwrap();
+    }
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .format_version = SnapshotStorageLogsChunk::DELTA_ENCODED_INDICES_FORMAT_VERSION;
+
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &client, &object_store)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_initial_writes = storage
+        .storage_logs_dedup_dal()
+        .dump_all_initial_writes_for_tests()
+        .await;
+    assert_eq!(all_initial_writes.len(), all_snapshot_storage_logs.len());
+    for initial_write in all_initial_writes {
+        let snapshot_log = &all_snapshot_storage_logs[&initial_write.hashed_key];
+        assert_eq!(initial_write.index, snapshot_log.enumeration_index);
+    }
+}
+
+#[test]
+fn converting_variable_size_storage_values() {
+    let limits = DecodingLimits::default();
+    let hashed_key = H256::repeat_byte(1);
+    let value = limits.storage_value(hashed_key, &[1, 2]).unwrap();
+    assert_eq!(value, H256::from_low_u64_be(0x0102));
+    let value = limits.storage_value(hashed_key, &[]).unwrap();
+    assert_eq!(value, H256::zero());
+
+    let err = limits.storage_value(hashed_key, &[0; 33]).unwrap_err();
+    assert_eq!(err.size, 33);
+    assert_eq!(err.max_size, 32);
+
+    // The limit cannot exceed the `StorageValue` size.
+    let limits = DecodingLimits {
+        max_storage_value_size: 64,
+        ..DecodingLimits::default()
+    };
+    let err = limits.storage_value(hashed_key, &[0; 33]).unwrap_err();
+    assert_eq!(err.max_size, 32);
+
+    let limits = DecodingLimits {
+        max_storage_value_size: 4,
+        ..DecodingLimits::default()
+    };
+    limits.storage_value(hashed_key, &[1; 4]).unwrap();
+    let err = limits.storage_value(hashed_key, &[1; 5]).unwrap_err();
+    assert_eq!(err.hashed_key, hashed_key);
+    assert_eq!(err.size, 5);
+    assert_eq!(err.max_size, 4);
+}
+
+/// Storage log in a data format with variable-size values.
+#[derive(Debug, Serialize, serde::Deserialize)]
+struct VariableSizeStorageLog {
+    address: Address,
+    key: H256,
+    value: Vec<u8>,
+    l1_batch_number_of_initial_write: L1BatchNumber,
+    enumeration_index: u64,
+}
+
+const VARIABLE_SIZE_VALUES_FORMAT: u32 = 2;
+
+fn decode_variable_size_chunk(
+    bytes: Vec<u8>,
+    limits: &DecodingLimits,
+) -> anyhow::Result<SnapshotStorageLogsChunk> {
+    let logs: Vec<VariableSizeStorageLog> = serde_json::from_slice(&bytes)?;
+    let storage_logs = logs.into_iter().map(|log| {
+        let key = StorageKey::new(AccountTreeId::new(log.address), log.key);
+        Ok(SnapshotStorageLog {
+            key,
+            value: limits.storage_value(key.hashed_key(), &log.value)?,
+            l1_batch_number_of_initial_write: log.l1_batch_number_of_initial_write,
+            enumeration_index: log.enumeration_index,
+        })
+    });
+    Ok(SnapshotStorageLogsChunk {
+        storage_logs: storage_logs.collect::<anyhow::Result<_>>()?,
+        proofs: vec![],
+    })
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn rejecting_oversized_storage_values(oversized_value: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+
+    let l1_batch_number = expected_status.l1_batch_number;
+    for chunk_id in 0..expected_status.storage_logs_chunks_processed.len() as u64 {
+        let key = SnapshotStorageLogsStorageKey {
+            l1_batch_number,
+            chunk_id,
+        };
+        let chunk: SnapshotStorageLogsChunk = object_store.get(key).await.unwrap();
+        let mut logs: Vec<_> = chunk
+            .storage_logs
+            .iter()
+            .map(|log| VariableSizeStorageLog {
+                address: *log.key.address(),
+                key: *log.key.key(),
+                // Strip leading zero bytes to get a variable-size value.
+                value: log
+                    .value
+                    .as_bytes()
+                    .iter()
+                    .copied()
+                    .skip_while(|&byte| byte == 0)
+                    .collect(),
+                l1_batch_number_of_initial_write: log.l1_batch_number_of_initial_write,
+                enumeration_index: log.enumeration_index,
+            })
+            .collect();
+        if oversized_value && chunk_id == 0 {
+            logs[0].value = vec![1; 33];
+        }
+        let bytes = serde_json::to_vec(&logs).unwrap();
+        object_store
+            .put_raw(
+                SnapshotStorageLogsChunk::BUCKET,
+                &SnapshotStorageLogsChunk::encode_key(key),
+                bytes,
+            )
+            .await
+            .unwrap();
+    }
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .format_version = VARIABLE_SIZE_VALUES_FORMAT;
+
+    let mut decoder_registry = SnapshotDecoderRegistry::default();
+    decoder_registry
+        .register_storage_logs_chunk_decoder(
+            VARIABLE_SIZE_VALUES_FORMAT,
+            decode_variable_size_chunk,
+        )
+        .register_factory_deps_decoder(VARIABLE_SIZE_VALUES_FORMAT, |bytes, _| {
+            SnapshotFactoryDependencies::deserialize(bytes).map_err(|err| anyhow::anyhow!(err))
+        });
+    let config = SnapshotsApplierConfig {
+        decoder_registry,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    assert_eq!(config.max_storage_value_size, 32);
+    let result = config.run(&pool, &client, &object_store).await;
+
+    if oversized_value {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(
+            err.contains("has 33 bytes, while at most 32 bytes are allowed"),
+            "{err}"
+        );
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        let mut storage = pool.access_storage().await.unwrap();
+        let all_storage_logs = storage
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await;
+        assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+        for db_log in all_storage_logs {
+            let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+            assert_eq!(db_log.value, expected_log.value);
+        }
+    }
+}
+
+#[tokio::test]
+async fn applying_snapshot_via_staging_tables() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    let last_chunk_id = expected_status.storage_logs_chunks_processed.len() as u64 - 1;
+    let last_chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: last_chunk_id,
+    });
+    let object_store_with_errors = ObjectStoreWithErrors::new(object_store.clone(), move |key| {
+        if key == last_chunk_key {
+            Err(ObjectStoreError::KeyNotFound("not found".into()))
+        } else {
+            Ok(())
+        }
+    });
+
+    // Process chunks one by one, so that all chunks but the last one are applied before the failure.
+    let config = SnapshotsApplierConfig {
+        apply_via_staging_tables: true,
+        chunk_order: StorageLogsChunkOrder::Sequential,
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    config
+        .run(&pool, &client, &object_store_with_errors)
+        .await
+        .unwrap_err();
+
+    // All chunks except for the last one are applied, but the live tables are not modified.
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    let processed_chunk_count = status
+        .storage_logs_chunks_processed
+        .iter()
+        .filter(|&&is_processed| is_processed)
+        .count();
+    assert_eq!(processed_chunk_count, last_chunk_id as usize);
+    assert!(storage
+        .snapshot_recovery_dal()
+        .staging_tables_exist()
+        .await
+        .unwrap());
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert!(all_storage_logs.is_empty());
+    let all_initial_writes = storage
+        .storage_logs_dedup_dal()
+        .dump_all_initial_writes_for_tests()
+        .await;
+    assert!(all_initial_writes.is_empty());
+
+    let config = SnapshotsApplierConfig {
+        apply_via_staging_tables: true,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    assert!(!storage
+        .snapshot_recovery_dal()
+        .staging_tables_exist()
+        .await
+        .unwrap());
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in all_storage_logs {
+        let expected_log = &all_snapshot_storage_logs[&db_log.hashed_key];
+        assert_eq!(db_log.value, expected_log.value);
+        assert_eq!(db_log.miniblock_number, expected_status.miniblock_number);
+    }
+    let all_initial_writes = storage
+        .storage_logs_dedup_dal()
+        .dump_all_initial_writes_for_tests()
+        .await;
+    assert_eq!(all_initial_writes.len(), all_snapshot_storage_logs.len());
+}
+
+#[tokio::test]
+async fn staging_tables_are_incompatible_with_progress_reconciliation() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let config = SnapshotsApplierConfig {
+        apply_via_staging_tables: true,
+        reconcile_progress: true,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("`reconcile_progress`"), "{err}");
+}
+
+#[tokio::test]
+async fn accumulating_per_account_stats() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let sink = AccountStatsSink::default();
+    let config = SnapshotsApplierConfig {
+        sinks: SinksConfig {
+            storage_logs_sinks: vec![Box::new(sink.clone())],
+            ..SinksConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut expected_counts = HashMap::<_, u64>::new();
+    for log in all_snapshot_storage_logs.values() {
+        *expected_counts.entry(*log.key.account()).or_default() += 1;
+    }
+    assert_eq!(sink.log_counts(), expected_counts);
+    assert_eq!(sink.chunk_count(), 2);
+    // 20 logs with enumeration indices 1..=20 are distributed among 3 accounts.
+    assert_eq!(
+        expected_counts,
+        HashMap::from([
+            (mock_account(0), 6),
+            (mock_account(1), 7),
+            (mock_account(2), 7)
+        ])
+    );
+
+    // Re-applying a chunk doesn't change stats.
+    let chunk: Vec<_> = all_snapshot_storage_logs.values().cloned().collect();
+    sink.write_storage_logs_chunk(&expected_status, 0, &chunk)
+        .await
+        .unwrap();
+    assert_eq!(sink.log_counts(), expected_counts);
+}
+
+#[cfg(feature = "chaos")]
+#[tokio::test]
+async fn per_account_stats_are_not_affected_by_commit_failures() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let sink = AccountStatsSink::default();
+    let config = SnapshotsApplierConfig {
+        retry_count: 100,
+        retry_backoff_multiplier: 1.0,
+        failure_injection: FailureInjection {
+            db_commit_failure_probability: 0.5,
+            ..FailureInjection::default()
+        },
+        sinks: SinksConfig {
+            storage_logs_sinks: vec![Box::new(sink.clone())],
+            ..SinksConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    // Chunks re-delivered after a commit failure must be counted once.
+    let mut expected_counts = HashMap::<_, u64>::new();
+    for log in all_snapshot_storage_logs.values() {
+        *expected_counts.entry(*log.key.account()).or_default() += 1;
+    }
+    assert_eq!(sink.log_counts(), expected_counts);
+    assert_eq!(sink.chunk_count(), 2);
+}
+
+#[test_casing(2, [NonFinalizedSnapshotPolicy::Refuse, NonFinalizedSnapshotPolicy::Allow])]
+#[tokio::test]
+async fn applying_snapshot_for_non_finalized_l1_batch(policy: NonFinalizedSnapshotPolicy) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let snapshot_commitment = client
+        .fetch_newest_snapshot_response
+        .as_ref()
+        .unwrap()
+        .last_l1_batch_with_metadata
+        .metadata
+        .commitment;
+    let l1_client = MockL1Client {
+        l1_batch_commitments: HashMap::from([(
+            expected_status.l1_batch_number,
+            snapshot_commitment,
+        )]),
+        non_finalized_l1_batches: HashSet::from([expected_status.l1_batch_number]),
+    };
+
+    let config = SnapshotsApplierConfig {
+        l1_client: Some(Box::new(l1_client)),
+        non_finalized_snapshot_policy: policy,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    match policy {
+        NonFinalizedSnapshotPolicy::Refuse => {
+            let err = format!("{:#}", result.unwrap_err());
+            assert!(err.contains("is not finalized on L1"), "{err}");
+            assert_eq!(status, None);
+        }
+        NonFinalizedSnapshotPolicy::Allow => {
+            assert_matches!(result, Ok(SnapshotsApplierOutcome::Ok));
+            assert_eq!(status, Some(expected_status));
+        }
+    }
+}
+
+#[test_casing(3, [None, Some(IsolationLevel::RepeatableRead), Some(IsolationLevel::Serializable)])]
+#[tokio::test]
+async fn configuring_chunk_transaction_isolation_level(level: Option<IsolationLevel>) {
+    let pool = ConnectionPool::test_pool().await;
+    // Serialization failures for concurrent chunk transactions are retried, but we'd like to avoid them in the test.
+    let config = SnapshotsApplierConfig {
+        chunk_transaction_isolation_level: level,
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let mut transaction = SnapshotsApplier::start_chunk_transaction(&config, &mut storage, 0)
+        .await
+        .unwrap();
+    let actual_level = transaction.isolation_level().await.unwrap();
+    assert_eq!(actual_level, level.unwrap_or(IsolationLevel::ReadCommitted));
+    drop(transaction);
+    drop(storage);
+
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let mut storage = pool.access_storage().await.unwrap();
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+/// Mock standby node receiving chunks forwarded by `StandbyForwardingSink` until the connection is closed.
+async fn receive_standby_chunks(listener: tokio::net::TcpListener) -> Vec<StandbyChunk> {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut receiver = StandbyChunkReceiver::default();
+    let mut chunks = vec![];
+    while let Some(chunk) = receiver.receive_chunk(&mut stream).await.unwrap() {
+        receiver.acknowledge(&mut stream, &chunk).await.unwrap();
+        chunks.push(chunk);
+    }
+    assert_eq!(receiver.received_chunk_count(), chunks.len());
+    chunks
+}
+
+#[tokio::test]
+async fn forwarding_chunks_to_standby_node() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let standby_address = listener.local_addr().unwrap();
+    let receiver_task = tokio::spawn(receive_standby_chunks(listener));
+
+    let config = SnapshotsApplierConfig {
+        chunk_order: StorageLogsChunkOrder::Sequential,
+        sinks: SinksConfig {
+            standby_address: Some(standby_address),
+            ..SinksConfig::default()
+        },
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    // The connection is closed once the config (and thus the forwarding sink) is dropped.
+    let received_chunks = receiver_task.await.unwrap();
+
+    let chunk_ids: Vec<_> = received_chunks.iter().map(|chunk| chunk.chunk_id).collect();
+    assert_eq!(chunk_ids, [0, 1]);
+    let mut received_log_count = 0;
+    for StandbyChunk {
+        l1_batch_number,
+        chunk,
+        ..
+    } in &received_chunks
+    {
+        assert_eq!(*l1_batch_number, expected_status.l1_batch_number);
+        for log in &chunk.storage_logs {
+            assert_eq!(all_snapshot_storage_logs[&log.key.hashed_key()], *log);
+        }
+        received_log_count += chunk.storage_logs.len();
+    }
+    assert_eq!(received_log_count, all_snapshot_storage_logs.len());
+}
+
+#[tokio::test]
+async fn standby_receiver_ignores_resent_chunks() {
+    let expected_status = mock_recovery_status();
+    let storage_logs = random_storage_logs(expected_status.l1_batch_number, 1, 10);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let sink = StandbyForwardingSink::new(listener.local_addr().unwrap());
+    let receiver_task = tokio::spawn(receive_standby_chunks(listener));
+
+    // Emulate re-sending chunks, e.g. after a failed commit of the chunk transaction.
+    for chunk_id in [0, 1, 0, 1, 1] {
+        sink.write_storage_logs_chunk(&expected_status, chunk_id, &storage_logs)
+            .await
+            .unwrap();
+    }
+    // Chunks with the same ID for another snapshot are not duplicates.
+    let other_status = SnapshotRecoveryStatus {
+        l1_batch_number: expected_status.l1_batch_number + 1,
+        ..mock_recovery_status()
+    };
+    sink.write_storage_logs_chunk(&other_status, 0, &storage_logs)
+        .await
+        .unwrap();
+    drop(sink);
+
+    let received_chunks = receiver_task.await.unwrap();
+    let chunk_keys: Vec<_> = received_chunks
+        .iter()
+        .map(|chunk| (chunk.l1_batch_number, chunk.chunk_id))
+        .collect();
+    assert_eq!(
+        chunk_keys,
+        [
+            (expected_status.l1_batch_number, 0),
+            (expected_status.l1_batch_number, 1),
+            (other_status.l1_batch_number, 0)
+        ]
+    );
+    for chunk in &received_chunks {
+        assert_eq!(chunk.chunk.storage_logs, storage_logs);
+    }
+}
+
+#[test]
+fn parsing_retry_after_header() {
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    let now = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777);
+    assert_eq!(
+        parse_retry_after("120", now),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+    assert_eq!(
+        parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(
+        parse_retry_after("Sun, 06 Nov 1994 08:51:07 GMT", now),
+        Some(Duration::from_secs(90))
+    );
+    assert_eq!(
+        parse_retry_after("Tue, 29 Feb 2000 00:00:00 GMT", now),
+        Some(Duration::from_secs(951_782_400 - 784_111_777))
+    );
+    // Dates in the past are treated as a zero delay.
+    assert_eq!(
+        parse_retry_after("Thu, 01 Jan 1970 00:00:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+
+    for invalid_value in [
+        "",
+        "-1",
+        "1.5",
+        "soon",
+        "Sun, 06 Nov 1994 08:49:37",
+        "Sun, 06 Foo 1994 08:49:37 GMT",
+        "Sun, 32 Nov 1994 08:49:37 GMT",
+        "Sun, 06 Nov 1994 24:00:00 GMT",
+        "Sunday, 06-Nov-94 08:49:37 GMT",
+    ] {
+        assert_eq!(
+            parse_retry_after(invalid_value, now),
+            None,
+            "{invalid_value}"
+        );
+    }
+}
+
+/// Serves HTTP requests, responding to the first request with HTTP 429 and a `Retry-After` header,
+/// and to subsequent requests with the provided body.
+#[cfg(feature = "ipfs")]
+async fn serve_throttling_http(
+    listener: tokio::net::TcpListener,
+    retry_after: &str,
+    body: Vec<u8>,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    for request_id in 0.. {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![];
+        while !request.ends_with(b"\r\n\r\n") {
+            let mut buffer = [0_u8; 1_024];
+            let bytes_read = stream.read(&mut buffer).await.unwrap();
+            assert!(
+                bytes_read > 0,
+                "connection closed before the request is read"
+            );
+            request.extend_from_slice(&buffer[..bytes_read]);
+        }
+
+        let response = if request_id == 0 {
+            format!(
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: {retry_after}\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .into_bytes()
+        } else {
+            let headers = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            [headers.as_bytes(), &body].concat()
+        };
+        stream.write_all(&response).await.unwrap();
+        stream.shutdown().await.ok();
+    }
+}
+
+#[cfg(feature = "ipfs")]
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn retrying_throttled_requests(respect_retry_after: bool) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let body = b"object contents".to_vec();
+    let server_task = tokio::spawn(serve_throttling_http(listener, "1", body.clone()));
+
+    let object_key = "snapshot_l1_batch_1_factory_deps.proto.gzip";
+    let cids = HashMap::from([(
+        IpfsObjectStore::object_key(Bucket::StorageSnapshot, object_key),
+        "cid".to_owned(),
+    )]);
+    let ipfs_store = IpfsObjectStore::new(HttpIpfsGateway::new(format!("http://{address}")), cids);
+    let config = SnapshotsApplierConfig {
+        respect_retry_after,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let object_store = RetryingObjectStore::new(&ipfs_store, &config, None);
+
+    let started_at = Instant::now();
+    let bytes = object_store
+        .get_raw(Bucket::StorageSnapshot, object_key)
+        .await
+        .unwrap();
+    let elapsed = started_at.elapsed();
+    assert_eq!(bytes, body);
+    if respect_retry_after {
+        assert!(elapsed >= Duration::from_secs(1), "{elapsed:?}");
+    } else {
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
+    server_task.abort();
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StoragePrefixCase {
+    Correct,
+    Mismatched,
+    MissingObject,
+}
+
+#[test_casing(3, [
+    StoragePrefixCase::Correct,
+    StoragePrefixCase::Mismatched,
+    StoragePrefixCase::MissingObject,
+])]
+#[tokio::test]
+async fn checking_storage_prefix(case: StoragePrefixCase) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    let bucket = SnapshotStorageLogsChunk::BUCKET;
+    let store_prefix = object_store.storage_prefix_raw(bucket);
+    let header_prefix = match case {
+        StoragePrefixCase::Mismatched => format!("{store_prefix}_v2"),
+        StoragePrefixCase::Correct | StoragePrefixCase::MissingObject => store_prefix.clone(),
+    };
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    for chunk in &mut header.storage_logs_chunks {
+        let key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id: chunk.chunk_id,
+        });
+        chunk.filepath = format!("{header_prefix}/{key}");
+    }
+    let first_chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 0,
+    });
+    if matches!(case, StoragePrefixCase::MissingObject) {
+        object_store
+            .remove_raw(bucket, &first_chunk_key)
+            .await
+            .unwrap();
+    }
+
+    let config = SnapshotsApplierConfig {
+        verification: VerificationConfig {
+            check_storage_prefix: true,
+            ..VerificationConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+
+    let expected_full_key = format!("`{store_prefix}/{first_chunk_key}`");
+    match case {
+        StoragePrefixCase::Correct => {
+            assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+            assert_eq!(status.unwrap(), expected_status);
+        }
+        StoragePrefixCase::Mismatched => {
+            let err = format!("{:#}", result.unwrap_err());
+            assert!(
+                err.contains("doesn't resolve under the object store prefix"),
+                "{err}"
+            );
+            assert!(err.contains(&expected_full_key), "{err}");
+            // The check should fail before anything is persisted.
+            assert!(status.is_none(), "{status:?}");
+        }
+        StoragePrefixCase::MissingObject => {
+            let err = format!("{:#}", result.unwrap_err());
+            assert!(err.contains("not found in the object store"), "{err}");
+            assert!(err.contains(&expected_full_key), "{err}");
+            assert!(status.is_none(), "{status:?}");
+        }
+    }
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn escalating_warnings_to_errors(fail_on_warnings: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.protocol_version = ProtocolVersionId::next();
+    let (object_store, mut client, _) = prepare_clients(&expected_status).await;
+    client
+        .fetch_newest_snapshot_response
+        .as_mut()
+        .unwrap()
+        .last_l1_batch_with_metadata
+        .header
+        .protocol_version = Some(expected_status.protocol_version);
+
+    // Applying a snapshot with a newer protocol version is a warning-level condition if it's allowed by the config.
+    let config = SnapshotsApplierConfig {
+        allow_newer_protocol_version: true,
+        fail_on_warnings,
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let result = config.run(&pool, &client, &object_store).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    if fail_on_warnings {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("newer than the latest version"), "{err}");
+        assert!(err.contains("`fail_on_warnings`"), "{err}");
+        assert_eq!(status, None);
+    } else {
+        assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+        assert_eq!(status.unwrap(), expected_status);
+    }
+}
+
+#[cfg(feature = "clickhouse")]
+#[tokio::test]
+async fn mirroring_storage_logs_into_clickhouse() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let clickhouse_client = Arc::new(utils::MockClickHouseClient::default());
+    let sink = ClickHouseStorageLogsSink::new(clickhouse_client.clone(), "storage_logs_mirror")
+        .with_batch_size(4);
+    let config = SnapshotsApplierConfig {
+        sinks: SinksConfig {
+            storage_logs_sinks: vec![Box::new(sink)],
+            ..SinksConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let batches = clickhouse_client.batches();
+    // Each of 2 chunks with 10 storage logs is split into batches of 4, 4 and 2 rows.
+    let mut batch_sizes: Vec<_> = batches.iter().map(|(_, rows)| rows.len()).collect();
+    batch_sizes.sort_unstable();
+    assert_eq!(batch_sizes, [2, 2, 4, 4, 4, 4]);
+
+    let mut mirrored_hashed_keys = HashSet::new();
+    for (table, rows) in &batches {
+        assert_eq!(table, "storage_logs_mirror");
+        for row in rows {
+            let log = &all_snapshot_storage_logs[&row.hashed_key];
+            assert_eq!(row.l1_batch_number, expected_status.l1_batch_number.0);
+            assert_eq!(row.address, *log.key.address());
+            assert_eq!(row.key, *log.key.key());
+            assert_eq!(row.value, log.value);
+            assert_eq!(
+                row.l1_batch_number_of_initial_write,
+                log.l1_batch_number_of_initial_write.0
+            );
+            assert_eq!(row.enumeration_index, log.enumeration_index);
+            assert!(mirrored_hashed_keys.insert(row.hashed_key));
+        }
+    }
+    assert_eq!(mirrored_hashed_keys.len(), all_snapshot_storage_logs.len());
+
+    // Re-delivering a chunk (e.g., after a failed commit) must not lead to duplicate rows.
+    let chunk_logs: Vec<_> = batches
+        .iter()
+        .flat_map(|(_, rows)| rows)
+        .filter(|row| row.chunk_id == 0)
+        .map(|row| all_snapshot_storage_logs[&row.hashed_key].clone())
+        .collect();
+    let sink = ClickHouseStorageLogsSink::new(clickhouse_client.clone(), "storage_logs_mirror")
+        .with_batch_size(4);
+    sink.write_storage_logs_chunk(&expected_status, 0, &chunk_logs)
+        .await
+        .unwrap();
+    assert_eq!(clickhouse_client.batches().len(), batches.len());
+}
+
+#[tokio::test]
+async fn shutdown_commits_in_flight_chunks_within_grace_period() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    let (stop_sender, stop_receiver) = watch::channel(false);
+    let chunk_key = |chunk_id| {
+        SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+            l1_batch_number: expected_status.l1_batch_number,
+            chunk_id,
+        })
+    };
+    let (fast_chunk_key, slow_chunk_key) = (chunk_key(0), chunk_key(1));
+    let object_store = ObjectStoreWithDelays::new(object_store, move |key| {
+        if key == fast_chunk_key {
+            Duration::from_millis(200)
+        } else if key == slow_chunk_key {
+            // Request shutdown once both chunks are in flight.
+            stop_sender.send_replace(true);
+            Duration::from_secs(30)
+        } else {
+            Duration::ZERO
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        chunk_order: StorageLogsChunkOrder::Sequential,
+        stop_receiver: Some(stop_receiver),
+        shutdown_grace_period: Duration::from_secs(2),
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(2),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let started_at = Instant::now();
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    assert!(started_at.elapsed() < Duration::from_secs(30));
+    assert_matches!(
+        CancellationReason::from_error(&err),
+        Some(CancellationReason::Shutdown { grace_period }) if grace_period == Duration::from_secs(2)
+    );
+
+    // The chunk committed within the grace period must be persisted; the other one must be rolled back.
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.storage_logs_chunks_processed, [true, false]);
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len() / 2);
+}
+
+#[test_casing(6, [
+    StateRelation::Equal,
+    StateRelation::Superset,
+    StateRelation::Subset,
+    StateRelation::Disjoint,
+    StateRelation::Overlapping,
+    StateRelation::OverlappingWithConflicts,
+])]
+#[tokio::test]
+async fn comparing_snapshot_with_state(relation: StateRelation) {
+    let pool = ConnectionPool::test_pool().await;
+    let status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&status).await;
+    let header = client.fetch_newest_snapshot_response.unwrap();
+
+    let mut snapshot_logs: Vec<_> = all_snapshot_storage_logs.into_values().collect();
+    snapshot_logs.sort_unstable_by_key(|log| log.enumeration_index);
+    let extra_logs = random_storage_logs(status.l1_batch_number, 100, 5);
+    let (state_logs, expected_conflicts) = match relation {
+        StateRelation::Equal => (snapshot_logs.clone(), vec![]),
+        StateRelation::Superset => (snapshot_logs[..15].to_vec(), vec![]),
+        StateRelation::Subset => ([snapshot_logs.clone(), extra_logs].concat(), vec![]),
+        StateRelation::Disjoint => (extra_logs, vec![]),
+        StateRelation::Overlapping => ([&snapshot_logs[..10], &extra_logs].concat(), vec![]),
+        StateRelation::OverlappingWithConflicts => {
+            let mut state_logs = snapshot_logs.clone();
+            state_logs[3].value = H256::repeat_byte(0xff);
+            let conflict = StateConflict {
+                hashed_key: state_logs[3].key.hashed_key(),
+                snapshot_value: snapshot_logs[3].value,
+                state_value: H256::repeat_byte(0xff),
+            };
+            (state_logs, vec![conflict])
+        }
+    };
+    // Values in the state must be compared with the latest values.
+    if let Some(log) = state_logs.first() {
+        let mut outdated_log = log.clone();
+        outdated_log.value = H256::repeat_byte(0xee);
+        let mut storage = pool.access_storage().await.unwrap();
+        storage
+            .storage_logs_dal()
+            .insert_storage_logs_from_snapshot(status.miniblock_number - 1, &[outdated_log])
+            .await
+            .unwrap();
+    }
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .storage_logs_dal()
+        .insert_storage_logs_from_snapshot(status.miniblock_number, &state_logs)
+        .await
+        .unwrap();
+
+    let comparison = SnapshotReader::new(object_store.as_ref(), &header)
+        .compare_with_state(&mut storage, 10)
+        .await
+        .unwrap();
+    assert_eq!(comparison.relation, relation);
+    assert_eq!(comparison.conflicts, expected_conflicts);
+    assert_eq!(
+        comparison.conflicting_key_count,
+        expected_conflicts.len() as u64
+    );
+    assert_eq!(
+        comparison.matching_key_count + comparison.conflicting_key_count,
+        state_logs
+            .iter()
+            .filter(|log| log.enumeration_index < 100)
+            .count() as u64
+    );
+    assert_eq!(
+        comparison.matching_key_count
+            + comparison.conflicting_key_count
+            + comparison.snapshot_only_key_count,
+        snapshot_logs.len() as u64
+    );
+    assert_eq!(
+        comparison.matching_key_count
+            + comparison.conflicting_key_count
+            + comparison.state_only_key_count,
+        state_logs.len() as u64
+    );
+}
+
+/// Assigns storage log chunks with even IDs to one locality, and chunks with odd IDs to another one.
+fn mock_chunk_locality(key: &str) -> String {
+    let chunk_id = key
+        .trim_end_matches(".proto.gzip")
+        .rsplit('_')
+        .next()
+        .unwrap();
+    let chunk_id: u64 = chunk_id.parse().unwrap();
+    if chunk_id % 2 == 0 { "even" } else { "odd" }.to_owned()
+}
+
+#[test]
+fn ordering_chunks_by_locality() {
+    let ordering = LocalityOrdering::new(|key| key.split('/').next().unwrap().to_owned());
+    let chunks = [
+        (0, "us/0"),
+        (1, "eu/1"),
+        (2, "us/2"),
+        (3, "asia/3"),
+        (4, "eu/4"),
+        (5, "us/5"),
+    ];
+    let chunks = chunks
+        .into_iter()
+        .map(|(chunk_id, key)| (chunk_id, key.to_owned()));
+    assert_eq!(ordering.order(chunks), [0, 2, 5, 1, 4, 3]);
+}
+
+#[tokio::test]
+async fn grouping_chunk_fetches_by_locality() {
+    let pool = ConnectionPool::test_pool().await;
+    let mut expected_status = mock_recovery_status();
+    expected_status.storage_logs_chunks_processed = vec![true; 6];
+    let (object_store, client, _) =
+        prepare_clients_with_chunk_sizes(&expected_status, &[5; 6]).await;
+    let fetched_localities = Arc::new(Mutex::new(vec![]));
+    let object_store = ObjectStoreWithErrors::new(object_store, {
+        let fetched_localities = fetched_localities.clone();
+        move |key| {
+            if key.contains("storage_logs") {
+                fetched_localities
+                    .lock()
+                    .unwrap()
+                    .push(mock_chunk_locality(key));
+            }
+            Ok(())
+        }
+    });
+
+    let config = SnapshotsApplierConfig {
+        chunk_order: StorageLogsChunkOrder::Locality(LocalityOrdering::new(mock_chunk_locality)),
+        throttling: ThrottlingConfig {
+            max_concurrency: Some(1),
+            ..ThrottlingConfig::default()
+        },
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let fetched_localities = fetched_localities.lock().unwrap().clone();
+    assert_eq!(
+        fetched_localities,
+        ["even", "even", "even", "odd", "odd", "odd"]
+    );
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn recovering_chunks_compressed_with_dictionary(exceed_size_limit: bool) {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, mut client, all_snapshot_storage_logs) =
+        prepare_clients(&expected_status).await;
+    let bucket = SnapshotStorageLogsChunk::BUCKET;
+    let chunk_keys: Vec<_> = (0..2)
+        .map(|chunk_id| {
+            SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+                l1_batch_number: expected_status.l1_batch_number,
+                chunk_id,
+            })
+        })
+        .collect();
+    let mut encoded_chunks = vec![];
+    for key in &chunk_keys {
+        let bytes = object_store.get_raw(bucket, key).await.unwrap();
+        let chunk = SnapshotStorageLogsChunk::deserialize(bytes).unwrap();
+        encoded_chunks.push(zksync_protobuf::encode(&chunk));
+    }
+
+    // Any content can be used as a raw zstd dictionary.
+    let dictionary = encoded_chunks[0].clone();
+    let dictionary_key = "storage_logs_dictionary.zstd";
+    object_store
+        .put_raw(bucket, dictionary_key, dictionary.clone())
+        .await
+        .unwrap();
+    let mut compressor = zstd::bulk::Compressor::with_dictionary(3, &dictionary).unwrap();
+    for (key, encoded_chunk) in chunk_keys.iter().zip(&encoded_chunks) {
+        let compressed_chunk = compressor.compress(encoded_chunk).unwrap();
+        object_store
+            .put_raw(bucket, key, compressed_chunk)
+            .await
+            .unwrap();
+    }
+    let header = client.fetch_newest_snapshot_response.as_mut().unwrap();
+    header.storage_logs_compression_dictionary = Some(dictionary_key.to_owned());
+
+    let mut config = SnapshotsApplierConfig::for_tests();
+    if exceed_size_limit {
+        config.max_decompressed_chunk_size = encoded_chunks[1].len() - 1;
+    } else {
+        config.max_decompressed_chunk_size = encoded_chunks[0].len().max(encoded_chunks[1].len());
+    }
+    let result = config.run(&pool, &client, &object_store).await;
+    if exceed_size_limit {
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("failed decoding storage logs chunk"), "{err}");
+        assert!(err.contains("exceeds"), "{err}");
+        return;
+    }
+    assert_matches!(result.unwrap(), SnapshotsApplierOutcome::Ok);
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status.unwrap(), expected_status);
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+}
+
+#[test_casing(2, [false, true])]
+#[tokio::test]
+async fn synchronizing_enumeration_index_sequence(sequence_is_ahead: bool) {
+    const SEQUENCE: &str = "test_enumeration_index_seq";
+
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .create_sequence_for_tests(SEQUENCE)
+        .await;
+    if sequence_is_ahead {
+        storage
+            .snapshot_recovery_dal()
+            .set_sequence_next_value(SEQUENCE, 100)
+            .await
+            .unwrap();
+    }
+    drop(storage);
+
+    let config = SnapshotsApplierConfig {
+        enumeration_index_sequence: Some(SEQUENCE.to_owned()),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let next_index = storage
+        .snapshot_recovery_dal()
+        .get_sequence_next_value(SEQUENCE)
+        .await
+        .unwrap();
+    // The mock snapshot has enumeration indices 1..=20.
+    let expected_next_index = if sequence_is_ahead { 100 } else { 21 };
+    assert_eq!(next_index, Some(expected_next_index));
+    drop(storage);
+
+    // Synchronizing the sequence again must not change it.
+    let config = SnapshotsApplierConfig {
+        enumeration_index_sequence: Some(SEQUENCE.to_owned()),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let mut storage = pool.access_storage().await.unwrap();
+    let next_index = storage
+        .snapshot_recovery_dal()
+        .get_sequence_next_value(SEQUENCE)
+        .await
+        .unwrap();
+    assert_eq!(next_index, Some(expected_next_index));
+
+    // A missing sequence should be reported as an error.
+    let config = SnapshotsApplierConfig {
+        enumeration_index_sequence: Some("missing_seq".to_owned()),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("`missing_seq` doesn't exist"), "{err}");
+}
+
+#[tokio::test]
+async fn restarting_stale_recovery() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+
+    // Emulate a partial recovery from an older snapshot.
+    let stale_status = SnapshotRecoveryStatus {
+        l1_batch_number: L1BatchNumber(100),
+        miniblock_number: MiniblockNumber(200),
+        storage_logs_chunks_processed: vec![true, false],
+        ..mock_recovery_status()
+    };
+    let stale_logs = random_storage_logs(stale_status.l1_batch_number, 1, 10);
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&stale_status)
+        .await
+        .unwrap();
+    storage
+        .storage_logs_dal()
+        .insert_storage_logs_from_snapshot(stale_status.miniblock_number, &stale_logs)
+        .await
+        .unwrap();
+    drop(storage);
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let config = SnapshotsApplierConfig {
+        max_resume_age: Some(Duration::from_millis(1)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let outcome = config.run(&pool, &client, &object_store).await.unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status.unwrap(), expected_status);
+    let all_storage_logs = storage
+        .storage_logs_dal()
+        .dump_all_storage_logs_for_tests()
+        .await;
+    assert_eq!(all_storage_logs.len(), all_snapshot_storage_logs.len());
+    for db_log in &all_storage_logs {
+        // Stale storage logs must be deleted.
+        assert!(
+            all_snapshot_storage_logs.contains_key(&db_log.hashed_key),
+            "{db_log:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn stale_recovery_is_not_restarted_with_post_recovery_data() {
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, _) = prepare_clients(&expected_status).await;
+
+    let stale_status = SnapshotRecoveryStatus {
+        l1_batch_number: L1BatchNumber(100),
+        miniblock_number: MiniblockNumber(200),
+        storage_logs_chunks_processed: vec![true, false],
+        ..mock_recovery_status()
+    };
+    let mut storage = pool.access_storage().await.unwrap();
+    storage
+        .snapshot_recovery_dal()
+        .insert_initial_recovery_status(&stale_status)
+        .await
+        .unwrap();
+    storage
+        .protocol_versions_dal()
+        .save_protocol_version_with_tx(ProtocolVersion::default())
+        .await;
+    let miniblock = MiniblockHeader {
+        number: stale_status.miniblock_number + 1,
+        timestamp: stale_status.miniblock_timestamp + 1,
+        hash: H256::zero(),
+        l1_tx_count: 0,
+        l2_tx_count: 0,
+        fee_account_address: Address::repeat_byte(1),
+        base_fee_per_gas: 1,
+        batch_fee_input: Default::default(),
+        gas_per_pubdata_limit: 2,
+        base_system_contracts_hashes: Default::default(),
+        protocol_version: Some(ProtocolVersionId::latest()),
+        virtual_blocks: 0,
+    };
+    storage
+        .blocks_dal()
+        .insert_miniblock(&miniblock)
+        .await
+        .unwrap();
+    drop(storage);
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let config = SnapshotsApplierConfig {
+        max_resume_age: Some(Duration::from_millis(1)),
+        ..SnapshotsApplierConfig::for_tests()
+    };
+    let err = config.run(&pool, &client, &object_store).await.unwrap_err();
+    let err = format!("{err:#}");
+    assert!(err.contains("post-recovery data"), "{err}");
+
+    // Recovery data must be retained.
+    let mut storage = pool.access_storage().await.unwrap();
+    let status = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await
+        .unwrap();
+    assert_eq!(status.unwrap(), stale_status);
+}
+
+#[tokio::test]
+async fn replaying_recovery_from_trace() {
+    async fn dump_recovered_state(
+        pool: &ConnectionPool,
+    ) -> (
+        Option<SnapshotRecoveryStatus>,
+        Vec<(H256, StorageValue, MiniblockNumber)>,
+    ) {
+        let mut storage = pool.access_storage().await.unwrap();
+        let status = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
+            .await
+            .unwrap();
+        let mut storage_logs: Vec<_> = storage
+            .storage_logs_dal()
+            .dump_all_storage_logs_for_tests()
+            .await
+            .into_iter()
+            .map(|log| (log.hashed_key, log.value, log.miniblock_number))
+            .collect();
+        storage_logs.sort_unstable();
+        (status, storage_logs)
+    }
+
+    let pool = ConnectionPool::test_pool().await;
+    let expected_status = mock_recovery_status();
+    let (object_store, client, all_snapshot_storage_logs) = prepare_clients(&expected_status).await;
+    let recorder = RecoveryTraceRecorder::new(client, object_store);
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&pool, &recorder, &recorder)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let recorded_state = dump_recovered_state(&pool).await;
+    assert_eq!(recorded_state.0.as_ref(), Some(&expected_status));
+    assert_eq!(recorded_state.1.len(), all_snapshot_storage_logs.len());
+
+    let trace = recorder.trace();
+    assert!(trace.main_node.contains_key("fetch_newest_snapshot()"));
+    let chunk_key = SnapshotStorageLogsChunk::encode_key(SnapshotStorageLogsStorageKey {
+        l1_batch_number: expected_status.l1_batch_number,
+        chunk_id: 0,
+    });
+    let chunk_request = format!("get {}/{chunk_key}", SnapshotStorageLogsChunk::BUCKET);
+    assert_matches!(
+        trace.object_store[&chunk_request].as_slice(),
+        [TracedObjectStoreResponse::Object(_)]
+    );
+    let temp_dir = TempDir::new().unwrap();
+    let trace_path = temp_dir.path().join("trace.json");
+    trace.write_to_file(&trace_path).unwrap();
+    let restored_trace = RecoveryTrace::read_from_file(&trace_path).unwrap();
+    assert_eq!(restored_trace, trace);
+
+    // Replay recovery into a fresh DB.
+    let replay_pool = ConnectionPool::test_pool().await;
+    let replayer = RecoveryTraceReplayer::new(restored_trace);
+    let outcome = SnapshotsApplierConfig::for_tests()
+        .run(&replay_pool, &replayer, &replayer)
+        .await
+        .unwrap();
+    assert_matches!(outcome, SnapshotsApplierOutcome::Ok);
+    let replayed_state = dump_recovered_state(&replay_pool).await;
+    assert_eq!(replayed_state, recorded_state);
+
+    // Requests absent from the trace must fail.
+    let err = replayer
+        .get_raw(Bucket::StorageSnapshot, "missing")
+        .await
+        .unwrap_err();
+    assert_matches!(err, ObjectStoreError::Unsupported(_));
+}
@@ -1,8 +1,20 @@
 //! Test utils.
 
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
+    path::Path,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
 
 use async_trait::async_trait;
+use tokio::io::{AsyncRead, ReadBuf};
 use zksync_object_store::{Bucket, ObjectStore, ObjectStoreError, ObjectStoreFactory};
 use zksync_types::{
     api::en::SyncBlock,
@@ -13,17 +25,29 @@ use zksync_types::{
         SnapshotRecoveryStatus, SnapshotStorageLog, SnapshotStorageLogsChunk,
         SnapshotStorageLogsChunkMetadata, SnapshotStorageLogsStorageKey,
     },
+    tokens::TokenInfo,
     AccountTreeId, Bytes, L1BatchNumber, MiniblockNumber, ProtocolVersionId, StorageKey,
     StorageValue, H160, H256,
 };
 use zksync_web3_decl::jsonrpsee::core::ClientError as RpcError;
 
-use crate::SnapshotsApplierMainNodeClient;
+use crate::{
+    state_checksum, BackoffStrategy, DiskStatsProvider, SnapshotsApplierL1Client,
+    SnapshotsApplierMainNodeClient, StreamedObject, StreamingObjectSource,
+};
 
 #[derive(Debug, Default)]
 pub(super) struct MockMainNodeClient {
     pub fetch_l2_block_responses: HashMap<MiniblockNumber, SyncBlock>,
     pub fetch_newest_snapshot_response: Option<SnapshotHeader>,
+    /// Number of initial `fetch_newest_snapshot()` calls returning `None` regardless of the configured response.
+    pub snapshot_polls_before_response: AtomicUsize,
+    pub fetch_tokens_responses: HashMap<MiniblockNumber, Vec<TokenInfo>>,
+    pub storage_values: HashMap<(StorageKey, MiniblockNumber), StorageValue>,
+    /// Shared with the test, so that root hashes can be changed during recovery.
+    pub l1_batch_root_hashes: Arc<Mutex<HashMap<L1BatchNumber, H256>>>,
+    /// Number of initial `fetch_l1_batch_root_hash()` calls failing with a transient error.
+    pub root_hash_failures: AtomicUsize,
 }
 
 #[async_trait]
@@ -33,8 +57,73 @@ impl SnapshotsApplierMainNodeClient for MockMainNodeClient {
     }
 
     async fn fetch_newest_snapshot(&self) -> Result<Option<SnapshotHeader>, RpcError> {
+        let decrement_result = self.snapshot_polls_before_response.fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |count| count.checked_sub(1),
+        );
+        if decrement_result.is_ok() {
+            return Ok(None);
+        }
         Ok(self.fetch_newest_snapshot_response.clone())
     }
+
+    async fn fetch_tokens(
+        &self,
+        at_miniblock: MiniblockNumber,
+    ) -> Result<Vec<TokenInfo>, RpcError> {
+        Ok(self
+            .fetch_tokens_responses
+            .get(&at_miniblock)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn fetch_storage_value_at(
+        &self,
+        key: &StorageKey,
+        miniblock_number: MiniblockNumber,
+    ) -> Result<StorageValue, RpcError> {
+        let value = self.storage_values.get(&(*key, miniblock_number));
+        Ok(value.copied().unwrap_or_default())
+    }
+
+    async fn fetch_l1_batch_root_hash(
+        &self,
+        number: L1BatchNumber,
+    ) -> Result<Option<H256>, RpcError> {
+        let decrement_result =
+            self.root_hash_failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                    count.checked_sub(1)
+                });
+        if decrement_result.is_ok() {
+            return Err(RpcError::RequestTimeout);
+        }
+        let root_hashes = self.l1_batch_root_hashes.lock().unwrap();
+        Ok(root_hashes.get(&number).copied())
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct MockL1Client {
+    pub l1_batch_commitments: HashMap<L1BatchNumber, H256>,
+    /// L1 batches reported as not finalized. All other batches are reported as finalized.
+    pub non_finalized_l1_batches: HashSet<L1BatchNumber>,
+}
+
+#[async_trait]
+impl SnapshotsApplierL1Client for MockL1Client {
+    async fn fetch_l1_batch_commitment(
+        &self,
+        number: L1BatchNumber,
+    ) -> anyhow::Result<Option<H256>> {
+        Ok(self.l1_batch_commitments.get(&number).copied())
+    }
+
+    async fn is_l1_batch_finalized(&self, number: L1BatchNumber) -> anyhow::Result<bool> {
+        Ok(!self.non_finalized_l1_batches.contains(&number))
+    }
 }
 
 type ValidateFn = dyn Fn(&str) -> Result<(), ObjectStoreError> + Send + Sync;
@@ -82,21 +171,271 @@ impl ObjectStore for ObjectStoreWithErrors {
         unreachable!("Should not be used in snapshot applier")
     }
 
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        self.inner.get_size_raw(bucket, key).await
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        self.inner.storage_prefix_raw(bucket)
+    }
+}
+
+type DelayFn = dyn Fn(&str) -> Duration + Send + Sync;
+
+/// Object store delaying responses for each key as specified by the delay function.
+pub(super) struct ObjectStoreWithDelays {
+    inner: Arc<dyn ObjectStore>,
+    delay_fn: Box<DelayFn>,
+}
+
+impl fmt::Debug for ObjectStoreWithDelays {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.as_ref().fmt(formatter)
+    }
+}
+
+impl ObjectStoreWithDelays {
+    pub fn new(
+        inner: Arc<dyn ObjectStore>,
+        delay_fn: impl Fn(&str) -> Duration + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            delay_fn: Box::new(delay_fn),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ObjectStoreWithDelays {
+    async fn get_raw(&self, bucket: Bucket, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        tokio::time::sleep((self.delay_fn)(key)).await;
+        self.inner.get_raw(bucket, key).await
+    }
+
+    async fn put_raw(
+        &self,
+        _bucket: Bucket,
+        _key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        unreachable!("Should not be used in snapshot applier")
+    }
+
+    async fn remove_raw(&self, _bucket: Bucket, _key: &str) -> Result<(), ObjectStoreError> {
+        unreachable!("Should not be used in snapshot applier")
+    }
+
+    async fn get_size_raw(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        self.inner.get_size_raw(bucket, key).await
+    }
+
     fn storage_prefix_raw(&self, bucket: Bucket) -> String {
         self.inner.storage_prefix_raw(bucket)
     }
 }
 
+#[cfg(feature = "ipfs")]
+#[derive(Debug, Default)]
+pub(super) struct MockIpfsGateway {
+    pub contents: HashMap<String, Vec<u8>>,
+}
+
+#[cfg(feature = "ipfs")]
+#[async_trait]
+impl crate::IpfsGateway for MockIpfsGateway {
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        self.contents.get(cid).cloned().ok_or_else(|| {
+            let err = format!("unknown CID: {cid}");
+            ObjectStoreError::KeyNotFound(err.into())
+        })
+    }
+}
+
+/// ClickHouse client recording all inserted batches. Like ClickHouse, ignores batches with a repeated deduplication token.
+#[cfg(feature = "clickhouse")]
+#[derive(Debug, Default)]
+pub(super) struct MockClickHouseClient {
+    batches: Mutex<Vec<(String, Vec<crate::ClickHouseStorageLogRow>)>>,
+    dedup_tokens: Mutex<HashSet<String>>,
+}
+
+#[cfg(feature = "clickhouse")]
+impl MockClickHouseClient {
+    pub fn batches(&self) -> Vec<(String, Vec<crate::ClickHouseStorageLogRow>)> {
+        self.batches.lock().unwrap().clone()
+    }
+}
+
+#[cfg(feature = "clickhouse")]
+#[async_trait]
+impl crate::ClickHouseClient for MockClickHouseClient {
+    async fn insert_rows(
+        &self,
+        table: &str,
+        dedup_token: &str,
+        rows: &[crate::ClickHouseStorageLogRow],
+    ) -> anyhow::Result<()> {
+        if !self
+            .dedup_tokens
+            .lock()
+            .unwrap()
+            .insert(dedup_token.to_owned())
+        {
+            return Ok(());
+        }
+        let batch = (table.to_owned(), rows.to_vec());
+        self.batches.lock().unwrap().push(batch);
+        Ok(())
+    }
+}
+
+/// Deterministic backoff strategy recording all returned delays. Clones share recorded delays.
+#[derive(Debug, Clone, Default)]
+pub(super) struct RecordingBackoff {
+    delays: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl RecordingBackoff {
+    pub fn delays(&self) -> Vec<Duration> {
+        self.delays.lock().unwrap().clone()
+    }
+}
+
+impl BackoffStrategy for RecordingBackoff {
+    fn retry_delay(&self, retry_number: usize) -> Duration {
+        let delay = Duration::from_millis(10 * retry_number as u64);
+        self.delays.lock().unwrap().push(delay);
+        delay
+    }
+}
+
+/// Object store tracking the maximum number of concurrent requests per bucket. Each request takes 10ms
+/// and returns an empty object.
+#[derive(Debug, Default)]
+pub(super) struct ConcurrencyTrackingStore {
+    // Current and maximum number of concurrent requests per bucket
+    requests: Mutex<HashMap<Bucket, (usize, usize)>>,
+}
+
+impl ConcurrencyTrackingStore {
+    pub fn max_concurrency(&self, bucket: Bucket) -> usize {
+        let requests = self.requests.lock().unwrap();
+        requests.get(&bucket).map_or(0, |&(_, max)| max)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ConcurrencyTrackingStore {
+    async fn get_raw(&self, bucket: Bucket, _key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        {
+            let mut requests = self.requests.lock().unwrap();
+            let (current, max) = requests.entry(bucket).or_default();
+            *current += 1;
+            *max = (*max).max(*current);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        self.requests.lock().unwrap().get_mut(&bucket).unwrap().0 -= 1;
+        Ok(vec![])
+    }
+
+    async fn put_raw(
+        &self,
+        _bucket: Bucket,
+        _key: &str,
+        _value: Vec<u8>,
+    ) -> Result<(), ObjectStoreError> {
+        unreachable!("Should not be used in snapshot applier")
+    }
+
+    async fn remove_raw(&self, _bucket: Bucket, _key: &str) -> Result<(), ObjectStoreError> {
+        unreachable!("Should not be used in snapshot applier")
+    }
+
+    fn storage_prefix_raw(&self, bucket: Bucket) -> String {
+        bucket.to_string()
+    }
+}
+
+/// Disk stats provider reporting a fixed amount of available space.
+#[derive(Debug)]
+pub(super) struct FixedDiskStats(pub u64);
+
+impl DiskStatsProvider for FixedDiskStats {
+    fn available_space(&self, _path: &Path) -> io::Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Streaming object source with objects keyed by `{bucket}/{key}` and optional CRC32C checksums.
+#[derive(Debug, Default)]
+pub(super) struct MockStreamingSource {
+    pub objects: HashMap<String, (Vec<u8>, Option<u32>)>,
+    /// Number of non-empty reads from streamed objects.
+    pub read_count: Arc<AtomicUsize>,
+}
+
+/// Reader counting non-empty reads.
+struct CountingReader<R> {
+    inner: R,
+    read_count: Arc<AtomicUsize>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled_len = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        if buf.filled().len() > filled_len {
+            this.read_count.fetch_add(1, Ordering::Relaxed);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[async_trait]
+impl StreamingObjectSource for MockStreamingSource {
+    async fn stream(&self, bucket: Bucket, key: &str) -> Result<StreamedObject, ObjectStoreError> {
+        let object_key = format!("{bucket}/{key}");
+        let (bytes, crc32c) = self.objects.get(&object_key).cloned().ok_or_else(|| {
+            let err = format!("missing object `{object_key}`");
+            ObjectStoreError::KeyNotFound(err.into())
+        })?;
+        Ok(StreamedObject {
+            crc32c,
+            size: Some(bytes.len() as u64),
+            reader: Box::new(CountingReader {
+                inner: std::io::Cursor::new(bytes),
+                read_count: self.read_count.clone(),
+            }),
+        })
+    }
+
+    async fn get_size(&self, bucket: Bucket, key: &str) -> Result<u64, ObjectStoreError> {
+        let object_key = format!("{bucket}/{key}");
+        let (bytes, _) = self.objects.get(&object_key).ok_or_else(|| {
+            let err = format!("missing object `{object_key}`");
+            ObjectStoreError::KeyNotFound(err.into())
+        })?;
+        Ok(bytes.len() as u64)
+    }
+}
+
 fn miniblock_metadata(
     number: MiniblockNumber,
     l1_batch_number: L1BatchNumber,
+    timestamp: u64,
     hash: H256,
 ) -> SyncBlock {
     SyncBlock {
         number,
         l1_batch_number,
         last_in_batch: true,
-        timestamp: 0,
+        timestamp,
         l1_gas_price: 0,
         l2_fair_gas_price: 0,
         fair_pubdata_price: None,
@@ -109,11 +448,15 @@ fn miniblock_metadata(
     }
 }
 
-fn l1_block_metadata(l1_batch_number: L1BatchNumber, root_hash: H256) -> L1BatchWithMetadata {
+fn l1_block_metadata(
+    l1_batch_number: L1BatchNumber,
+    timestamp: u64,
+    root_hash: H256,
+) -> L1BatchWithMetadata {
     L1BatchWithMetadata {
         header: L1BatchHeader::new(
             l1_batch_number,
-            0,
+            timestamp,
             Default::default(),
             ProtocolVersionId::default(),
         ),
@@ -141,20 +484,26 @@ fn l1_block_metadata(l1_batch_number: L1BatchNumber, root_hash: H256) -> L1Batch
     }
 }
 
-fn random_storage_logs(
+/// Number of distinct accounts in storage logs produced by [`random_storage_logs()`].
+pub(super) const MOCK_ACCOUNT_COUNT: u64 = 3;
+
+/// Returns the deterministic account for a storage log with the specified enumeration index.
+pub(super) fn mock_account(enumeration_index: u64) -> AccountTreeId {
+    let byte = (enumeration_index % MOCK_ACCOUNT_COUNT) as u8 + 0x10;
+    AccountTreeId::new(H160::repeat_byte(byte))
+}
+
+pub(super) fn random_storage_logs(
     l1_batch_number: L1BatchNumber,
-    chunk_id: u64,
-    logs_per_chunk: u64,
+    first_enumeration_index: u64,
+    log_count: u64,
 ) -> Vec<SnapshotStorageLog> {
-    (0..logs_per_chunk)
+    (0..log_count)
         .map(|x| SnapshotStorageLog {
-            key: StorageKey::new(
-                AccountTreeId::from_fixed_bytes(H160::random().to_fixed_bytes()),
-                H256::random(),
-            ),
+            key: StorageKey::new(mock_account(first_enumeration_index + x), H256::random()),
             value: StorageValue::random(),
             l1_batch_number_of_initial_write: l1_batch_number,
-            enumeration_index: x + chunk_id * logs_per_chunk,
+            enumeration_index: first_enumeration_index + x,
         })
         .collect()
 }
@@ -163,15 +512,26 @@ pub(super) fn mock_recovery_status() -> SnapshotRecoveryStatus {
     SnapshotRecoveryStatus {
         l1_batch_number: L1BatchNumber(123),
         l1_batch_root_hash: H256::random(),
-        l1_batch_timestamp: 0,
+        l1_batch_timestamp: 100,
         miniblock_number: MiniblockNumber(321),
         miniblock_hash: H256::random(),
-        miniblock_timestamp: 0,
+        miniblock_timestamp: 105,
         protocol_version: ProtocolVersionId::default(),
         storage_logs_chunks_processed: vec![true, true],
     }
 }
 
+/// Computes the expected [`state_checksum()`] of the state recovered from `storage_logs`.
+pub(super) fn expected_state_checksum(storage_logs: &HashMap<H256, SnapshotStorageLog>) -> H256 {
+    let mut storage_logs: Vec<_> = storage_logs.iter().collect();
+    storage_logs.sort_unstable_by_key(|(hashed_key, _)| **hashed_key);
+    let storage_logs: Vec<_> = storage_logs
+        .into_iter()
+        .map(|(_, log)| log.clone())
+        .collect();
+    state_checksum(&storage_logs, 1)
+}
+
 pub(super) async fn prepare_clients(
     status: &SnapshotRecoveryStatus,
 ) -> (
@@ -179,6 +539,23 @@ pub(super) async fn prepare_clients(
     MockMainNodeClient,
     HashMap<H256, SnapshotStorageLog>,
 ) {
+    let chunk_sizes = vec![10; status.storage_logs_chunks_processed.len()];
+    prepare_clients_with_chunk_sizes(status, &chunk_sizes).await
+}
+
+/// Same as [`prepare_clients()`], but with the specified number of storage logs in each chunk.
+pub(super) async fn prepare_clients_with_chunk_sizes(
+    status: &SnapshotRecoveryStatus,
+    chunk_sizes: &[u64],
+) -> (
+    Arc<dyn ObjectStore>,
+    MockMainNodeClient,
+    HashMap<H256, SnapshotStorageLog>,
+) {
+    assert_eq!(
+        chunk_sizes.len(),
+        status.storage_logs_chunks_processed.len()
+    );
     let object_store_factory = ObjectStoreFactory::mock();
     let object_store = object_store_factory.create_store().await;
     let mut client = MockMainNodeClient::default();
@@ -194,10 +571,17 @@ pub(super) async fn prepare_clients(
         .unwrap();
 
     let mut all_snapshot_storage_logs = HashMap::<H256, SnapshotStorageLog>::new();
-    for chunk_id in 0..status.storage_logs_chunks_processed.len() as u64 {
+    let mut next_enumeration_index = 1;
+    for (chunk_id, &chunk_size) in (0..).zip(chunk_sizes) {
         let chunk_storage_logs = SnapshotStorageLogsChunk {
-            storage_logs: random_storage_logs(status.l1_batch_number, chunk_id, 10),
+            storage_logs: random_storage_logs(
+                status.l1_batch_number,
+                next_enumeration_index,
+                chunk_size,
+            ),
+            proofs: vec![],
         };
+        next_enumeration_index += chunk_size;
         let chunk_key = SnapshotStorageLogsStorageKey {
             l1_batch_number: status.l1_batch_number,
             chunk_id,
@@ -220,19 +604,20 @@ pub(super) async fn prepare_clients(
         miniblock_number: status.miniblock_number,
         last_l1_batch_with_metadata: l1_block_metadata(
             status.l1_batch_number,
+            status.l1_batch_timestamp,
             status.l1_batch_root_hash,
         ),
-        storage_logs_chunks: vec![
-            SnapshotStorageLogsChunkMetadata {
-                chunk_id: 0,
-                filepath: "file0".to_string(),
-            },
-            SnapshotStorageLogsChunkMetadata {
-                chunk_id: 1,
-                filepath: "file1".to_string(),
-            },
-        ],
+        storage_logs_chunks: (0..chunk_sizes.len() as u64)
+            .map(|chunk_id| {
+                SnapshotStorageLogsChunkMetadata::new(chunk_id, format!("file{chunk_id}"))
+            })
+            .collect(),
         factory_deps_filepath: "some_filepath".to_string(),
+        includes_tokens: false,
+        factory_deps_shards: vec![],
+        storage_logs_path_template: None,
+        format_version: 0,
+        storage_logs_compression_dictionary: None,
     };
     client.fetch_newest_snapshot_response = Some(snapshot_header);
     client.fetch_l2_block_responses.insert(
@@ -240,6 +625,7 @@ pub(super) async fn prepare_clients(
         miniblock_metadata(
             status.miniblock_number,
             status.l1_batch_number,
+            status.miniblock_timestamp,
             status.miniblock_hash,
         ),
     );
@@ -0,0 +1,92 @@
+//! Handling of throttling responses from object store backends.
+
+use std::{
+    error,
+    time::{Duration, SystemTime},
+};
+
+use zksync_object_store::ObjectStoreError;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Error returned by object store backends if a request is throttled (e.g., on an HTTP 429 response with
+/// a `Retry-After` header). Should be wrapped into [`ObjectStoreError::Other`]. Unless disabled with
+/// [`SnapshotsApplierConfig::respect_retry_after`], the applier retries throttled requests after the specified
+/// delay instead of using its own backoff.
+///
+/// [`SnapshotsApplierConfig::respect_retry_after`]: crate::SnapshotsApplierConfig::respect_retry_after
+#[derive(Debug, thiserror::Error)]
+#[error("request is throttled by the object store backend; retry after {retry_after:?}")]
+pub struct ObjectStoreThrottled {
+    pub retry_after: Duration,
+}
+
+/// Parses the value of a `Retry-After` HTTP header, which is either a non-negative number of seconds,
+/// or an HTTP date in the IMF-fixdate format (e.g., `Sun, 06 Nov 1994 08:49:37 GMT`). Dates in the past
+/// are returned as a zero delay. Returns `None` if the value cannot be parsed.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let value = value.strip_suffix(" GMT")?;
+    let (_weekday, value) = value.split_once(", ")?;
+    let mut parts = value.split(' ');
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|&name| name == month)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time_parts = parts.next()?.split(':');
+    if parts.next().is_some() {
+        return None;
+    }
+    let hour: u64 = time_parts.next()?.parse().ok()?;
+    let minute: u64 = time_parts.next()?.parse().ok()?;
+    let second: u64 = time_parts.next()?.parse().ok()?;
+    if time_parts.next().is_some() {
+        return None;
+    }
+    // Leap seconds are allowed by the format.
+    if !(1..=31).contains(&day) || hour >= 24 || minute >= 60 || second > 60 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Returns the number of days since the Unix epoch for the specified date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Shift the year start to March, so that the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Extracts the delay requested by the backend from an object store error, if the error was caused by throttling.
+pub(crate) fn retry_after(err: &ObjectStoreError) -> Option<Duration> {
+    let ObjectStoreError::Other(err) = err else {
+        return None;
+    };
+    let mut source: Option<&(dyn error::Error + 'static)> = Some(err.as_ref());
+    while let Some(err) = source {
+        if let Some(throttled) = err.downcast_ref::<ObjectStoreThrottled>() {
+            return Some(throttled.retry_after);
+        }
+        source = err.source();
+    }
+    None
+}
@@ -0,0 +1,49 @@
+//! Watchdog detecting stalled snapshot recovery.
+
+use std::{sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+/// Error returned if no storage log chunks were processed during [`SnapshotsApplierConfig::stall_timeout`].
+/// Recovery is resumable after this error; the snapshot applier retries it like other transient errors.
+///
+/// [`SnapshotsApplierConfig::stall_timeout`]: crate::SnapshotsApplierConfig::stall_timeout
+#[derive(Debug, thiserror::Error)]
+#[error("snapshot recovery stalled: no storage log chunks were processed in {stall_timeout:?}")]
+pub struct SnapshotRecoveryStalled {
+    pub stall_timeout: Duration,
+}
+
+/// Watchdog tracking the last time a storage logs chunk was processed.
+#[derive(Debug)]
+pub(crate) struct ProgressWatchdog {
+    stall_timeout: Duration,
+    last_progress: Mutex<Instant>,
+}
+
+impl ProgressWatchdog {
+    pub fn new(stall_timeout: Duration) -> Self {
+        Self {
+            stall_timeout,
+            last_progress: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn report_progress(&self) {
+        *self.last_progress.lock().unwrap() = Instant::now();
+    }
+
+    /// Resolves once no progress is reported for the stall timeout. Never resolves if progress is reported
+    /// often enough.
+    pub async fn wait_for_stall(&self) -> SnapshotRecoveryStalled {
+        loop {
+            let deadline = *self.last_progress.lock().unwrap() + self.stall_timeout;
+            if Instant::now() >= deadline {
+                return SnapshotRecoveryStalled {
+                    stall_timeout: self.stall_timeout,
+                };
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
@@ -6,13 +6,13 @@ use anyhow::Context as _;
 use tokio::sync::watch;
 use zksync_dal::{storage_logs_dal::StorageRecoveryLogEntry, StorageProcessor};
 use zksync_types::{
-    snapshots::{uniform_hashed_keys_chunk, SnapshotRecoveryStatus},
-    L1BatchNumber, MiniblockNumber, H256,
+    snapshots::{uniform_hashed_keys_chunk, SnapshotRecoveryStatus, SnapshotStorageLog},
+    L1BatchNumber, MiniblockNumber, StorageValue, H256,
 };
 
 use super::{
     metrics::{ChunkRecoveryStage, RecoveryStage, RECOVERY_METRICS},
-    RocksdbStorage, RocksdbSyncError, StateValue,
+    RocksbStorageBuilder, RocksdbStorage, RocksdbSyncError, StateValue,
 };
 
 #[derive(Debug)]
@@ -252,3 +252,37 @@ impl RocksdbStorage {
         Ok(())
     }
 }
+
+impl RocksbStorageBuilder {
+    /// Inserts storage logs from an application-level snapshot directly into the storage. This allows
+    /// bootstrapping the storage together with the Postgres snapshot recovery.
+    ///
+    /// The storage is not marked as recovered by this method; this happens during [`Self::synchronize()`],
+    /// which skips key chunks that are already present in the storage.
+    ///
+    /// # Errors
+    ///
+    /// Propagates RocksDB I/O errors.
+    pub async fn insert_snapshot_storage_logs(
+        &mut self,
+        storage_logs: &[SnapshotStorageLog],
+    ) -> anyhow::Result<()> {
+        self.0.pending_patch.state = storage_logs
+            .iter()
+            .map(|log| (log.key.hashed_key(), (log.value, log.enumeration_index)))
+            .collect();
+        self.0
+            .save(None)
+            .await
+            .context("failed saving snapshot storage logs")
+    }
+
+    /// Reads the value and enumeration index for the specified hashed key. Unlike [`ReadStorage`] methods,
+    /// this doesn't require the storage to be synchronized with Postgres.
+    ///
+    /// [`ReadStorage`]: crate::ReadStorage
+    pub async fn read_state_entry(&self, hashed_key: H256) -> Option<(StorageValue, Option<u64>)> {
+        let state_value = self.0.read_state_value_async(hashed_key).await?;
+        Some((state_value.value, state_value.enum_index))
+    }
+}
@@ -6,7 +6,7 @@ use assert_matches::assert_matches;
 use tempfile::TempDir;
 use test_casing::test_casing;
 use zksync_dal::ConnectionPool;
-use zksync_types::{MiniblockNumber, StorageLog};
+use zksync_types::{snapshots::SnapshotStorageLog, MiniblockNumber, StorageLog};
 
 use super::*;
 use crate::test_utils::{
@@ -491,3 +491,30 @@ async fn recovery_fault_tolerance() {
         assert!(!storage.is_write_initial(&log.key));
     }
 }
+
+#[tokio::test]
+async fn inserting_snapshot_storage_logs() {
+    let dir = TempDir::new().expect("cannot create temporary dir for state keeper");
+    let mut storage = RocksdbStorage::builder(dir.path()).await.unwrap();
+    let storage_logs: Vec<_> = gen_storage_logs(0..20)
+        .into_iter()
+        .enumerate()
+        .map(|(i, log)| SnapshotStorageLog {
+            key: log.key,
+            value: log.value,
+            l1_batch_number_of_initial_write: L1BatchNumber(1),
+            enumeration_index: i as u64 + 1,
+        })
+        .collect();
+    storage
+        .insert_snapshot_storage_logs(&storage_logs)
+        .await
+        .unwrap();
+
+    for log in &storage_logs {
+        let entry = storage.read_state_entry(log.key.hashed_key()).await;
+        assert_eq!(entry, Some((log.value, Some(log.enumeration_index))));
+    }
+    // The storage must not be marked as recovered.
+    assert_eq!(storage.l1_batch_number().await, None);
+}
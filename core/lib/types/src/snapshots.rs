@@ -49,14 +49,60 @@ pub struct SnapshotHeader {
     pub storage_logs_chunks: Vec<SnapshotStorageLogsChunkMetadata>,
     pub factory_deps_filepath: String,
     pub last_l1_batch_with_metadata: L1BatchWithMetadata,
+    /// Whether token metadata is available for the snapshot miniblock via the main node API.
+    #[serde(default)]
+    pub includes_tokens: bool,
+    /// Object store keys of factory dependency shards. If empty, factory dependencies are stored
+    /// in a single object with the standard key.
+    #[serde(default)]
+    pub factory_deps_shards: Vec<String>,
+    /// Template for object store keys of storage log chunks (e.g., `snapshots/{l1_batch}/chunk_{chunk_id:06}.bin`).
+    /// If set, keys are computed from the template, and `filepath`s in `storage_logs_chunks` are ignored.
+    #[serde(default)]
+    pub storage_logs_path_template: Option<String>,
+    /// Version of the data format of snapshot objects (storage logs chunks and factory dependencies).
+    /// Snapshots produced before formats were versioned have version 0.
+    #[serde(default)]
+    pub format_version: u32,
+    /// Object store key of the zstd dictionary used to compress storage log chunks. If set, chunks are
+    /// Protobuf messages compressed with zstd using this dictionary (instead of gzip), which considerably
+    /// reduces the size of small chunks. Supported for data format version 0 and
+    /// [`SnapshotStorageLogsChunk::DELTA_ENCODED_INDICES_FORMAT_VERSION`].
+    #[serde(default)]
+    pub storage_logs_compression_dictionary: Option<String>,
 }
 
+/// Metadata of a storage logs chunk included into a [`SnapshotHeader`].
+///
+/// The metadata schema is versioned, so that snapshots produced by different exporter versions can be parsed:
+/// fields missing in older schema versions are defaulted, and unknown fields added in newer versions are ignored.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SnapshotStorageLogsChunkMetadata {
+    /// Version of the metadata schema. Metadata without a version has version 0.
+    #[serde(default)]
+    pub version: u16,
     pub chunk_id: u64,
     // can be either be a file available under HTTP(s) or local filesystem path
     pub filepath: String,
+    /// Number of storage logs in the chunk, if known. Added in version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_log_count: Option<u64>,
+}
+
+impl SnapshotStorageLogsChunkMetadata {
+    /// Current version of the metadata schema.
+    pub const CURRENT_VERSION: u16 = 1;
+
+    /// Creates metadata of the current version.
+    pub fn new(chunk_id: u64, filepath: impl Into<String>) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            chunk_id,
+            filepath: filepath.into(),
+            storage_log_count: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -69,6 +115,19 @@ pub struct SnapshotStorageLogsStorageKey {
 #[derive(Debug, Clone, PartialEq)]
 pub struct SnapshotStorageLogsChunk {
     pub storage_logs: Vec<SnapshotStorageLog>,
+    /// Optional Merkle proofs for a subset of storage logs in the chunk, allowing to spot-check chunk contents
+    /// against the root hash of the snapshot L1 batch.
+    pub proofs: Vec<SnapshotStorageLogProof>,
+}
+
+/// Merkle proof of a storage log in a [`SnapshotStorageLogsChunk`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotStorageLogProof {
+    /// Index of the proven log in [`SnapshotStorageLogsChunk::storage_logs`].
+    pub storage_log_index: u64,
+    /// Merkle path for the log ordered starting from the leaf level. Hashes for empty subtrees
+    /// at the beginning of the path may be omitted.
+    pub merkle_path: Vec<H256>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -165,6 +224,39 @@ impl ProtoFmt for SnapshotStorageLog {
     }
 }
 
+impl SnapshotStorageLogsChunk {
+    /// Data format version (see [`SnapshotHeader::format_version`]) in which `enumeration_index` of each storage log
+    /// in a chunk is encoded as a delta (modulo 2^64) from the enumeration index of the previous log in the chunk
+    /// (or from 0 for the first log). Apart from that, the format is identical to version 0.
+    pub const DELTA_ENCODED_INDICES_FORMAT_VERSION: u32 = 1;
+
+    /// Builds a Protobuf message for this chunk with delta-encoded enumeration indices. Unlike
+    /// [`ProtoFmt::build()`], which uses absolute indices, the output must be decoded according to
+    /// [`Self::DELTA_ENCODED_INDICES_FORMAT_VERSION`].
+    pub fn build_with_delta_encoded_indices(&self) -> crate::proto::SnapshotStorageLogsChunk {
+        let mut proto = self.build();
+        let mut prev_index = 0_u64;
+        for storage_log in &mut proto.storage_logs {
+            if let Some(index) = &mut storage_log.enumeration_index {
+                let absolute_index = *index;
+                *index = absolute_index.wrapping_sub(prev_index);
+                prev_index = absolute_index;
+            }
+        }
+        proto
+    }
+
+    /// Restores absolute enumeration indices of storage logs in a chunk decoded from
+    /// the [`Self::DELTA_ENCODED_INDICES_FORMAT_VERSION`] format.
+    pub fn restore_absolute_indices(&mut self) {
+        let mut prev_index = 0_u64;
+        for storage_log in &mut self.storage_logs {
+            storage_log.enumeration_index = prev_index.wrapping_add(storage_log.enumeration_index);
+            prev_index = storage_log.enumeration_index;
+        }
+    }
+}
+
 impl ProtoFmt for SnapshotStorageLogsChunk {
     type Proto = crate::proto::SnapshotStorageLogsChunk;
 
@@ -176,7 +268,16 @@ impl ProtoFmt for SnapshotStorageLogsChunk {
                     .with_context(|| format!("storage_log[{i}]"))?,
             )
         }
-        Ok(Self { storage_logs })
+        let mut proofs = Vec::with_capacity(r.proofs.len());
+        for (i, proof) in r.proofs.iter().enumerate() {
+            proofs.push(
+                SnapshotStorageLogProof::read(proof).with_context(|| format!("proofs[{i}]"))?,
+            );
+        }
+        Ok(Self {
+            storage_logs,
+            proofs,
+        })
     }
 
     fn build(&self) -> Self::Proto {
@@ -186,6 +287,39 @@ impl ProtoFmt for SnapshotStorageLogsChunk {
                 .iter()
                 .map(SnapshotStorageLog::build)
                 .collect(),
+            proofs: self
+                .proofs
+                .iter()
+                .map(SnapshotStorageLogProof::build)
+                .collect(),
+        }
+    }
+}
+
+impl ProtoFmt for SnapshotStorageLogProof {
+    type Proto = crate::proto::SnapshotStorageLogProof;
+
+    fn read(r: &Self::Proto) -> anyhow::Result<Self> {
+        let mut merkle_path = Vec::with_capacity(r.merkle_path.len());
+        for (i, hash) in r.merkle_path.iter().enumerate() {
+            let hash = <[u8; 32]>::try_from(hash.as_slice())
+                .with_context(|| format!("merkle_path[{i}]"))?;
+            merkle_path.push(hash.into());
+        }
+        Ok(Self {
+            storage_log_index: *required(&r.storage_log_index).context("storage_log_index")?,
+            merkle_path,
+        })
+    }
+
+    fn build(&self) -> Self::Proto {
+        Self::Proto {
+            storage_log_index: Some(self.storage_log_index),
+            merkle_path: self
+                .merkle_path
+                .iter()
+                .map(|hash| hash.as_bytes().into())
+                .collect(),
         }
     }
 }
@@ -213,6 +347,16 @@ impl SnapshotRecoveryStatus {
     }
 }
 
+/// Incremental verification checkpoint of snapshot recovery. Storage log chunks covered by a checkpoint
+/// were verified to be persisted and do not need to be re-verified when recovery is resumed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotRecoveryCheckpoint {
+    /// Flags indicating whether a storage logs chunk is covered by the checkpoint, indexed by the chunk ID.
+    pub verified_chunks: Vec<bool>,
+    /// Total number of storage logs in the covered chunks.
+    pub storage_logs_count: u64,
+}
+
 /// Returns a chunk of `hashed_keys` with 0-based index `chunk_id` among `count`. Chunks do not intersect and jointly cover
 /// the entire `hashed_key` space. If `hashed_key`s are uniformly distributed (which is the case), the returned ranges
 /// are expected to contain the same number of entries.
@@ -253,6 +397,82 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn deserializing_chunk_metadata_of_different_versions() {
+        let old_metadata = r#"{ "chunkId": 3, "filepath": "file3" }"#;
+        let old_metadata: SnapshotStorageLogsChunkMetadata =
+            serde_json::from_str(old_metadata).unwrap();
+        assert_eq!(
+            old_metadata,
+            SnapshotStorageLogsChunkMetadata {
+                version: 0,
+                chunk_id: 3,
+                filepath: "file3".to_owned(),
+                storage_log_count: None,
+            }
+        );
+
+        let newer_metadata = r#"{
+            "version": 2,
+            "chunkId": 3,
+            "filepath": "file3",
+            "storageLogCount": 1000,
+            "compression": "zstd"
+        }"#;
+        let newer_metadata: SnapshotStorageLogsChunkMetadata =
+            serde_json::from_str(newer_metadata).unwrap();
+        assert_eq!(newer_metadata.version, 2);
+        assert_eq!(newer_metadata.chunk_id, 3);
+        assert_eq!(newer_metadata.storage_log_count, Some(1000));
+
+        let metadata = SnapshotStorageLogsChunkMetadata::new(5, "file5");
+        let serialized = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(
+            serialized,
+            serde_json::json!({ "version": 1, "chunkId": 5, "filepath": "file5" })
+        );
+        let deserialized: SnapshotStorageLogsChunkMetadata =
+            serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized, metadata);
+    }
+
+    #[test]
+    fn decoding_chunk_with_delta_encoded_indices() {
+        let storage_logs = [5_u64, 3, 100, 101, u64::MAX, 1]
+            .into_iter()
+            .enumerate()
+            .map(|(i, enumeration_index)| SnapshotStorageLog {
+                key: StorageKey::new(
+                    AccountTreeId::new(zksync_basic_types::Address::repeat_byte(1)),
+                    H256::from_low_u64_be(i as u64),
+                ),
+                value: H256::repeat_byte(0xaa),
+                l1_batch_number_of_initial_write: L1BatchNumber(i as u32),
+                enumeration_index,
+            })
+            .collect();
+        let chunk = SnapshotStorageLogsChunk {
+            storage_logs,
+            proofs: vec![],
+        };
+
+        let absolute_proto = chunk.build();
+        let delta_proto = chunk.build_with_delta_encoded_indices();
+        let deltas: Vec<_> = delta_proto
+            .storage_logs
+            .iter()
+            .map(|log| log.enumeration_index.unwrap())
+            .collect();
+        assert_eq!(deltas[..4], [5, 3_u64.wrapping_sub(5), 97, 1]);
+
+        let decoded_absolute = SnapshotStorageLogsChunk::read(&absolute_proto).unwrap();
+        let mut decoded_delta = SnapshotStorageLogsChunk::read(&delta_proto).unwrap();
+        assert_eq!(decoded_absolute, chunk);
+        assert_ne!(decoded_delta, decoded_absolute);
+        decoded_delta.restore_absolute_indices();
+        assert_eq!(decoded_delta, decoded_absolute);
+    }
+
     #[test]
     fn chunking_is_correct() {
         for chunks_count in (2..10).chain([42, 256, 500, 1_001, 12_345]) {
@@ -71,10 +71,10 @@ impl SnapshotsNamespace {
             .into_iter()
             .enumerate()
             .filter_map(|(chunk_id, filepath)| {
-                Some(SnapshotStorageLogsChunkMetadata {
-                    chunk_id: chunk_id as u64,
-                    filepath: filepath?,
-                })
+                Some(SnapshotStorageLogsChunkMetadata::new(
+                    chunk_id as u64,
+                    filepath?,
+                ))
             })
             .collect();
         let l1_batch_with_metadata = storage_processor
@@ -103,6 +103,11 @@ impl SnapshotsNamespace {
             last_l1_batch_with_metadata: l1_batch_with_metadata,
             storage_logs_chunks: chunks,
             factory_deps_filepath: snapshot_metadata.factory_deps_filepath,
+            includes_tokens: false,
+            factory_deps_shards: vec![],
+            storage_logs_path_template: None,
+            format_version: 0,
+            storage_logs_compression_dictionary: None,
         }))
     }
 }